@@ -0,0 +1,11 @@
+-- wrk -t16 -c400 -d30s -s bench_balance_events.lua http://localhost:8080
+-- Reads pages of 100 events from random offsets, compare runs with BALANCE_EVENT_STORE=rocksdb and BALANCE_EVENT_STORE=segment_log
+
+wrk.method = "GET"
+
+local max_offset = tonumber(os.getenv("MAX_EVENT_ID") or "1000000")
+
+request = function()
+    local offset = math.random(1, max_offset)
+    return wrk.format(nil, "/balance-events?offset=" .. offset .. "&limit=100")
+end
@@ -0,0 +1,50 @@
+#!/usr/bin/env sh
+# Prints how many records per second land in BALANCE_EVENT_TOPIC on the redpanda of compose.infra.yml,
+# then one markdown table row summing up the run: label, records, seconds in which records
+# arrived, average and peak records per second.
+#
+#   ./bench_emitter.sh [label]
+#
+# 1. Build a backlog: run the app with BALANCE_EVENT_EMITTER_JOB_ENABLED=false and
+#    wrk -t16 -c400 -d30s -s bench_deposit.lua http://localhost:8080/balance/deposit
+# 2. Restart it with the emitter enabled and run this script while it catches up.
+#
+# Compare builds, or KAFKA_BALANCE_EVENT_BATCH_NUM_MESSAGES, KAFKA_BALANCE_EVENT_LINGER_MS and
+# BALANCE_EVENT_EMITTER_JOB_POOLING_SIZE settings, by repeating the run with another label.
+# The average only counts the seconds in which records arrived, so the idle seconds after
+# the backlog is drained do not lower it.
+
+label=${1:-run}
+topic=${BALANCE_EVENT_TOPIC:-balance.event}
+samples=${SAMPLES:-30}
+
+high_watermark() {
+    docker exec kafka rpk topic describe "$topic" -p | awk 'NR > 1 { sum += $NF } END { print sum + 0 }'
+}
+
+start=$(high_watermark)
+previous=$start
+busy_seconds=0
+peak=0
+i=0
+while [ "$i" -lt "$samples" ]; do
+    sleep 1
+    current=$(high_watermark)
+    rate=$((current - previous))
+    echo "$(date +%T) $rate records/s"
+    if [ "$rate" -gt 0 ]; then
+        busy_seconds=$((busy_seconds + 1))
+    fi
+    if [ "$rate" -gt "$peak" ]; then
+        peak=$rate
+    fi
+    previous=$current
+    i=$((i + 1))
+done
+
+total=$((previous - start))
+average=0
+if [ "$busy_seconds" -gt 0 ]; then
+    average=$((total / busy_seconds))
+fi
+echo "| $label | $total | $busy_seconds | $average | $peak |"
@@ -0,0 +1,19 @@
+#!/usr/bin/env sh
+# Prints one markdown table row with the throughput and latency percentiles of a deposit load.
+#
+#   ./bench_latency.sh <label> [url]
+#
+# Run it once per build to compare, for example on a build without the dedicated arbiters
+# and on the current one, with the emitter enabled in both.
+
+label=${1:?usage: bench_latency.sh <label> [url]}
+url=${2:-http://localhost:8080/balance/deposit}
+duration=${DURATION:-30s}
+
+wrk --latency -t16 -c400 -d"$duration" -s bench_deposit.lua "$url" | awk -v label="$label" '
+    /Requests\/sec/ { rps = $2 }
+    $1 == "50%" { p50 = $2 }
+    $1 == "90%" { p90 = $2 }
+    $1 == "99%" { p99 = $2 }
+    END { printf "| %s | %s | %s | %s | %s |\n", label, rps, p50, p90, p99 }
+'
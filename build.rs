@@ -0,0 +1,90 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    grpc::generate_services();
+}
+
+/// The gRPC services of `schemas/proto/balance_service.v1.proto`, generated from Rust
+/// definitions: the messages are written by hand like the event message, so no `protoc` is
+/// needed to build.
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    const PACKAGE: &str = "actor_bank.balance.v1";
+    const MESSAGES: &str = "crate::transport::grpc::balance_grpc_message";
+    const CODEC: &str = "tonic_prost::ProstCodec";
+
+    fn method(name: &str, route_name: &str, input_type: &str, output_type: &str) -> Method {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("{MESSAGES}::{input_type}"))
+            .output_type(format!("{MESSAGES}::{output_type}"))
+            .codec_path(CODEC)
+            .build()
+    }
+
+    pub fn generate_services() {
+        let balance_service = Service::builder()
+            .name("BalanceService")
+            .package(PACKAGE)
+            .method(method(
+                "create_balance",
+                "CreateBalance",
+                "CreateBalanceRequest",
+                "CommandReply",
+            ))
+            .method(method(
+                "deposit",
+                "Deposit",
+                "DepositRequest",
+                "CommandReply",
+            ))
+            .method(method(
+                "withdraw",
+                "Withdraw",
+                "WithdrawRequest",
+                "CommandReply",
+            ))
+            .method(method(
+                "transfer",
+                "Transfer",
+                "TransferRequest",
+                "CommandReply",
+            ))
+            .method(method(
+                "get_balance",
+                "GetBalance",
+                "GetBalanceRequest",
+                "BalanceReply",
+            ))
+            .build();
+
+        let subscribe = Method::builder()
+            .name("subscribe")
+            .route_name("Subscribe")
+            .input_type(format!("{MESSAGES}::SubscribeRequest"))
+            .output_type(
+                "crate::infrastructure::event_sink::protobuf_event_encoding::BalanceEventProto",
+            )
+            .codec_path(CODEC)
+            .server_streaming()
+            .build();
+        let balance_event_service = Service::builder()
+            .name("BalanceEventService")
+            .package(PACKAGE)
+            .method(method(
+                "read_events",
+                "ReadEvents",
+                "ReadEventsRequest",
+                "ReadEventsReply",
+            ))
+            .method(subscribe)
+            .build();
+
+        Builder::new()
+            .build_client(false)
+            .compile(&[balance_service, balance_event_service]);
+    }
+}
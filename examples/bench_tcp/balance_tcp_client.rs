@@ -0,0 +1,108 @@
+use std::{
+    collections::HashMap,
+    io,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpStream, ToSocketAddrs, tcp::OwnedReadHalf, tcp::OwnedWriteHalf},
+    sync::oneshot,
+    task::JoinHandle,
+};
+
+use actor_bank::{
+    application::balance::api::ledger_command_api::CommandId,
+    transport::tcp::balance_tcp_frame::{
+        RequestId, TcpCommand, TcpReply, TcpRequest, TcpResponse, encode_frame, read_frame,
+    },
+};
+
+const MAX_REPLY_FRAME_BYTES: usize = 1 << 16;
+
+type PendingReplies = Arc<Mutex<HashMap<RequestId, oneshot::Sender<TcpReply>>>>;
+
+/// Client of the binary protocol. Calls may run concurrently: they are pipelined on the one
+/// connection and each gets its own reply, whatever order the replies come back in.
+pub struct BalanceTcpClient {
+    writer: tokio::sync::Mutex<OwnedWriteHalf>,
+    pending: PendingReplies,
+    next_request_id: AtomicU64,
+    reader_task: JoinHandle<()>,
+}
+
+impl BalanceTcpClient {
+    pub async fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(address).await?;
+        stream.set_nodelay(true)?;
+        let (reader, writer) = stream.into_split();
+        let pending = PendingReplies::default();
+        let reader_task = tokio::spawn(read_replies(reader, pending.clone()));
+        Ok(Self {
+            writer: tokio::sync::Mutex::new(writer),
+            pending,
+            next_request_id: AtomicU64::new(1),
+            reader_task,
+        })
+    }
+
+    pub async fn send(&self, command: TcpCommand) -> io::Result<TcpReply> {
+        self.request(None, command).await
+    }
+
+    /// Applied at most once: the same `command_id` sent again is answered with the stored
+    /// result.
+    pub async fn send_idempotent(
+        &self,
+        command_id: CommandId,
+        command: TcpCommand,
+    ) -> io::Result<TcpReply> {
+        self.request(Some(command_id), command).await
+    }
+
+    async fn request(
+        &self,
+        command_id: Option<CommandId>,
+        command: TcpCommand,
+    ) -> io::Result<TcpReply> {
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(request_id, sender);
+        let frame = encode_frame(&TcpRequest {
+            request_id,
+            command_id,
+            command,
+        });
+        if let Err(io_error) = self.writer.lock().await.write_all(&frame).await {
+            self.pending.lock().unwrap().remove(&request_id);
+            return Err(io_error);
+        }
+        receiver.await.map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "Connection closed before the reply",
+            )
+        })
+    }
+}
+
+impl Drop for BalanceTcpClient {
+    fn drop(&mut self) {
+        self.reader_task.abort();
+    }
+}
+
+/// Ends with the connection, failing the requests still waiting for a reply.
+async fn read_replies(mut reader: OwnedReadHalf, pending: PendingReplies) {
+    while let Ok(Some(response)) =
+        read_frame::<TcpResponse, _>(&mut reader, MAX_REPLY_FRAME_BYTES).await
+    {
+        if let Some(sender) = pending.lock().unwrap().remove(&response.request_id) {
+            let _ = sender.send(response.reply);
+        }
+    }
+    pending.lock().unwrap().clear();
+}
@@ -0,0 +1,216 @@
+use std::{
+    env, io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use reqwest::header::CONTENT_TYPE;
+use serde_json::json;
+
+use actor_bank::transport::tcp::balance_tcp_frame::{TcpCommand, TcpReply};
+
+use crate::balance_tcp_client::BalanceTcpClient;
+
+mod balance_tcp_client;
+
+const WARMUP_REQUESTS: usize = 100;
+const PIPELINE_DEPTH: usize = 64;
+
+/// Deposit latency of REST and of the binary protocol against a running server, run with
+/// `cargo run --release --example bench_tcp -- [requests]`.
+///
+/// `BENCH_REST_URL` (default `http://localhost:8080`) and `BENCH_TCP_ADDRESS` (default
+/// `localhost:7000`) locate the server. Deposits of 1 go to `BENCH_BALANCE_ID`, created when
+/// missing.
+struct BalanceTcpBenchmark {
+    rest_url: String,
+    tcp_address: String,
+    balance_id: u64,
+    requests: usize,
+}
+
+struct LatencyReport {
+    name: &'static str,
+    latencies: Vec<Duration>,
+    elapsed: Duration,
+}
+
+impl LatencyReport {
+    fn print(mut self) {
+        self.latencies.sort();
+        let count = self.latencies.len();
+        let percentile = |p: f64| self.latencies[((count as f64 * p) as usize).min(count - 1)];
+        let mean = self.latencies.iter().sum::<Duration>() / count as u32;
+        println!(
+            "{:<14} {:>8} {:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>12.0}",
+            self.name,
+            count,
+            micros(mean),
+            micros(percentile(0.5)),
+            micros(percentile(0.99)),
+            micros(self.latencies[count - 1]),
+            count as f64 / self.elapsed.as_secs_f64(),
+        );
+    }
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
+
+fn failed(message: String) -> io::Error {
+    io::Error::other(message)
+}
+
+impl BalanceTcpBenchmark {
+    fn from_env(args: &[String]) -> Self {
+        Self {
+            rest_url: env::var("BENCH_REST_URL").unwrap_or("http://localhost:8080".to_string()),
+            tcp_address: env::var("BENCH_TCP_ADDRESS").unwrap_or("localhost:7000".to_string()),
+            balance_id: env::var("BENCH_BALANCE_ID")
+                .unwrap_or("900000001".to_string())
+                .parse::<u64>()
+                .unwrap_or(900000001),
+            requests: args
+                .first()
+                .and_then(|requests| requests.parse::<usize>().ok())
+                .unwrap_or(10000)
+                .max(1),
+        }
+    }
+
+    async fn run(&self) -> io::Result<()> {
+        let http_client = reqwest::Client::new();
+        let tcp_client = Arc::new(BalanceTcpClient::connect(self.tcp_address.as_str()).await?);
+        // already existing is fine; a rerun is answered with the stored result
+        tcp_client
+            .send_idempotent(
+                format!("bench-tcp-create-{}", self.balance_id),
+                TcpCommand::Create {
+                    id: self.balance_id,
+                },
+            )
+            .await?;
+
+        println!(
+            "{:<14} {:>8} {:>10} {:>10} {:>10} {:>10} {:>12}",
+            "transport", "requests", "mean µs", "p50 µs", "p99 µs", "max µs", "req/s"
+        );
+        self.rest(&http_client).await?.print();
+        self.tcp(&tcp_client).await?.print();
+        self.tcp_pipelined(tcp_client).await?.print();
+        Ok(())
+    }
+
+    /// One request at a time over a kept-alive connection.
+    async fn rest(&self, http_client: &reqwest::Client) -> io::Result<LatencyReport> {
+        let url = format!("{}/balance/deposit", self.rest_url);
+        let body = json!({ "id": self.balance_id, "amount": 1 }).to_string();
+        let deposit = || async {
+            let response = http_client
+                .post(&url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone())
+                .send()
+                .await
+                .map_err(|http_error| failed(format!("REST deposit failed: {http_error}")))?;
+            if !response.status().is_success() {
+                return Err(failed(format!(
+                    "REST deposit failed: {}",
+                    response.status()
+                )));
+            }
+            response
+                .bytes()
+                .await
+                .map_err(|http_error| failed(format!("REST deposit failed: {http_error}")))?;
+            Ok(())
+        };
+        for _ in 0..WARMUP_REQUESTS {
+            deposit().await?;
+        }
+        let mut latencies = Vec::with_capacity(self.requests);
+        let started = Instant::now();
+        for _ in 0..self.requests {
+            let request_started = Instant::now();
+            deposit().await?;
+            latencies.push(request_started.elapsed());
+        }
+        Ok(LatencyReport {
+            name: "rest",
+            latencies,
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// One request at a time, the same as REST.
+    async fn tcp(&self, tcp_client: &BalanceTcpClient) -> io::Result<LatencyReport> {
+        for _ in 0..WARMUP_REQUESTS {
+            deposit(tcp_client, self.balance_id).await?;
+        }
+        let mut latencies = Vec::with_capacity(self.requests);
+        let started = Instant::now();
+        for _ in 0..self.requests {
+            let request_started = Instant::now();
+            deposit(tcp_client, self.balance_id).await?;
+            latencies.push(request_started.elapsed());
+        }
+        Ok(LatencyReport {
+            name: "tcp",
+            latencies,
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// `PIPELINE_DEPTH` requests in flight on the one connection.
+    async fn tcp_pipelined(&self, tcp_client: Arc<BalanceTcpClient>) -> io::Result<LatencyReport> {
+        let per_task = self.requests.div_ceil(PIPELINE_DEPTH);
+        let started = Instant::now();
+        let tasks: Vec<_> = (0..PIPELINE_DEPTH)
+            .map(|_| {
+                let tcp_client = tcp_client.clone();
+                let balance_id = self.balance_id;
+                tokio::spawn(async move {
+                    let mut latencies = Vec::with_capacity(per_task);
+                    for _ in 0..per_task {
+                        let request_started = Instant::now();
+                        deposit(&tcp_client, balance_id).await?;
+                        latencies.push(request_started.elapsed());
+                    }
+                    Ok::<_, io::Error>(latencies)
+                })
+            })
+            .collect();
+        let mut latencies = Vec::with_capacity(per_task * PIPELINE_DEPTH);
+        for task in tasks {
+            latencies.extend(
+                task.await
+                    .map_err(|join_error| failed(join_error.to_string()))??,
+            );
+        }
+        Ok(LatencyReport {
+            name: "tcp pipelined",
+            latencies,
+            elapsed: started.elapsed(),
+        })
+    }
+}
+
+async fn deposit(tcp_client: &BalanceTcpClient, balance_id: u64) -> io::Result<()> {
+    match tcp_client
+        .send(TcpCommand::Deposit {
+            id: balance_id,
+            amount: 1,
+        })
+        .await?
+    {
+        TcpReply::Failed { message } => Err(failed(format!("TCP deposit failed: {message}"))),
+        _ => Ok(()),
+    }
+}
+
+#[tokio::main]
+async fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    BalanceTcpBenchmark::from_env(&args[1..]).run().await
+}
@@ -5,12 +5,18 @@ use crate::{
         balance::{
             api::{
                 balance_query_api::{BalanceQuery, BalanceQueryApi, BalanceResponse},
+                batch_balance_api::{BatchBalanceApi, BatchCommand, BatchResponse},
                 create_balance_api::{
                     CreateBalanceApi, CreateBalanceCommand, CreateBalanceResponse,
                 },
                 deposit_balance_api::{
                     DepositBalanceApi, DepositBalanceCommand, DepositBalanceResponse,
                 },
+                ledger_command_api::{
+                    CommandId, CommandReceipt, CommandResult, IdempotentLedgerCommand,
+                    LedgerCommand, LedgerCommandApi, LedgerCommandOutcome, LedgerCommandResponse,
+                    RejectLedgerCommand,
+                },
                 transfer_balance_api::{
                     TransferBalanceApi, TransferBalanceCommand, TransferBalanceResponse,
                 },
@@ -21,11 +27,15 @@ use crate::{
             spi::{
                 balance_event_repository::BalanceEventRepository,
                 balance_repository::BalanceRepository,
+                command_result_repository::CommandResultRepository,
             },
         },
         transaction_spi::Transaction,
     },
-    core::domain::balance::{Balance, Balances},
+    core::domain::{
+        balance::{Balance, Balances},
+        balance_error::BalanceError,
+    },
 };
 
 #[derive(Clone)]
@@ -35,6 +45,8 @@ pub struct BalanceApi {
     withdraw_balance_api: WithdrawBalanceApi,
     transfer_balance_api: TransferBalanceApi,
     balance_query_api: BalanceQueryApi,
+    batch_balance_api: BatchBalanceApi,
+    ledger_command_api: LedgerCommandApi,
 }
 
 impl BalanceApi {
@@ -42,6 +54,7 @@ impl BalanceApi {
         transaction: Arc<dyn Transaction>,
         balance_event_repository: Arc<dyn BalanceEventRepository>,
         balance_repository: Arc<dyn BalanceRepository>,
+        command_result_repository: Arc<dyn CommandResultRepository>,
     ) -> Self {
         let balances: Rc<RefCell<Balances>> = Self::load_balances(balance_repository.clone());
 
@@ -50,6 +63,7 @@ impl BalanceApi {
             transaction: transaction.clone(),
             balance_event_repository: balance_event_repository.clone(),
             balance_repository: balance_repository.clone(),
+            command_result_repository: command_result_repository.clone(),
         };
 
         let deposit_balance_api = DepositBalanceApi {
@@ -57,6 +71,7 @@ impl BalanceApi {
             transaction: transaction.clone(),
             balance_event_repository: balance_event_repository.clone(),
             balance_repository: balance_repository.clone(),
+            command_result_repository: command_result_repository.clone(),
         };
 
         let withdraw_balance_api = WithdrawBalanceApi {
@@ -64,6 +79,7 @@ impl BalanceApi {
             transaction: transaction.clone(),
             balance_event_repository: balance_event_repository.clone(),
             balance_repository: balance_repository.clone(),
+            command_result_repository: command_result_repository.clone(),
         };
 
         let transfer_balance_api = TransferBalanceApi {
@@ -71,18 +87,33 @@ impl BalanceApi {
             transaction: transaction.clone(),
             balance_event_repository: balance_event_repository.clone(),
             balance_repository: balance_repository.clone(),
+            command_result_repository: command_result_repository.clone(),
         };
 
         let balance_query_api = BalanceQueryApi {
             balances: balances.clone(),
         };
 
+        let batch_balance_api = BatchBalanceApi {
+            balances: balances.clone(),
+            transaction: transaction.clone(),
+            balance_event_repository: balance_event_repository.clone(),
+            balance_repository: balance_repository.clone(),
+        };
+
+        let ledger_command_api = LedgerCommandApi {
+            transaction: transaction.clone(),
+            command_result_repository: command_result_repository.clone(),
+        };
+
         Self {
             create_balance_api,
             deposit_balance_api,
             withdraw_balance_api,
             transfer_balance_api,
             balance_query_api,
+            batch_balance_api,
+            ledger_command_api,
         }
     }
 
@@ -121,4 +152,391 @@ impl BalanceApi {
     pub fn get_balance(&mut self, query: BalanceQuery) -> BalanceResponse {
         self.balance_query_api.get_balance(query)
     }
+
+    pub fn execute_batch(&mut self, command: BatchCommand) -> BatchResponse {
+        self.batch_balance_api.execute_batch(command)
+    }
+
+    /// Runs on the ledger actor, so checking for a stored result and applying the command
+    /// cannot interleave with a replay of the same command.
+    pub fn execute(&mut self, command: IdempotentLedgerCommand) -> LedgerCommandResponse {
+        let command_id = command.command_id;
+        if let Some(result) = self.ledger_command_api.find_result(&command_id) {
+            return Ok(LedgerCommandOutcome {
+                result,
+                replayed: true,
+            });
+        }
+
+        let result = match self.apply(command.command, Some(command_id.clone())) {
+            Ok(receipt) => CommandResult::Applied {
+                receipt: Some(receipt),
+            },
+            // not a decision of the ledger, the command may succeed when sent again
+            Err(BalanceError::UnknownError(message)) => {
+                return Err(BalanceError::UnknownError(message));
+            }
+            Err(balance_error) => self
+                .ledger_command_api
+                .record_rejection(&command_id, balance_error.to_string()),
+        };
+        Ok(LedgerCommandOutcome {
+            result,
+            replayed: false,
+        })
+    }
+
+    /// A command that got a result in the meantime keeps it.
+    pub fn reject(&mut self, command: RejectLedgerCommand) -> LedgerCommandResponse {
+        if let Some(result) = self.ledger_command_api.find_result(&command.command_id) {
+            return Ok(LedgerCommandOutcome {
+                result,
+                replayed: true,
+            });
+        }
+        let result = self
+            .ledger_command_api
+            .record_rejection(&command.command_id, command.message);
+        Ok(LedgerCommandOutcome {
+            result,
+            replayed: false,
+        })
+    }
+
+    /// A command without an id: applied every time it is sent, and its result is not stored.
+    pub fn submit(&mut self, command: LedgerCommand) -> LedgerCommandResponse {
+        let result = match self.apply(command, None) {
+            Ok(receipt) => CommandResult::Applied {
+                receipt: Some(receipt),
+            },
+            Err(BalanceError::UnknownError(message)) => {
+                return Err(BalanceError::UnknownError(message));
+            }
+            Err(balance_error) => CommandResult::Rejected {
+                message: balance_error.to_string(),
+            },
+        };
+        Ok(LedgerCommandOutcome {
+            result,
+            replayed: false,
+        })
+    }
+
+    fn apply(
+        &mut self,
+        command: LedgerCommand,
+        command_id: Option<CommandId>,
+    ) -> Result<CommandReceipt, BalanceError> {
+        match command {
+            LedgerCommand::Create(mut command) => {
+                command.command_id = command_id;
+                self.create_balance(command)
+            }
+            LedgerCommand::Deposit(mut command) => {
+                command.command_id = command_id;
+                self.deposit(command)
+            }
+            LedgerCommand::Withdraw(mut command) => {
+                command.command_id = command_id;
+                self.withdraw(command)
+            }
+            LedgerCommand::Transfer(mut command) => {
+                command.command_id = command_id;
+                self.transfer(command)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::core::domain::{
+        balance::{BalanceAmount, BalanceId},
+        balance_event::BalanceEventType,
+    };
+    use crate::infrastructure::{
+        balance::{
+            balance_config::open_db,
+            balance_event_repository_rocksdb::BalanceEventRepositoryRocksdb,
+            balance_repository_rocksdb::BalanceRepositoryRocksdb,
+            command_result_repository_rocksdb::CommandResultRepositoryRocksdb,
+        },
+        rocksdb_transaction::RocksdbTransaction,
+    };
+
+    struct Ledger {
+        balance_api: BalanceApi,
+        balance_event_repository: Arc<dyn BalanceEventRepository>,
+        balance_repository: Arc<dyn BalanceRepository>,
+    }
+
+    fn ledger(path: &Path) -> Ledger {
+        let db = open_db(path);
+        let balance_event_repository: Arc<dyn BalanceEventRepository> =
+            Arc::new(BalanceEventRepositoryRocksdb::new(db.clone()));
+        let balance_repository: Arc<dyn BalanceRepository> =
+            Arc::new(BalanceRepositoryRocksdb::new(db.clone()));
+        let balance_api = BalanceApi::new(
+            Arc::new(RocksdbTransaction::new(db.clone(), vec![])),
+            balance_event_repository.clone(),
+            balance_repository.clone(),
+            Arc::new(CommandResultRepositoryRocksdb::new(db)),
+        );
+        Ledger {
+            balance_api,
+            balance_event_repository,
+            balance_repository,
+        }
+    }
+
+    fn idempotent(command_id: &str, command: LedgerCommand) -> IdempotentLedgerCommand {
+        IdempotentLedgerCommand {
+            command_id: command_id.to_string(),
+            command,
+        }
+    }
+
+    fn deposit(id: BalanceId, amount: BalanceAmount) -> LedgerCommand {
+        LedgerCommand::Deposit(DepositBalanceCommand::new(id, amount))
+    }
+
+    fn withdraw(id: BalanceId, amount: BalanceAmount) -> LedgerCommand {
+        LedgerCommand::Withdraw(WithdrawBalanceCommand::new(id, amount))
+    }
+
+    fn amount(ledger: &mut Ledger, id: BalanceId) -> BalanceAmount {
+        ledger
+            .balance_api
+            .get_balance(BalanceQuery { id })
+            .unwrap()
+            .amount()
+    }
+
+    fn ledger_with_balance(path: &Path) -> Ledger {
+        let mut ledger = ledger(path);
+        ledger
+            .balance_api
+            .create_balance(CreateBalanceCommand::new(1))
+            .unwrap();
+        ledger
+    }
+
+    #[test]
+    fn command_sent_twice_is_applied_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ledger = ledger_with_balance(dir.path());
+
+        let first = ledger
+            .balance_api
+            .execute(idempotent("deposit-1", deposit(1, 100)))
+            .unwrap();
+        let second = ledger
+            .balance_api
+            .execute(idempotent("deposit-1", deposit(1, 100)))
+            .unwrap();
+
+        assert!(!first.replayed);
+        assert!(second.replayed);
+        assert_eq!(second.result, first.result);
+        assert_eq!(ledger.balance_event_repository.last_event_id(), 2);
+        assert_eq!(amount(&mut ledger, 1), 100);
+        assert_eq!(ledger.balance_repository.get(1).unwrap().amount(), 100);
+    }
+
+    #[test]
+    fn replay_survives_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = {
+            let mut ledger = ledger_with_balance(dir.path());
+            ledger
+                .balance_api
+                .execute(idempotent("deposit-1", deposit(1, 100)))
+                .unwrap()
+        };
+
+        let mut ledger = ledger(dir.path());
+        let second = ledger
+            .balance_api
+            .execute(idempotent("deposit-1", deposit(1, 100)))
+            .unwrap();
+
+        assert!(second.replayed);
+        assert_eq!(second.result, first.result);
+        assert_eq!(ledger.balance_event_repository.last_event_id(), 2);
+        assert_eq!(amount(&mut ledger, 1), 100);
+    }
+
+    #[test]
+    fn rejected_command_is_replayed_as_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ledger = ledger_with_balance(dir.path());
+
+        let first = ledger
+            .balance_api
+            .execute(idempotent("withdraw-1", withdraw(1, 50)))
+            .unwrap();
+        // funds that arrive later do not change the answer to the same command
+        ledger
+            .balance_api
+            .deposit(DepositBalanceCommand::new(1, 100))
+            .unwrap();
+        let second = ledger
+            .balance_api
+            .execute(idempotent("withdraw-1", withdraw(1, 50)))
+            .unwrap();
+
+        assert!(!first.replayed);
+        assert!(matches!(first.result, CommandResult::Rejected { .. }));
+        assert!(second.replayed);
+        assert_eq!(second.result, first.result);
+        assert_eq!(ledger.balance_event_repository.last_event_id(), 2);
+        assert_eq!(amount(&mut ledger, 1), 100);
+    }
+
+    #[test]
+    fn given_up_command_is_not_applied_when_sent_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ledger = ledger_with_balance(dir.path());
+
+        let rejection = ledger
+            .balance_api
+            .reject(RejectLedgerCommand {
+                command_id: "deposit-1".to_string(),
+                message: "gave up".to_string(),
+            })
+            .unwrap();
+        let replay = ledger
+            .balance_api
+            .execute(idempotent("deposit-1", deposit(1, 100)))
+            .unwrap();
+
+        assert!(!rejection.replayed);
+        assert!(replay.replayed);
+        assert_eq!(
+            replay.result,
+            CommandResult::Rejected {
+                message: "gave up".to_string()
+            }
+        );
+        assert_eq!(ledger.balance_event_repository.last_event_id(), 1);
+        assert_eq!(amount(&mut ledger, 1), 0);
+    }
+
+    #[test]
+    fn applied_command_keeps_its_result_when_rejected_later() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ledger = ledger_with_balance(dir.path());
+
+        let applied = ledger
+            .balance_api
+            .execute(idempotent("deposit-1", deposit(1, 100)))
+            .unwrap();
+        let rejection = ledger
+            .balance_api
+            .reject(RejectLedgerCommand {
+                command_id: "deposit-1".to_string(),
+                message: "gave up".to_string(),
+            })
+            .unwrap();
+
+        assert!(rejection.replayed);
+        assert_eq!(rejection.result, applied.result);
+        assert_eq!(amount(&mut ledger, 1), 100);
+    }
+
+    /// The receipt names the event just committed, of the command's type.
+    fn assert_receipt_event(
+        ledger: &Ledger,
+        receipt: &CommandReceipt,
+        event_type: BalanceEventType,
+    ) {
+        assert_eq!(
+            receipt.event_id,
+            ledger.balance_event_repository.last_event_id()
+        );
+        let event = ledger
+            .balance_event_repository
+            .read(receipt.event_id, 1)
+            .pop()
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.id, receipt.event_id);
+        assert_eq!(event.event_type, event_type);
+    }
+
+    #[test]
+    fn every_command_returns_its_event_and_the_balances_it_left() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ledger = ledger(dir.path());
+
+        let created = ledger
+            .balance_api
+            .create_balance(CreateBalanceCommand::new(1))
+            .unwrap();
+        assert_receipt_event(&ledger, &created, BalanceEventType::BalanceCreated);
+        assert_eq!(created.balances, vec![Balance::new(1, 0)]);
+
+        let deposited = ledger
+            .balance_api
+            .deposit(DepositBalanceCommand::new(1, 100))
+            .unwrap();
+        assert_receipt_event(&ledger, &deposited, BalanceEventType::BalanceDeposited);
+        assert_eq!(deposited.balances, vec![Balance::new(1, 100)]);
+
+        let withdrawn = ledger
+            .balance_api
+            .withdraw(WithdrawBalanceCommand::new(1, 30))
+            .unwrap();
+        assert_receipt_event(&ledger, &withdrawn, BalanceEventType::BalanceWithdrawn);
+        assert_eq!(withdrawn.balances, vec![Balance::new(1, 70)]);
+
+        ledger
+            .balance_api
+            .create_balance(CreateBalanceCommand::new(2))
+            .unwrap();
+        let transferred = ledger
+            .balance_api
+            .transfer(TransferBalanceCommand::new(1, 2, 20))
+            .unwrap();
+        assert_receipt_event(&ledger, &transferred, BalanceEventType::BalanceTransferred);
+        assert_eq!(
+            transferred.balances,
+            vec![Balance::new(1, 50), Balance::new(2, 20)]
+        );
+    }
+
+    #[test]
+    fn replay_returns_the_stored_receipt() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ledger = ledger_with_balance(dir.path());
+
+        let first = ledger
+            .balance_api
+            .execute(idempotent("deposit-1", deposit(1, 100)))
+            .unwrap();
+        // later commands do not change the receipt of an earlier one
+        ledger
+            .balance_api
+            .deposit(DepositBalanceCommand::new(1, 5))
+            .unwrap();
+        let replay = ledger
+            .balance_api
+            .execute(idempotent("deposit-1", deposit(1, 100)))
+            .unwrap();
+
+        assert!(replay.replayed);
+        assert_eq!(
+            replay.result,
+            CommandResult::Applied {
+                receipt: Some(CommandReceipt {
+                    event_id: 2,
+                    balances: vec![Balance::new(1, 100)],
+                })
+            }
+        );
+        assert_eq!(replay.result, first.result);
+    }
 }
@@ -1,66 +1,107 @@
 use std::sync::Arc;
 
 use bincode::config;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
+use utoipa::ToSchema;
 
 use crate::{
-    application::balance::spi::balance_event_repository::BalanceEventRepository,
-    core::domain::balance_event::{
-        BalanceCreatedEvent, BalanceDepositedEvent, BalanceEventType, BalanceTransferredEvent,
-        BalanceWithdrawnEvent, EventId,
+    application::balance::spi::balance_event_repository::{
+        BalanceEventRepository, UnreadableBalanceEvent,
+    },
+    core::domain::{
+        balance_event::{
+            BalanceCreatedEvent, BalanceDepositedEvent, BalanceEvent, BalanceEventPayload,
+            BalanceEventType, BalanceTransferredEvent, BalanceWithdrawnEvent, EventId,
+            EventSchemaVersion,
+        },
+        balance_event_upcaster::{BalanceEventUpcastError, BalanceEventUpcasterChain},
     },
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Published shape of an event, described by `schemas/balance_event_data.v1.schema.json`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct BalanceEventData {
+    #[schema(value_type = u64)]
     pub id: EventId,
-    pub event_type: BalanceEventType,
+    #[schema(value_type = u16)]
+    pub schema_version: EventSchemaVersion,
     pub event_time: u64,
-    pub data: String,
+    #[serde(flatten)]
+    pub payload: BalanceEventPayload,
+}
+
+impl BalanceEventData {
+    pub fn event_type(&self) -> BalanceEventType {
+        self.payload.event_type()
+    }
 }
 
+pub type BalanceEventResult = Result<BalanceEventData, UnreadableBalanceEvent>;
+
+/// One result per stored event, in id order: a bad event does not hide the ones around it.
+pub type BalanceEventsResponse = Vec<BalanceEventResult>;
+
 pub struct BalanceEventApi {
     pub balance_event_repository: Arc<dyn BalanceEventRepository>,
+    pub balance_event_upcaster_chain: BalanceEventUpcasterChain,
 }
 
 impl BalanceEventApi {
-    pub fn get_balance_events(&self, offset: u64, limit: u64) -> Vec<BalanceEventData> {
+    pub fn get_balance_events(&self, offset: u64, limit: u64) -> BalanceEventsResponse {
         let balance_events = self.balance_event_repository.read(offset, limit);
         balance_events
             .into_iter()
-            .map(|event| BalanceEventData {
-                id: event.id,
-                event_type: event.event_type.clone(),
-                event_time: event.event_time,
-                data: self.string_event(event.event_type.clone(), event.data),
+            .map(|stored_event| {
+                let event = stored_event?;
+                let id = event.id;
+                self.event_data(event)
+                    .map_err(|upcast_error| UnreadableBalanceEvent {
+                        id,
+                        reason: upcast_error.to_string(),
+                    })
             })
             .collect()
     }
 
-    fn string_event(&self, event_type: BalanceEventType, data: Vec<u8>) -> String {
-        match event_type {
+    pub fn last_event_id(&self) -> EventId {
+        self.balance_event_repository.last_event_id()
+    }
+
+    fn event_data(&self, event: BalanceEvent) -> Result<BalanceEventData, BalanceEventUpcastError> {
+        let event = self.balance_event_upcaster_chain.upcast(event)?;
+        Ok(BalanceEventData {
+            id: event.id,
+            schema_version: event.schema_version,
+            event_time: event.event_time,
+            payload: self.decode_payload(event.event_type, &event.data)?,
+        })
+    }
+
+    fn decode_payload(
+        &self,
+        event_type: BalanceEventType,
+        data: &[u8],
+    ) -> Result<BalanceEventPayload, BalanceEventUpcastError> {
+        let payload = match event_type {
             BalanceEventType::BalanceCreated => {
-                self.decode_and_serialize::<BalanceCreatedEvent>(&data)
+                BalanceEventPayload::BalanceCreated(self.decode::<BalanceCreatedEvent>(data)?)
             }
             BalanceEventType::BalanceDeposited => {
-                self.decode_and_serialize::<BalanceDepositedEvent>(&data)
+                BalanceEventPayload::BalanceDeposited(self.decode::<BalanceDepositedEvent>(data)?)
             }
             BalanceEventType::BalanceWithdrawn => {
-                self.decode_and_serialize::<BalanceWithdrawnEvent>(&data)
+                BalanceEventPayload::BalanceWithdrawn(self.decode::<BalanceWithdrawnEvent>(data)?)
             }
-            BalanceEventType::BalanceTransferred => {
-                self.decode_and_serialize::<BalanceTransferredEvent>(&data)
-            }
-        }
+            BalanceEventType::BalanceTransferred => BalanceEventPayload::BalanceTransferred(
+                self.decode::<BalanceTransferredEvent>(data)?,
+            ),
+        };
+        Ok(payload)
     }
 
-    fn decode_and_serialize<T: serde::Serialize + bincode::Decode<()>>(
-        &self,
-        data: &[u8],
-    ) -> String {
-        let event: T = bincode::decode_from_slice(data, config::standard())
-            .unwrap()
-            .0;
-        serde_json::to_string(&event).unwrap()
+    fn decode<T: bincode::Decode<()>>(&self, data: &[u8]) -> Result<T, BalanceEventUpcastError> {
+        bincode::decode_from_slice(data, config::standard())
+            .map(|(event, _)| event)
+            .map_err(|error| BalanceEventUpcastError::InvalidPayload(error.to_string()))
     }
 }
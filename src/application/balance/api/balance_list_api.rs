@@ -0,0 +1,299 @@
+use std::{cmp::Ordering, collections::BinaryHeap, fmt, str::FromStr, sync::Arc};
+
+use crate::{
+    application::balance::spi::balance_repository::BalanceRepository,
+    core::domain::balance::{Balance, BalanceAmount, BalanceId},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceSort {
+    IdAsc,
+    AmountAsc,
+    AmountDesc,
+}
+
+/// Position after the last balance of a page. Ties on amount are broken by id, so the
+/// position is unique in every sort order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceCursor {
+    pub amount: BalanceAmount,
+    pub id: BalanceId,
+}
+
+impl BalanceCursor {
+    fn of(balance: &Balance) -> Self {
+        Self {
+            amount: balance.amount,
+            id: balance.id,
+        }
+    }
+}
+
+/// `<amount>_<id>`; clients pass it back as they got it.
+impl fmt::Display for BalanceCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}", self.amount, self.id)
+    }
+}
+
+impl FromStr for BalanceCursor {
+    type Err = String;
+
+    fn from_str(cursor: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid cursor: {cursor}");
+        let (amount, id) = cursor.split_once('_').ok_or_else(invalid)?;
+        Ok(Self {
+            amount: amount.parse().map_err(|_| invalid())?,
+            id: id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+pub struct BalanceListQuery {
+    pub sort: BalanceSort,
+    pub after: Option<BalanceCursor>,
+    pub limit: usize,
+    pub min_amount: Option<BalanceAmount>,
+    pub max_amount: Option<BalanceAmount>,
+}
+
+impl BalanceListQuery {
+    fn matches(&self, balance: &Balance) -> bool {
+        self.min_amount.is_none_or(|min| balance.amount >= min)
+            && self.max_amount.is_none_or(|max| balance.amount <= max)
+    }
+
+    fn order(&self, left: &Balance, right: &Balance) -> Ordering {
+        match self.sort {
+            BalanceSort::IdAsc => left.id.cmp(&right.id),
+            BalanceSort::AmountAsc => (left.amount, left.id).cmp(&(right.amount, right.id)),
+            BalanceSort::AmountDesc => (right.amount, left.id).cmp(&(left.amount, right.id)),
+        }
+    }
+
+    fn is_after_cursor(&self, balance: &Balance) -> bool {
+        self.after.is_none_or(|cursor| {
+            let cursor_balance = Balance::new(cursor.id, cursor.amount);
+            self.order(balance, &cursor_balance) == Ordering::Greater
+        })
+    }
+}
+
+pub struct BalancePage {
+    pub balances: Vec<Balance>,
+    /// `None` on the last page.
+    pub next_cursor: Option<BalanceCursor>,
+}
+
+/// Lists committed balances from the store rather than from the ledger actor, so a listing
+/// never holds back commands. A page sees the balances as of the moment it is read.
+pub struct BalanceListApi {
+    pub balance_repository: Arc<dyn BalanceRepository>,
+}
+
+impl BalanceListApi {
+    pub fn list_balances(&self, query: &BalanceListQuery) -> BalancePage {
+        let mut balances = match query.sort {
+            BalanceSort::IdAsc => self.first_by_id(query),
+            BalanceSort::AmountAsc | BalanceSort::AmountDesc => self.first_by_amount(query),
+        };
+        let next_cursor = if balances.len() > query.limit {
+            balances.truncate(query.limit);
+            balances.last().map(BalanceCursor::of)
+        } else {
+            None
+        };
+        BalancePage {
+            balances,
+            next_cursor,
+        }
+    }
+
+    /// Key order is id order: the scan starts at the cursor and stops once the page is full.
+    fn first_by_id(&self, query: &BalanceListQuery) -> Vec<Balance> {
+        self.balance_repository
+            .scan(query.after.map(|cursor| cursor.id))
+            .filter(|balance| query.matches(balance))
+            .take(query.limit + 1)
+            .collect()
+    }
+
+    /// Every balance is visited; only the `limit + 1` first in sort order are kept.
+    fn first_by_amount(&self, query: &BalanceListQuery) -> Vec<Balance> {
+        let mut first = BinaryHeap::with_capacity(query.limit + 2);
+        for balance in self.balance_repository.scan(None) {
+            if !query.matches(&balance) || !query.is_after_cursor(&balance) {
+                continue;
+            }
+            first.push(Ordered { query, balance });
+            if first.len() > query.limit + 1 {
+                first.pop();
+            }
+        }
+        first
+            .into_sorted_vec()
+            .into_iter()
+            .map(|ordered| ordered.balance)
+            .collect()
+    }
+}
+
+/// A balance ordered as its query sorts, so the heap pops the last one first.
+struct Ordered<'a> {
+    query: &'a BalanceListQuery,
+    balance: Balance,
+}
+
+impl PartialEq for Ordered<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ordered<'_> {}
+
+impl PartialOrd for Ordered<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ordered<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.query.order(&self.balance, &other.balance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        application::transaction_spi::Transaction,
+        infrastructure::{
+            balance::{
+                balance_config::open_db, balance_repository_rocksdb::BalanceRepositoryRocksdb,
+            },
+            rocksdb_transaction::RocksdbTransaction,
+        },
+    };
+
+    /// Committed as given; amounts repeat so that ties are broken by id.
+    fn balance_list_api(path: &Path, balances: &[Balance]) -> BalanceListApi {
+        let db = open_db(path);
+        let balance_repository = Arc::new(BalanceRepositoryRocksdb::new(db.clone()));
+        let transaction_context = RocksdbTransaction::new(db, vec![]).start();
+        for balance in balances {
+            balance_repository.persist_in_transaction(balance.clone(), transaction_context.clone());
+        }
+        transaction_context.commit();
+        BalanceListApi { balance_repository }
+    }
+
+    fn ledger_balances() -> Vec<Balance> {
+        [(1, 30), (2, 10), (3, 20), (4, 10), (5, 30), (6, 0), (7, 20)]
+            .into_iter()
+            .map(|(id, amount)| Balance::new(id, amount))
+            .collect()
+    }
+
+    fn query(sort: BalanceSort, limit: usize) -> BalanceListQuery {
+        BalanceListQuery {
+            sort,
+            after: None,
+            limit,
+            min_amount: None,
+            max_amount: None,
+        }
+    }
+
+    /// Follows `next_cursor` until the last page, checking that only that page has none.
+    fn all_pages(balance_list_api: &BalanceListApi, mut query: BalanceListQuery) -> Vec<Balance> {
+        let mut visited = Vec::new();
+        loop {
+            let page = balance_list_api.list_balances(&query);
+            assert!(page.balances.len() <= query.limit);
+            visited.extend(page.balances);
+            match page.next_cursor {
+                Some(cursor) => query.after = Some(cursor),
+                None => return visited,
+            }
+        }
+    }
+
+    fn ids(balances: &[Balance]) -> Vec<BalanceId> {
+        balances.iter().map(Balance::id).collect()
+    }
+
+    #[test]
+    fn pages_visit_every_balance_once_in_sort_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let balance_list_api = balance_list_api(dir.path(), &ledger_balances());
+
+        for (sort, expected) in [
+            (BalanceSort::IdAsc, vec![1, 2, 3, 4, 5, 6, 7]),
+            (BalanceSort::AmountAsc, vec![6, 2, 4, 3, 7, 1, 5]),
+            (BalanceSort::AmountDesc, vec![1, 5, 3, 7, 2, 4, 6]),
+        ] {
+            for limit in [1, 2, 3, 7, 8] {
+                let visited = all_pages(&balance_list_api, query(sort, limit));
+                assert_eq!(ids(&visited), expected, "{sort:?} by {limit}");
+            }
+        }
+    }
+
+    #[test]
+    fn full_last_page_has_no_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let balance_list_api = balance_list_api(dir.path(), &ledger_balances());
+
+        let first = balance_list_api.list_balances(&query(BalanceSort::AmountAsc, 4));
+        let mut rest = query(BalanceSort::AmountAsc, 3);
+        rest.after = first.next_cursor;
+        let last = balance_list_api.list_balances(&rest);
+
+        assert_eq!(first.next_cursor, Some(BalanceCursor { amount: 20, id: 3 }));
+        assert_eq!(ids(&last.balances), vec![7, 1, 5]);
+        assert_eq!(last.next_cursor, None);
+    }
+
+    #[test]
+    fn amount_filters_are_inclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let balance_list_api = balance_list_api(dir.path(), &ledger_balances());
+
+        for sort in [BalanceSort::IdAsc, BalanceSort::AmountAsc] {
+            let mut between = query(sort, 2);
+            between.min_amount = Some(10);
+            between.max_amount = Some(20);
+            let mut visited = ids(&all_pages(&balance_list_api, between));
+            visited.sort();
+            assert_eq!(visited, vec![2, 3, 4, 7], "{sort:?}");
+        }
+
+        let mut at_least = query(BalanceSort::AmountDesc, 10);
+        at_least.min_amount = Some(30);
+        assert_eq!(ids(&all_pages(&balance_list_api, at_least)), vec![1, 5]);
+
+        let mut at_most = query(BalanceSort::IdAsc, 10);
+        at_most.max_amount = Some(0);
+        assert_eq!(ids(&all_pages(&balance_list_api, at_most)), vec![6]);
+    }
+
+    #[test]
+    fn cursor_reads_back_as_written() {
+        let cursor = BalanceCursor {
+            amount: u128::MAX,
+            id: 7,
+        };
+
+        assert_eq!(cursor.to_string().parse::<BalanceCursor>(), Ok(cursor));
+        for malformed in ["", "7", "_7", "10_", "10-7", "a_7", "10_b", "-1_7"] {
+            assert_eq!(
+                malformed.parse::<BalanceCursor>(),
+                Err(format!("Invalid cursor: {malformed}"))
+            );
+        }
+    }
+}
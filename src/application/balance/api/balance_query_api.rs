@@ -1,14 +1,16 @@
 use std::{cell::RefCell, rc::Rc};
 
 use serde::Deserialize;
+use utoipa::IntoParams;
 
 use crate::core::domain::{
     balance::{Balance, BalanceId, Balances},
     balance_error::BalanceError,
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct BalanceQuery {
+    #[param(value_type = u64)]
     pub id: BalanceId,
 }
 
@@ -0,0 +1,416 @@
+use std::{cell::RefCell, collections::BTreeSet, rc::Rc, sync::Arc};
+
+use crate::{
+    application::{
+        balance::{
+            api::ledger_command_api::{CommandReceipt, LedgerCommand},
+            spi::{
+                balance_event_repository::BalanceEventRepository,
+                balance_repository::BalanceRepository,
+            },
+        },
+        transaction_spi::{Transaction, TransactionContext},
+    },
+    core::{
+        common::types::Void,
+        domain::{
+            balance::{Balance, BalanceId, Balances},
+            balance_error::BalanceError,
+            balance_event::{
+                BalanceCreatedEvent, BalanceDepositedEvent, BalanceEventType,
+                BalanceTransferredEvent, BalanceWithdrawnEvent, EventId,
+            },
+        },
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    /// Every command is applied, or none when one of them is rejected.
+    Atomic,
+    /// Each command is applied unless it is rejected itself.
+    Independent,
+}
+
+/// Commands applied in order, in one ledger message and one write.
+pub struct BatchCommand {
+    pub mode: BatchMode,
+    pub commands: Vec<LedgerCommand>,
+}
+
+#[derive(Debug)]
+pub enum BatchItemResult {
+    /// Its balances are those right after this command, before the next ones of the batch.
+    Applied(CommandReceipt),
+    Rejected(BalanceError),
+    /// Valid in its place, but another command of the atomic batch was rejected.
+    NotApplied,
+}
+
+/// One result per command, in the order of the commands.
+pub type BatchResponse = Result<Vec<BatchItemResult>, BalanceError>;
+
+#[derive(Clone)]
+pub struct BatchBalanceApi {
+    pub balances: Rc<RefCell<Balances>>,
+    pub transaction: Arc<dyn Transaction>,
+    pub balance_repository: Arc<dyn BalanceRepository>,
+    pub balance_event_repository: Arc<dyn BalanceEventRepository>,
+}
+
+impl BatchBalanceApi {
+    /// The commands run first on a copy of the balances they touch, so a rejected command
+    /// of an atomic batch leaves neither the ledger nor the event sequence changed.
+    pub fn execute_batch(&mut self, command: BatchCommand) -> BatchResponse {
+        let mut scratch = self.scratch_balances(&command.commands);
+        let outcomes: Vec<Result<Vec<Balance>, BalanceError>> = command
+            .commands
+            .iter()
+            .map(|ledger_command| {
+                apply(ledger_command, &mut scratch)?;
+                Ok(balance_ids(ledger_command)
+                    .into_iter()
+                    .map(|balance_id| scratch.get_balance(balance_id).unwrap().clone())
+                    .collect())
+            })
+            .collect();
+
+        let rejected = outcomes.iter().any(Result::is_err);
+        if command.mode == BatchMode::Atomic && rejected {
+            return Ok(outcomes
+                .into_iter()
+                .map(|outcome| match outcome {
+                    Ok(_) => BatchItemResult::NotApplied,
+                    Err(balance_error) => BatchItemResult::Rejected(balance_error),
+                })
+                .collect());
+        }
+
+        let applied: Vec<&LedgerCommand> = command
+            .commands
+            .iter()
+            .zip(&outcomes)
+            .filter(|(_, outcome)| outcome.is_ok())
+            .map(|(ledger_command, _)| ledger_command)
+            .collect();
+        let mut event_ids = Vec::new();
+        if !applied.is_empty() {
+            event_ids = self.persist_in_transaction(&applied, &scratch);
+            self.balances.borrow_mut().balances.extend(scratch.balances);
+        }
+        let mut event_ids = event_ids.into_iter();
+        Ok(outcomes
+            .into_iter()
+            .map(|outcome| match outcome {
+                Ok(balances) => BatchItemResult::Applied(CommandReceipt {
+                    event_id: event_ids.next().unwrap(),
+                    balances,
+                }),
+                Err(balance_error) => BatchItemResult::Rejected(balance_error),
+            })
+            .collect())
+    }
+
+    /// The existing balances the commands refer to.
+    fn scratch_balances(&self, commands: &[LedgerCommand]) -> Balances {
+        let balances = self.balances.borrow();
+        let mut scratch = Balances::default();
+        for balance_id in commands.iter().flat_map(balance_ids) {
+            if let Ok(balance) = balances.get_balance(balance_id) {
+                scratch.balances.insert(balance_id, balance.clone());
+            }
+        }
+        scratch
+    }
+
+    /// An event per applied command, and the final state of each balance they touched.
+    /// Returns the event ids in the order of the commands.
+    fn persist_in_transaction(
+        &self,
+        applied: &[&LedgerCommand],
+        scratch: &Balances,
+    ) -> Vec<EventId> {
+        let transaction_context: Rc<dyn TransactionContext> = self.transaction.start();
+        let mut touched = BTreeSet::new();
+        let mut event_ids = Vec::with_capacity(applied.len());
+        for ledger_command in applied {
+            let (event_type, event) = event(ledger_command);
+            event_ids.push(self.balance_event_repository.persist_in_transaction(
+                event_type,
+                event,
+                transaction_context.clone(),
+            ));
+            touched.extend(balance_ids(ledger_command));
+        }
+        for balance_id in touched {
+            let balance = scratch.get_balance(balance_id).unwrap();
+            self.balance_repository
+                .persist_in_transaction(balance.clone(), transaction_context.clone());
+        }
+        transaction_context.commit();
+        event_ids
+    }
+}
+
+fn apply(ledger_command: &LedgerCommand, balances: &mut Balances) -> Result<Void, BalanceError> {
+    match ledger_command {
+        LedgerCommand::Create(command) => balances.create_balance(command.id),
+        LedgerCommand::Deposit(command) => balances.deposit(command.id, command.amount),
+        LedgerCommand::Withdraw(command) => balances.withdraw(command.id, command.amount),
+        LedgerCommand::Transfer(command) => {
+            balances.transfer(command.from_id, command.to_id, command.amount)
+        }
+    }
+}
+
+fn balance_ids(ledger_command: &LedgerCommand) -> Vec<BalanceId> {
+    match ledger_command {
+        LedgerCommand::Create(command) => vec![command.id],
+        LedgerCommand::Deposit(command) => vec![command.id],
+        LedgerCommand::Withdraw(command) => vec![command.id],
+        LedgerCommand::Transfer(command) => vec![command.from_id, command.to_id],
+    }
+}
+
+fn event(ledger_command: &LedgerCommand) -> (BalanceEventType, Vec<u8>) {
+    match ledger_command {
+        LedgerCommand::Create(command) => (
+            BalanceEventType::BalanceCreated,
+            BalanceCreatedEvent { id: command.id }.bytes(),
+        ),
+        LedgerCommand::Deposit(command) => (
+            BalanceEventType::BalanceDeposited,
+            BalanceDepositedEvent {
+                id: command.id,
+                amount: command.amount,
+            }
+            .bytes(),
+        ),
+        LedgerCommand::Withdraw(command) => (
+            BalanceEventType::BalanceWithdrawn,
+            BalanceWithdrawnEvent {
+                id: command.id,
+                amount: command.amount,
+            }
+            .bytes(),
+        ),
+        LedgerCommand::Transfer(command) => (
+            BalanceEventType::BalanceTransferred,
+            BalanceTransferredEvent {
+                from_id: command.from_id,
+                to_id: command.to_id,
+                amount: command.amount,
+            }
+            .bytes(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::Path,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use super::*;
+    use crate::{
+        application::balance::api::{
+            create_balance_api::CreateBalanceCommand, deposit_balance_api::DepositBalanceCommand,
+            transfer_balance_api::TransferBalanceCommand,
+            withdraw_balance_api::WithdrawBalanceCommand,
+        },
+        core::domain::balance::BalanceAmount,
+        infrastructure::{
+            balance::{
+                balance_config::open_db,
+                balance_event_repository_rocksdb::BalanceEventRepositoryRocksdb,
+                balance_repository_rocksdb::BalanceRepositoryRocksdb,
+            },
+            rocksdb_transaction::RocksdbTransaction,
+        },
+    };
+
+    /// Counts the writes: each transaction is committed as one write batch.
+    struct CountingTransaction {
+        transaction: RocksdbTransaction,
+        starts: AtomicU32,
+    }
+
+    impl Transaction for CountingTransaction {
+        fn start(&self) -> Rc<dyn TransactionContext> {
+            self.starts.fetch_add(1, Ordering::Relaxed);
+            self.transaction.start()
+        }
+    }
+
+    struct Ledger {
+        batch_balance_api: BatchBalanceApi,
+        transaction: Arc<CountingTransaction>,
+        balance_repository: Arc<dyn BalanceRepository>,
+        balance_event_repository: Arc<dyn BalanceEventRepository>,
+    }
+
+    impl Ledger {
+        fn new(path: &Path) -> Self {
+            let db = open_db(path);
+            let transaction = Arc::new(CountingTransaction {
+                transaction: RocksdbTransaction::new(db.clone(), vec![]),
+                starts: AtomicU32::new(0),
+            });
+            let balance_repository: Arc<dyn BalanceRepository> =
+                Arc::new(BalanceRepositoryRocksdb::new(db.clone()));
+            let balance_event_repository: Arc<dyn BalanceEventRepository> =
+                Arc::new(BalanceEventRepositoryRocksdb::new(db));
+            let batch_balance_api = BatchBalanceApi {
+                balances: Rc::new(RefCell::new(Balances::default())),
+                transaction: transaction.clone(),
+                balance_repository: balance_repository.clone(),
+                balance_event_repository: balance_event_repository.clone(),
+            };
+            Self {
+                batch_balance_api,
+                transaction,
+                balance_repository,
+                balance_event_repository,
+            }
+        }
+
+        fn execute(
+            &mut self,
+            mode: BatchMode,
+            commands: Vec<LedgerCommand>,
+        ) -> Vec<BatchItemResult> {
+            self.batch_balance_api
+                .execute_batch(BatchCommand { mode, commands })
+                .unwrap()
+        }
+
+        /// In memory and in the store, which must agree.
+        fn amount(&self, id: BalanceId) -> Option<BalanceAmount> {
+            let in_memory = self
+                .batch_balance_api
+                .balances
+                .borrow()
+                .get_balance(id)
+                .ok()
+                .map(Balance::amount);
+            let stored = self
+                .balance_repository
+                .get(id)
+                .map(|balance| balance.amount());
+            assert_eq!(in_memory, stored, "balance {id}");
+            stored
+        }
+    }
+
+    fn create(id: BalanceId) -> LedgerCommand {
+        LedgerCommand::Create(CreateBalanceCommand::new(id))
+    }
+
+    fn deposit(id: BalanceId, amount: BalanceAmount) -> LedgerCommand {
+        LedgerCommand::Deposit(DepositBalanceCommand::new(id, amount))
+    }
+
+    fn withdraw(id: BalanceId, amount: BalanceAmount) -> LedgerCommand {
+        LedgerCommand::Withdraw(WithdrawBalanceCommand::new(id, amount))
+    }
+
+    fn transfer(from_id: BalanceId, to_id: BalanceId, amount: BalanceAmount) -> LedgerCommand {
+        LedgerCommand::Transfer(TransferBalanceCommand::new(from_id, to_id, amount))
+    }
+
+    fn receipt(result: &BatchItemResult) -> (EventId, Vec<(BalanceId, BalanceAmount)>) {
+        let BatchItemResult::Applied(receipt) = result else {
+            panic!("not applied: {result:?}");
+        };
+        let balances = receipt
+            .balances
+            .iter()
+            .map(|balance| (balance.id(), balance.amount()))
+            .collect();
+        (receipt.event_id, balances)
+    }
+
+    #[test]
+    fn atomic_batch_with_a_rejected_command_changes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ledger = Ledger::new(dir.path());
+        ledger.execute(BatchMode::Atomic, vec![create(1), deposit(1, 50)]);
+
+        let results = ledger.execute(
+            BatchMode::Atomic,
+            vec![deposit(1, 10), withdraw(1, 100), create(2)],
+        );
+
+        assert!(matches!(results[0], BatchItemResult::NotApplied));
+        assert!(matches!(
+            results[1],
+            BatchItemResult::Rejected(BalanceError::InsufficientFunds {
+                balance: 60,
+                amount: 100
+            })
+        ));
+        assert!(matches!(results[2], BatchItemResult::NotApplied));
+        assert_eq!(ledger.balance_event_repository.last_event_id(), 2);
+        assert_eq!(ledger.amount(1), Some(50));
+        assert_eq!(ledger.amount(2), None);
+        assert_eq!(ledger.transaction.starts.load(Ordering::Relaxed), 1);
+
+        // the event sequence did not move either
+        let results = ledger.execute(BatchMode::Atomic, vec![create(2)]);
+        assert_eq!(receipt(&results[0]), (3, vec![(2, 0)]));
+    }
+
+    #[test]
+    fn independent_batch_applies_the_accepted_commands_in_one_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ledger = Ledger::new(dir.path());
+
+        let results = ledger.execute(
+            BatchMode::Independent,
+            vec![
+                create(1),
+                deposit(1, 100),
+                withdraw(2, 5),
+                create(1),
+                create(3),
+                transfer(1, 3, 40),
+            ],
+        );
+
+        assert_eq!(receipt(&results[0]), (1, vec![(1, 0)]));
+        assert_eq!(receipt(&results[1]), (2, vec![(1, 100)]));
+        assert!(matches!(
+            results[2],
+            BatchItemResult::Rejected(BalanceError::BalanceNotFound(2))
+        ));
+        assert!(matches!(
+            results[3],
+            BatchItemResult::Rejected(BalanceError::BalanceAlreadyExists(1))
+        ));
+        assert_eq!(receipt(&results[4]), (3, vec![(3, 0)]));
+        assert_eq!(receipt(&results[5]), (4, vec![(1, 60), (3, 40)]));
+        assert_eq!(ledger.transaction.starts.load(Ordering::Relaxed), 1);
+        assert_eq!(ledger.balance_event_repository.last_event_id(), 4);
+        assert_eq!(ledger.amount(1), Some(60));
+        assert_eq!(ledger.amount(3), Some(40));
+    }
+
+    #[test]
+    fn batch_without_an_accepted_command_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ledger = Ledger::new(dir.path());
+
+        let rejected = ledger.execute(BatchMode::Independent, vec![deposit(1, 10)]);
+        let empty = ledger.execute(BatchMode::Independent, vec![]);
+
+        assert!(matches!(
+            rejected[0],
+            BatchItemResult::Rejected(BalanceError::BalanceNotFound(1))
+        ));
+        assert!(empty.is_empty());
+        assert_eq!(ledger.transaction.starts.load(Ordering::Relaxed), 0);
+        assert_eq!(ledger.balance_event_repository.last_event_id(), 0);
+    }
+}
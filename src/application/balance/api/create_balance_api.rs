@@ -2,8 +2,13 @@ use std::{cell::RefCell, rc::Rc, sync::Arc};
 
 use crate::{
     application::{
-        balance::spi::{
-            balance_event_repository::BalanceEventRepository, balance_repository::BalanceRepository,
+        balance::{
+            api::ledger_command_api::{CommandId, CommandReceipt, CommandResult},
+            spi::{
+                balance_event_repository::BalanceEventRepository,
+                balance_repository::BalanceRepository,
+                command_result_repository::CommandResultRepository,
+            },
         },
         transaction_spi::{Transaction, TransactionContext},
     },
@@ -17,14 +22,19 @@ use crate::{
     },
 };
 
-pub type CreateBalanceResponse = Result<BalanceId, BalanceError>;
+pub type CreateBalanceResponse = Result<CommandReceipt, BalanceError>;
 pub struct CreateBalanceCommand {
     pub id: BalanceId,
+    /// Set for commands that must be applied at most once, see `IdempotentLedgerCommand`.
+    pub command_id: Option<CommandId>,
 }
 
 impl CreateBalanceCommand {
     pub fn new(id: BalanceId) -> Self {
-        Self { id }
+        Self {
+            id,
+            command_id: None,
+        }
     }
 }
 
@@ -34,6 +44,7 @@ pub struct CreateBalanceApi {
     pub transaction: Arc<dyn Transaction>,
     pub balance_repository: Arc<dyn BalanceRepository>,
     pub balance_event_repository: Arc<dyn BalanceEventRepository>,
+    pub command_result_repository: Arc<dyn CommandResultRepository>,
 }
 
 impl CreateBalanceApi {
@@ -58,12 +69,28 @@ impl CreateBalanceApi {
             },
             transaction_context.clone(),
         );
-        self.balance_event_repository.persist_in_transaction(
+        let event_id = self.balance_event_repository.persist_in_transaction(
             BalanceEventType::BalanceCreated,
             BalanceCreatedEvent { id: command.id }.bytes(),
             transaction_context.clone(),
         );
+        let receipt = CommandReceipt {
+            event_id,
+            balances: vec![Balance {
+                id: command.id,
+                amount: 0,
+            }],
+        };
+        if let Some(command_id) = &command.command_id {
+            self.command_result_repository.persist_in_transaction(
+                command_id,
+                &CommandResult::Applied {
+                    receipt: Some(receipt.clone()),
+                },
+                transaction_context.clone(),
+            );
+        }
         transaction_context.commit();
-        Ok(command.id)
+        Ok(receipt)
     }
 }
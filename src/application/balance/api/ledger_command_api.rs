@@ -0,0 +1,98 @@
+use std::{rc::Rc, sync::Arc};
+
+use bincode::{Decode, Encode};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    application::{
+        balance::{
+            api::{
+                create_balance_api::CreateBalanceCommand,
+                deposit_balance_api::DepositBalanceCommand,
+                transfer_balance_api::TransferBalanceCommand,
+                withdraw_balance_api::WithdrawBalanceCommand,
+            },
+            spi::command_result_repository::CommandResultRepository,
+        },
+        transaction_spi::{Transaction, TransactionContext},
+    },
+    core::domain::{balance::Balance, balance_error::BalanceError, balance_event::EventId},
+};
+
+/// Chosen by the sender, unique per command; the same id sent again is a replay.
+pub type CommandId = String;
+
+/// What an applied command wrote: its event, and the balances it touched as they were right
+/// after it, so callers need no follow-up read that could see later commands.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, ToSchema)]
+pub struct CommandReceipt {
+    #[schema(value_type = u64)]
+    pub event_id: EventId,
+    pub balances: Vec<Balance>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CommandResult {
+    /// `receipt` is `None` for results stored before receipts were kept.
+    Applied { receipt: Option<CommandReceipt> },
+    /// Refused by the ledger, nothing was written but the result itself.
+    Rejected { message: String },
+}
+
+pub enum LedgerCommand {
+    Create(CreateBalanceCommand),
+    Deposit(DepositBalanceCommand),
+    Withdraw(WithdrawBalanceCommand),
+    Transfer(TransferBalanceCommand),
+}
+
+/// A command applied at most once: its result is committed with its effects, and a command
+/// whose id already has a result is answered from it.
+pub struct IdempotentLedgerCommand {
+    pub command_id: CommandId,
+    pub command: LedgerCommand,
+}
+
+/// Gives up on a command the ledger failed to apply: `message` becomes its stored rejection,
+/// so the command is answered the same way when it is sent again.
+pub struct RejectLedgerCommand {
+    pub command_id: CommandId,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct LedgerCommandOutcome {
+    pub result: CommandResult,
+    /// The command had been executed before, this is the stored result.
+    pub replayed: bool,
+}
+
+/// `Err` only for failures that did not store a result; the command can be sent again.
+pub type LedgerCommandResponse = Result<LedgerCommandOutcome, BalanceError>;
+
+#[derive(Clone)]
+pub struct LedgerCommandApi {
+    pub transaction: Arc<dyn Transaction>,
+    pub command_result_repository: Arc<dyn CommandResultRepository>,
+}
+
+impl LedgerCommandApi {
+    pub fn find_result(&self, command_id: &CommandId) -> Option<CommandResult> {
+        self.command_result_repository.get(command_id)
+    }
+
+    /// A rejected command wrote nothing, its result is committed on its own.
+    pub fn record_rejection(&self, command_id: &CommandId, message: String) -> CommandResult {
+        let result = CommandResult::Rejected { message };
+        let transaction_context: Rc<dyn TransactionContext> = self.transaction.start();
+        self.command_result_repository.persist_in_transaction(
+            command_id,
+            &result,
+            transaction_context.clone(),
+        );
+        transaction_context.commit();
+        result
+    }
+}
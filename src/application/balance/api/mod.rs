@@ -1,7 +1,10 @@
 pub mod balance_api;
 pub mod balance_event_api;
+pub mod balance_list_api;
 pub mod balance_query_api;
+pub mod batch_balance_api;
 pub mod create_balance_api;
 pub mod deposit_balance_api;
+pub mod ledger_command_api;
 pub mod transfer_balance_api;
 pub mod withdraw_balance_api;
@@ -2,8 +2,13 @@ use std::{cell::RefCell, rc::Rc, sync::Arc};
 
 use crate::{
     application::{
-        balance::spi::{
-            balance_event_repository::BalanceEventRepository, balance_repository::BalanceRepository,
+        balance::{
+            api::ledger_command_api::{CommandId, CommandReceipt, CommandResult},
+            spi::{
+                balance_event_repository::BalanceEventRepository,
+                balance_repository::BalanceRepository,
+                command_result_repository::CommandResultRepository,
+            },
         },
         transaction_spi::{Transaction, TransactionContext},
     },
@@ -17,11 +22,13 @@ use crate::{
     },
 };
 
-pub type TransferBalanceResponse = Result<Void, BalanceError>;
+pub type TransferBalanceResponse = Result<CommandReceipt, BalanceError>;
 pub struct TransferBalanceCommand {
     pub from_id: BalanceId,
     pub to_id: BalanceId,
     pub amount: BalanceAmount,
+    /// Set for commands that must be applied at most once, see `IdempotentLedgerCommand`.
+    pub command_id: Option<CommandId>,
 }
 
 impl TransferBalanceCommand {
@@ -30,6 +37,7 @@ impl TransferBalanceCommand {
             from_id,
             to_id,
             amount,
+            command_id: None,
         }
     }
 }
@@ -40,6 +48,7 @@ pub struct TransferBalanceApi {
     pub transaction: Arc<dyn Transaction>,
     pub balance_repository: Arc<dyn BalanceRepository>,
     pub balance_event_repository: Arc<dyn BalanceEventRepository>,
+    pub command_result_repository: Arc<dyn CommandResultRepository>,
 }
 
 impl TransferBalanceApi {
@@ -66,7 +75,7 @@ impl TransferBalanceApi {
             .persist_in_transaction(from_balance.clone(), transaction_context.clone());
         self.balance_repository
             .persist_in_transaction(to_balance.clone(), transaction_context.clone());
-        self.balance_event_repository.persist_in_transaction(
+        let event_id = self.balance_event_repository.persist_in_transaction(
             BalanceEventType::BalanceTransferred,
             BalanceTransferredEvent {
                 from_id: command.from_id,
@@ -76,7 +85,20 @@ impl TransferBalanceApi {
             .bytes(),
             transaction_context.clone(),
         );
+        let receipt = CommandReceipt {
+            event_id,
+            balances: vec![from_balance.clone(), to_balance.clone()],
+        };
+        if let Some(command_id) = &command.command_id {
+            self.command_result_repository.persist_in_transaction(
+                command_id,
+                &CommandResult::Applied {
+                    receipt: Some(receipt.clone()),
+                },
+                transaction_context.clone(),
+            );
+        }
         transaction_context.commit();
-        Ok(())
+        Ok(receipt)
     }
 }
@@ -2,8 +2,13 @@ use std::{cell::RefCell, rc::Rc, sync::Arc};
 
 use crate::{
     application::{
-        balance::spi::{
-            balance_event_repository::BalanceEventRepository, balance_repository::BalanceRepository,
+        balance::{
+            api::ledger_command_api::{CommandId, CommandReceipt, CommandResult},
+            spi::{
+                balance_event_repository::BalanceEventRepository,
+                balance_repository::BalanceRepository,
+                command_result_repository::CommandResultRepository,
+            },
         },
         transaction_spi::{Transaction, TransactionContext},
     },
@@ -17,15 +22,21 @@ use crate::{
     },
 };
 
-pub type WithdrawBalanceResponse = Result<Void, BalanceError>;
+pub type WithdrawBalanceResponse = Result<CommandReceipt, BalanceError>;
 pub struct WithdrawBalanceCommand {
     pub id: BalanceId,
     pub amount: BalanceAmount,
+    /// Set for commands that must be applied at most once, see `IdempotentLedgerCommand`.
+    pub command_id: Option<CommandId>,
 }
 
 impl WithdrawBalanceCommand {
     pub fn new(id: BalanceId, amount: BalanceAmount) -> Self {
-        Self { id, amount }
+        Self {
+            id,
+            amount,
+            command_id: None,
+        }
     }
 }
 
@@ -35,6 +46,7 @@ pub struct WithdrawBalanceApi {
     pub transaction: Arc<dyn Transaction>,
     pub balance_repository: Arc<dyn BalanceRepository>,
     pub balance_event_repository: Arc<dyn BalanceEventRepository>,
+    pub command_result_repository: Arc<dyn CommandResultRepository>,
 }
 
 impl WithdrawBalanceApi {
@@ -58,7 +70,7 @@ impl WithdrawBalanceApi {
         let balance = balances_guard.get_balance(command.id).unwrap();
         self.balance_repository
             .persist_in_transaction(balance.clone(), transaction_context.clone());
-        self.balance_event_repository.persist_in_transaction(
+        let event_id = self.balance_event_repository.persist_in_transaction(
             BalanceEventType::BalanceWithdrawn,
             BalanceWithdrawnEvent {
                 id: command.id,
@@ -67,7 +79,20 @@ impl WithdrawBalanceApi {
             .bytes(),
             transaction_context.clone(),
         );
+        let receipt = CommandReceipt {
+            event_id,
+            balances: vec![balance.clone()],
+        };
+        if let Some(command_id) = &command.command_id {
+            self.command_result_repository.persist_in_transaction(
+                command_id,
+                &CommandResult::Applied {
+                    receipt: Some(receipt.clone()),
+                },
+                transaction_context.clone(),
+            );
+        }
         transaction_context.commit();
-        Ok(())
+        Ok(receipt)
     }
 }
@@ -1,10 +1,28 @@
-use std::rc::Rc;
+use std::{error::Error, fmt, rc::Rc};
 
 use crate::{
     application::transaction_spi::TransactionContext,
     core::domain::balance_event::{BalanceEvent, BalanceEventType, EventId},
 };
 
+/// A stored event that cannot be turned back into an event: its record is corrupt, or its
+/// payload cannot be upcast or decoded.
+#[derive(Debug)]
+pub struct UnreadableBalanceEvent {
+    pub id: EventId,
+    pub reason: String,
+}
+
+impl fmt::Display for UnreadableBalanceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Event {} is unreadable: {}", self.id, self.reason)
+    }
+}
+
+impl Error for UnreadableBalanceEvent {}
+
+pub type StoredBalanceEvent = Result<BalanceEvent, UnreadableBalanceEvent>;
+
 pub trait BalanceEventRepository: Send + Sync {
     fn persist_in_transaction(
         &self,
@@ -13,5 +31,10 @@ pub trait BalanceEventRepository: Send + Sync {
         transaction_context: Rc<dyn TransactionContext>,
     ) -> EventId;
 
-    fn read(&self, offset: u64, limit: u64) -> Vec<BalanceEvent>;
+    /// Committed events from `offset`, at most `limit`, in id order. An event that cannot be
+    /// read is returned as an error in its place, so callers can skip it and go on.
+    fn read(&self, offset: u64, limit: u64) -> Vec<StoredBalanceEvent>;
+
+    /// Id of the last committed event, `0` when there is none.
+    fn last_event_id(&self) -> EventId;
 }
@@ -5,7 +5,7 @@ use crate::{
     core::domain::balance::{Balance, BalanceId},
 };
 
-pub trait BalanceRepository {
+pub trait BalanceRepository: Send + Sync {
     fn persist_in_transaction(
         &self,
         balance: Balance,
@@ -13,4 +13,8 @@ pub trait BalanceRepository {
     );
     fn get(&self, id: BalanceId) -> Option<Balance>;
     fn load_all(&self) -> Vec<Balance>;
+
+    /// Committed balances with ids greater than `after`, in id order, read from one
+    /// point-in-time view of the store.
+    fn scan(&self, after: Option<BalanceId>) -> Box<dyn Iterator<Item = Balance> + '_>;
 }
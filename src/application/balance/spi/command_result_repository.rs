@@ -0,0 +1,19 @@
+use std::rc::Rc;
+
+use crate::application::{
+    balance::api::ledger_command_api::{CommandId, CommandResult},
+    transaction_spi::TransactionContext,
+};
+
+/// Results of the commands sent with a command id, so a replayed command is answered
+/// instead of applied again.
+pub trait CommandResultRepository {
+    /// Stored with the balances and events of the command, in the same transaction.
+    fn persist_in_transaction(
+        &self,
+        command_id: &CommandId,
+        result: &CommandResult,
+        transaction_context: Rc<dyn TransactionContext>,
+    );
+    fn get(&self, command_id: &CommandId) -> Option<CommandResult>;
+}
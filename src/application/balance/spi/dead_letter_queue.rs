@@ -0,0 +1,21 @@
+use serde::Serialize;
+
+use crate::application::balance::{
+    api::balance_event_api::BalanceEventData, spi::event_sink::EventSinkFuture,
+};
+
+/// An event a sink refused, with the sink and its last error.
+#[derive(Debug, Serialize)]
+pub struct DeadLetter<'a> {
+    pub sink: &'a str,
+    pub reason: &'a str,
+    pub dead_lettered_at: u64,
+    pub event: &'a BalanceEventData,
+}
+
+/// Where the emitter parks events a sink keeps rejecting, so they stop blocking the sink.
+pub trait DeadLetterQueue: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn store<'a>(&'a self, dead_letter: &'a DeadLetter<'a>) -> EventSinkFuture<'a>;
+}
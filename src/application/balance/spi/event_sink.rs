@@ -0,0 +1,103 @@
+use std::{error::Error, fmt, future::Future, pin::Pin};
+
+use crate::{
+    application::balance::api::balance_event_api::BalanceEventData,
+    core::{common::types::Void, domain::balance_event::EventId},
+};
+
+#[derive(Debug)]
+pub enum EventSinkError {
+    DeliveryFailed(String),
+    /// Events up to `last_delivered` are acknowledged, the ones after it are not.
+    PartiallyDelivered {
+        last_delivered: EventId,
+        message: String,
+    },
+    /// The sink refuses `event_id` itself, so retrying it cannot succeed. The events before it
+    /// in the batch are delivered.
+    Rejected {
+        event_id: EventId,
+        message: String,
+    },
+    OffsetUnavailable(String),
+}
+
+impl EventSinkError {
+    /// Short name used as the key of the emitter error counters.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EventSinkError::DeliveryFailed(_) => "delivery_failed",
+            EventSinkError::PartiallyDelivered { .. } => "partially_delivered",
+            EventSinkError::Rejected { .. } => "rejected",
+            EventSinkError::OffsetUnavailable(_) => "offset_unavailable",
+        }
+    }
+}
+
+impl fmt::Display for EventSinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventSinkError::DeliveryFailed(message) => write!(f, "Delivery failed: {message}"),
+            EventSinkError::PartiallyDelivered {
+                last_delivered,
+                message,
+            } => write!(f, "Delivery failed after event {last_delivered}: {message}"),
+            EventSinkError::Rejected { event_id, message } => {
+                write!(f, "Event {event_id} rejected: {message}")
+            }
+            EventSinkError::OffsetUnavailable(message) => {
+                write!(f, "Offset unavailable: {message}")
+            }
+        }
+    }
+}
+
+impl Error for EventSinkError {}
+
+pub type EventSinkFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Void, EventSinkError>> + Send + 'a>>;
+
+/// Destination of committed balance events.
+///
+/// The emitter hands each sink the events after its offset, in id order, and advances the
+/// offset once `publish` succeeds. A failed batch is retried as a whole, or from the event
+/// after `last_delivered` when the sink reports `PartiallyDelivered`.
+pub trait EventSink: Send + Sync {
+    /// Stable name, also the key of the sink's persisted offset.
+    fn name(&self) -> &str;
+
+    fn publish<'a>(&'a self, events: &'a [BalanceEventData]) -> EventSinkFuture<'a>;
+
+    /// Sinks that commit their offset together with the delivery, such as Kafka transactions,
+    /// keep it themselves and are handed batches through `publish_with_offset`; the emitter
+    /// stores the offset of every other sink.
+    fn owns_offset(&self) -> bool {
+        false
+    }
+
+    /// Publishes `events` and moves the owned offset to `offset` in one step. `offset` is the
+    /// last event id the batch covers, which can be past the last event of `events` when the
+    /// events after it were filtered out; `events` can be empty to move the offset alone.
+    fn publish_with_offset<'a>(
+        &'a self,
+        _events: &'a [BalanceEventData],
+        _offset: EventId,
+    ) -> EventSinkFuture<'a> {
+        Box::pin(async move {
+            Err(EventSinkError::OffsetUnavailable(format!(
+                "{} does not own its offset",
+                self.name()
+            )))
+        })
+    }
+
+    /// `None` when the sink has not committed an offset yet. The emitter then starts from the
+    /// offset it stored for the subscription, so taking over the offset does not replay what
+    /// was already delivered.
+    fn read_owned_offset(&self) -> Result<Option<EventId>, EventSinkError> {
+        Err(EventSinkError::OffsetUnavailable(format!(
+            "{} does not own its offset",
+            self.name()
+        )))
+    }
+}
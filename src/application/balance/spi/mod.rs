@@ -1,2 +1,5 @@
 pub mod balance_event_repository;
 pub mod balance_repository;
+pub mod command_result_repository;
+pub mod dead_letter_queue;
+pub mod event_sink;
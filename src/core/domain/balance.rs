@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use bincode::{Decode, Encode};
 use serde::Serialize;
+use utoipa::ToSchema;
 
 use crate::core::{common::types::Void, domain::balance_error::BalanceError};
 
@@ -67,9 +68,11 @@ impl Balances {
     }
 }
 
-#[derive(Debug, Encode, Decode, Clone, Serialize)]
+#[derive(Debug, Encode, Decode, Clone, PartialEq, Eq, Serialize, ToSchema)]
 pub struct Balance {
+    #[schema(value_type = u64)]
     pub id: BalanceId,
+    #[schema(value_type = u128)]
     pub amount: BalanceAmount,
 }
 
@@ -14,6 +14,18 @@ pub enum BalanceError {
     UnknownError(String),
 }
 
+impl BalanceError {
+    /// Stable identifier of the error kind, for clients that must not parse messages.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BalanceError::BalanceAlreadyExists(_) => "balance_already_exists",
+            BalanceError::BalanceNotFound(_) => "balance_not_found",
+            BalanceError::InsufficientFunds { .. } => "insufficient_funds",
+            BalanceError::UnknownError(_) => "unknown_error",
+        }
+    }
+}
+
 impl fmt::Display for BalanceError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -1,11 +1,13 @@
 use bincode::{Decode, Encode, config};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::core::domain::balance::{BalanceAmount, BalanceId};
 
 pub type EventId = u64;
+pub type EventSchemaVersion = u16;
 
-#[derive(Debug, Encode, Decode, Clone, Serialize, Deserialize)]
+#[derive(Debug, Encode, Decode, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BalanceEventType {
     BalanceCreated,
     BalanceDeposited,
@@ -13,58 +15,147 @@ pub enum BalanceEventType {
     BalanceTransferred,
 }
 
+impl BalanceEventType {
+    /// Schema version of the payload currently written for this event type.
+    /// Bump it whenever the matching `Balance*Event` struct changes and register an upcaster.
+    pub fn schema_version(&self) -> EventSchemaVersion {
+        match self {
+            BalanceEventType::BalanceCreated => BalanceCreatedEvent::SCHEMA_VERSION,
+            BalanceEventType::BalanceDeposited => BalanceDepositedEvent::SCHEMA_VERSION,
+            BalanceEventType::BalanceWithdrawn => BalanceWithdrawnEvent::SCHEMA_VERSION,
+            BalanceEventType::BalanceTransferred => BalanceTransferredEvent::SCHEMA_VERSION,
+        }
+    }
+
+    /// Name used in Kafka headers, CloudEvents types and JSON Schema file names.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BalanceEventType::BalanceCreated => "balance_created",
+            BalanceEventType::BalanceDeposited => "balance_deposited",
+            BalanceEventType::BalanceWithdrawn => "balance_withdrawn",
+            BalanceEventType::BalanceTransferred => "balance_transferred",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "balance_created" => Some(BalanceEventType::BalanceCreated),
+            "balance_deposited" => Some(BalanceEventType::BalanceDeposited),
+            "balance_withdrawn" => Some(BalanceEventType::BalanceWithdrawn),
+            "balance_transferred" => Some(BalanceEventType::BalanceTransferred),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Encode, Decode)]
 pub struct BalanceEvent {
     pub id: EventId,
     pub event_type: BalanceEventType,
+    pub schema_version: EventSchemaVersion,
     pub event_time: u64,
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, Encode, Decode, Serialize)]
+#[derive(Debug, Encode, Decode, Clone, Serialize, Deserialize, ToSchema)]
 pub struct BalanceCreatedEvent {
+    #[schema(value_type = u64)]
     pub id: BalanceId,
 }
 
 impl BalanceCreatedEvent {
+    pub const SCHEMA_VERSION: EventSchemaVersion = 1;
+
     pub fn bytes(&self) -> Vec<u8> {
         bincode::encode_to_vec(self, config::standard()).unwrap()
     }
 }
 
-#[derive(Debug, Encode, Decode, Serialize)]
+#[derive(Debug, Encode, Decode, Clone, Serialize, Deserialize, ToSchema)]
 pub struct BalanceDepositedEvent {
+    #[schema(value_type = u64)]
     pub id: BalanceId,
+    #[schema(value_type = u128)]
     pub amount: BalanceAmount,
 }
 
 impl BalanceDepositedEvent {
+    pub const SCHEMA_VERSION: EventSchemaVersion = 1;
+
     pub fn bytes(&self) -> Vec<u8> {
         bincode::encode_to_vec(self, config::standard()).unwrap()
     }
 }
 
-#[derive(Debug, Encode, Decode, Serialize)]
+#[derive(Debug, Encode, Decode, Clone, Serialize, Deserialize, ToSchema)]
 pub struct BalanceWithdrawnEvent {
+    #[schema(value_type = u64)]
     pub id: BalanceId,
+    #[schema(value_type = u128)]
     pub amount: BalanceAmount,
 }
 
 impl BalanceWithdrawnEvent {
+    pub const SCHEMA_VERSION: EventSchemaVersion = 1;
+
     pub fn bytes(&self) -> Vec<u8> {
         bincode::encode_to_vec(self, config::standard()).unwrap()
     }
 }
 
-#[derive(Debug, Encode, Decode, Serialize)]
+#[derive(Debug, Encode, Decode, Clone, Serialize, Deserialize, ToSchema)]
 pub struct BalanceTransferredEvent {
+    #[schema(value_type = u64)]
     pub from_id: BalanceId,
+    #[schema(value_type = u64)]
     pub to_id: BalanceId,
+    #[schema(value_type = u128)]
     pub amount: BalanceAmount,
 }
 
 impl BalanceTransferredEvent {
+    pub const SCHEMA_VERSION: EventSchemaVersion = 1;
+
     pub fn bytes(&self) -> Vec<u8> {
         bincode::encode_to_vec(self, config::standard()).unwrap()
     }
 }
+
+/// Decoded event body, serialized as `"event_type": "...", "data": { ... }`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "event_type", content = "data")]
+pub enum BalanceEventPayload {
+    BalanceCreated(BalanceCreatedEvent),
+    BalanceDeposited(BalanceDepositedEvent),
+    BalanceWithdrawn(BalanceWithdrawnEvent),
+    BalanceTransferred(BalanceTransferredEvent),
+}
+
+impl BalanceEventPayload {
+    pub fn event_type(&self) -> BalanceEventType {
+        match self {
+            BalanceEventPayload::BalanceCreated(_) => BalanceEventType::BalanceCreated,
+            BalanceEventPayload::BalanceDeposited(_) => BalanceEventType::BalanceDeposited,
+            BalanceEventPayload::BalanceWithdrawn(_) => BalanceEventType::BalanceWithdrawn,
+            BalanceEventPayload::BalanceTransferred(_) => BalanceEventType::BalanceTransferred,
+        }
+    }
+
+    /// Balance the event belongs to, the source balance for a transfer.
+    pub fn balance_id(&self) -> BalanceId {
+        match self {
+            BalanceEventPayload::BalanceCreated(event) => event.id,
+            BalanceEventPayload::BalanceDeposited(event) => event.id,
+            BalanceEventPayload::BalanceWithdrawn(event) => event.id,
+            BalanceEventPayload::BalanceTransferred(event) => event.from_id,
+        }
+    }
+
+    /// The other balance a transfer changes.
+    pub fn counterparty_balance_id(&self) -> Option<BalanceId> {
+        match self {
+            BalanceEventPayload::BalanceTransferred(event) => Some(event.to_id),
+            _ => None,
+        }
+    }
+}
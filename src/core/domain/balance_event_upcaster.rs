@@ -0,0 +1,275 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::core::domain::balance_event::{BalanceEvent, BalanceEventType, EventSchemaVersion};
+
+#[derive(Debug)]
+pub enum BalanceEventUpcastError {
+    MissingUpcaster {
+        event_type: BalanceEventType,
+        schema_version: EventSchemaVersion,
+    },
+    /// Written by a newer build than this one.
+    UnknownSchemaVersion {
+        event_type: BalanceEventType,
+        schema_version: EventSchemaVersion,
+    },
+    InvalidPayload(String),
+}
+
+impl fmt::Display for BalanceEventUpcastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalanceEventUpcastError::MissingUpcaster {
+                event_type,
+                schema_version,
+            } => write!(
+                f,
+                "No upcaster for {event_type:?} from schema version {schema_version}"
+            ),
+            BalanceEventUpcastError::UnknownSchemaVersion {
+                event_type,
+                schema_version,
+            } => write!(
+                f,
+                "Unknown schema version {schema_version} of {event_type:?}"
+            ),
+            BalanceEventUpcastError::InvalidPayload(message) => {
+                write!(f, "Invalid event payload: {message}")
+            }
+        }
+    }
+}
+
+impl Error for BalanceEventUpcastError {}
+
+/// Rewrites the payload of one event type from `source_version` to `source_version + 1`.
+///
+/// Example: when `BalanceDepositedEvent` gains a `memo`, keep the old struct as
+/// `BalanceDepositedEventV1`, bump `SCHEMA_VERSION` to 2 and register an upcaster that
+/// decodes the v1 payload and re-encodes it as the new struct with an empty memo.
+pub trait BalanceEventUpcaster: Send + Sync {
+    fn event_type(&self) -> BalanceEventType;
+    fn source_version(&self) -> EventSchemaVersion;
+    fn upcast(&self, data: &[u8]) -> Result<Vec<u8>, BalanceEventUpcastError>;
+}
+
+pub struct BalanceEventUpcasterChain {
+    upcasters: Vec<Box<dyn BalanceEventUpcaster>>,
+}
+
+impl Default for BalanceEventUpcasterChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BalanceEventUpcasterChain {
+    pub fn new() -> Self {
+        Self { upcasters: vec![] }
+    }
+
+    pub fn register(mut self, upcaster: Box<dyn BalanceEventUpcaster>) -> Self {
+        self.upcasters.push(upcaster);
+        self
+    }
+
+    /// Brings a stored event to the schema version currently written for its type.
+    pub fn upcast(&self, event: BalanceEvent) -> Result<BalanceEvent, BalanceEventUpcastError> {
+        let current_version = event.event_type.schema_version();
+        self.upcast_to(event, current_version)
+    }
+
+    /// Brings a stored event to `target_version`, one registered upcaster at a time.
+    pub fn upcast_to(
+        &self,
+        mut event: BalanceEvent,
+        target_version: EventSchemaVersion,
+    ) -> Result<BalanceEvent, BalanceEventUpcastError> {
+        if event.schema_version > target_version {
+            return Err(BalanceEventUpcastError::UnknownSchemaVersion {
+                event_type: event.event_type,
+                schema_version: event.schema_version,
+            });
+        }
+        while event.schema_version < target_version {
+            let upcaster = self
+                .upcasters
+                .iter()
+                .find(|upcaster| {
+                    upcaster.event_type() == event.event_type
+                        && upcaster.source_version() == event.schema_version
+                })
+                .ok_or(BalanceEventUpcastError::MissingUpcaster {
+                    event_type: event.event_type,
+                    schema_version: event.schema_version,
+                })?;
+            event.data = upcaster.upcast(&event.data)?;
+            event.schema_version += 1;
+        }
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode::{Decode, Encode, config};
+
+    use super::*;
+    use crate::core::domain::balance_event::BalanceDepositedEvent;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct BalanceDepositedEventV2 {
+        id: u64,
+        amount: u128,
+        memo: String,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct BalanceDepositedEventV3 {
+        id: u64,
+        amount: u128,
+        memo: String,
+        channel: String,
+    }
+
+    fn decode<T: Decode<()>>(data: &[u8]) -> Result<T, BalanceEventUpcastError> {
+        bincode::decode_from_slice(data, config::standard())
+            .map(|(event, _)| event)
+            .map_err(|error| BalanceEventUpcastError::InvalidPayload(error.to_string()))
+    }
+
+    /// v1 to v2: an empty memo.
+    struct DepositMemoUpcaster;
+
+    impl BalanceEventUpcaster for DepositMemoUpcaster {
+        fn event_type(&self) -> BalanceEventType {
+            BalanceEventType::BalanceDeposited
+        }
+
+        fn source_version(&self) -> EventSchemaVersion {
+            1
+        }
+
+        fn upcast(&self, data: &[u8]) -> Result<Vec<u8>, BalanceEventUpcastError> {
+            let event: BalanceDepositedEvent = decode(data)?;
+            let upcast = BalanceDepositedEventV2 {
+                id: event.id,
+                amount: event.amount,
+                memo: String::new(),
+            };
+            Ok(bincode::encode_to_vec(upcast, config::standard()).unwrap())
+        }
+    }
+
+    /// v2 to v3: deposits before channels came over REST.
+    struct DepositChannelUpcaster;
+
+    impl BalanceEventUpcaster for DepositChannelUpcaster {
+        fn event_type(&self) -> BalanceEventType {
+            BalanceEventType::BalanceDeposited
+        }
+
+        fn source_version(&self) -> EventSchemaVersion {
+            2
+        }
+
+        fn upcast(&self, data: &[u8]) -> Result<Vec<u8>, BalanceEventUpcastError> {
+            let event: BalanceDepositedEventV2 = decode(data)?;
+            let upcast = BalanceDepositedEventV3 {
+                id: event.id,
+                amount: event.amount,
+                memo: event.memo,
+                channel: "rest".to_string(),
+            };
+            Ok(bincode::encode_to_vec(upcast, config::standard()).unwrap())
+        }
+    }
+
+    fn deposited_v1() -> BalanceEvent {
+        BalanceEvent {
+            id: 7,
+            event_type: BalanceEventType::BalanceDeposited,
+            schema_version: 1,
+            event_time: 0,
+            data: BalanceDepositedEvent { id: 3, amount: 50 }.bytes(),
+        }
+    }
+
+    #[test]
+    fn upcaster_brings_a_v1_payload_to_v2() {
+        let chain = BalanceEventUpcasterChain::new().register(Box::new(DepositMemoUpcaster));
+
+        let event = chain.upcast_to(deposited_v1(), 2).unwrap();
+
+        assert_eq!(event.id, 7);
+        assert_eq!(event.schema_version, 2);
+        assert_eq!(
+            decode::<BalanceDepositedEventV2>(&event.data).unwrap(),
+            BalanceDepositedEventV2 {
+                id: 3,
+                amount: 50,
+                memo: String::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn upcasters_are_chained_in_version_order() {
+        // registration order does not matter, the source version picks the upcaster
+        let chain = BalanceEventUpcasterChain::new()
+            .register(Box::new(DepositChannelUpcaster))
+            .register(Box::new(DepositMemoUpcaster));
+
+        let event = chain.upcast_to(deposited_v1(), 3).unwrap();
+
+        assert_eq!(event.schema_version, 3);
+        assert_eq!(
+            decode::<BalanceDepositedEventV3>(&event.data).unwrap(),
+            BalanceDepositedEventV3 {
+                id: 3,
+                amount: 50,
+                memo: String::new(),
+                channel: "rest".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn current_version_is_left_as_is() {
+        let event = BalanceEventUpcasterChain::new()
+            .upcast(deposited_v1())
+            .unwrap();
+
+        assert_eq!(event.schema_version, 1);
+        assert_eq!(event.data, deposited_v1().data);
+    }
+
+    #[test]
+    fn missing_upcaster_is_an_error() {
+        let chain = BalanceEventUpcasterChain::new().register(Box::new(DepositMemoUpcaster));
+
+        let error = chain.upcast_to(deposited_v1(), 3).unwrap_err();
+
+        assert!(matches!(
+            error,
+            BalanceEventUpcastError::MissingUpcaster {
+                event_type: BalanceEventType::BalanceDeposited,
+                schema_version: 2,
+            }
+        ));
+    }
+
+    #[test]
+    fn future_version_is_an_error() {
+        let mut event = deposited_v1();
+        event.schema_version = 2;
+
+        let error = BalanceEventUpcasterChain::new().upcast(event).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Unknown schema version 2 of BalanceDeposited"
+        );
+    }
+}
@@ -1,3 +1,4 @@
 pub mod balance;
 pub mod balance_error;
 pub mod balance_event;
+pub mod balance_event_upcaster;
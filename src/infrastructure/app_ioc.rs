@@ -1,22 +1,51 @@
-use actix::{Actor, Addr};
+use actix::{Actor, Addr, Arbiter};
 use rust_rocksdb::{DBWithThreadMode, SingleThreaded};
-use std::sync::Arc;
+use std::{
+    env,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::Notify;
 
 use crate::{
-    application::balance::api::{balance_api::BalanceApi, balance_event_api::BalanceEventApi},
+    application::balance::{
+        api::{
+            balance_api::BalanceApi, balance_event_api::BalanceEventApi,
+            balance_list_api::BalanceListApi,
+        },
+        spi::balance_event_repository::BalanceEventRepository,
+    },
+    core::domain::balance_event_upcaster::BalanceEventUpcasterChain,
     infrastructure::{
         balance::{
             balance_config::new_db_single_threaded_mode,
             balance_event_repository_rocksdb::BalanceEventRepositoryRocksdb,
+            balance_event_repository_segment_log::BalanceEventRepositorySegmentLog,
             balance_repository_rocksdb::BalanceRepositoryRocksdb,
+            command_result_repository_rocksdb::CommandResultRepositoryRocksdb,
         },
+        event_log::{segment_log::SegmentLog, segment_log_config::SegmentLogConfig},
+        event_sink::event_subscription::EventSubscriptions,
+        event_stream::balance_event_stream::BalanceEventStream,
         rocksdb_transaction::RocksdbTransaction,
+        scheduler::{
+            balance_event_emitter_metrics::BalanceEventEmitterMetrics, job_registry::JobRegistry,
+        },
+        storage::storage_migrator::StorageMigrator,
     },
 };
 #[derive(Clone)]
 pub struct AppState {
     pub balance_api_addr: Arc<Addr<BalanceApi>>,
     pub balance_event_api: Arc<BalanceEventApi>,
+    pub balance_list_api: Arc<BalanceListApi>,
+    /// Notified whenever the ledger commits new events.
+    pub event_commit_notify: Arc<Notify>,
+    pub balance_event_emitter_metrics: Arc<BalanceEventEmitterMetrics>,
+    pub balance_event_subscriptions: Arc<EventSubscriptions>,
+    pub job_registry: Arc<JobRegistry>,
+    pub balance_event_stream: Arc<BalanceEventStream>,
+    /// Set when events are kept in the segment log, for its retention job.
+    pub event_log: Option<Arc<Mutex<SegmentLog>>>,
 }
 
 impl Default for AppState {
@@ -27,24 +56,76 @@ impl Default for AppState {
 
 impl AppState {
     pub fn new() -> Self {
-        let db: Arc<DBWithThreadMode<SingleThreaded>> = new_db_single_threaded_mode();
+        Self::with_db(new_db_single_threaded_mode())
+    }
+
+    /// On an already opened store, migrated first.
+    pub fn with_db(db: Arc<DBWithThreadMode<SingleThreaded>>) -> Self {
+        StorageMigrator::new(db.clone()).migrate_on_startup();
         let balance_repository = Arc::new(BalanceRepositoryRocksdb::new(db.clone()));
-        let balance_event_repository = Arc::new(BalanceEventRepositoryRocksdb::new(db.clone()));
-        let transaction = Arc::new(RocksdbTransaction::new(db.clone()));
-        let balance_api = BalanceApi::new(
-            transaction.clone(),
-            balance_event_repository.clone(),
-            balance_repository.clone(),
-        );
-        let balance_event_api = BalanceEventApi {
+        let (balance_event_repository, event_log) = Self::new_balance_event_repository(db.clone());
+        let command_result_repository = Arc::new(CommandResultRepositoryRocksdb::new(db.clone()));
+        let event_commit_notify = Arc::new(Notify::new());
+        let event_stream_notify = Arc::new(Notify::new());
+        let transaction = Arc::new(RocksdbTransaction::new(
+            db.clone(),
+            vec![event_commit_notify.clone(), event_stream_notify.clone()],
+        ));
+        let balance_event_api = Arc::new(BalanceEventApi {
             balance_event_repository: balance_event_repository.clone(),
-        };
+            balance_event_upcaster_chain: BalanceEventUpcasterChain::new(),
+        });
+        let balance_event_stream =
+            BalanceEventStream::new(balance_event_api.clone(), event_stream_notify);
+        let balance_list_api = Arc::new(BalanceListApi {
+            balance_repository: balance_repository.clone(),
+        });
 
-        let balance_api_addr = balance_api.start();
+        // The ledger actor gets its own arbiter thread: every handler blocks on RocksDB writes,
+        // and the actor replies only after its write batch is committed.
+        let balance_arbiter = Arbiter::new();
+        let balance_api_addr = BalanceApi::start_in_arbiter(&balance_arbiter.handle(), move |_| {
+            BalanceApi::new(
+                transaction,
+                balance_event_repository,
+                balance_repository,
+                command_result_repository,
+            )
+        });
 
         Self {
             balance_api_addr: Arc::new(balance_api_addr),
-            balance_event_api: Arc::new(balance_event_api),
+            balance_event_api,
+            balance_list_api,
+            event_commit_notify,
+            balance_event_emitter_metrics: Arc::new(BalanceEventEmitterMetrics::default()),
+            balance_event_subscriptions: Arc::new(EventSubscriptions::from_env()),
+            job_registry: Arc::new(JobRegistry::default()),
+            balance_event_stream: Arc::new(balance_event_stream),
+            event_log,
+        }
+    }
+}
+
+impl AppState {
+    /// `BALANCE_EVENT_STORE` selects where events live: `rocksdb` (default) or `segment_log`.
+    /// Switching stores does not copy existing events. The segment log is returned as well.
+    fn new_balance_event_repository(
+        db: Arc<DBWithThreadMode<SingleThreaded>>,
+    ) -> (
+        Arc<dyn BalanceEventRepository>,
+        Option<Arc<Mutex<SegmentLog>>>,
+    ) {
+        let event_store = env::var("BALANCE_EVENT_STORE").unwrap_or("rocksdb".to_string());
+        match event_store.as_str() {
+            "segment_log" => {
+                let repository =
+                    BalanceEventRepositorySegmentLog::new(db, SegmentLogConfig::from_env());
+                let segment_log = repository.segment_log();
+                (Arc::new(repository), Some(segment_log))
+            }
+            "rocksdb" => (Arc::new(BalanceEventRepositoryRocksdb::new(db)), None),
+            other => panic!("Unknown BALANCE_EVENT_STORE: {other}"),
         }
     }
 }
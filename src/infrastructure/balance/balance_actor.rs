@@ -5,8 +5,12 @@ use crate::{
     application::balance::api::{
         balance_api::BalanceApi,
         balance_query_api::{BalanceQuery, BalanceResponse},
+        batch_balance_api::{BatchCommand, BatchResponse},
         create_balance_api::{CreateBalanceCommand, CreateBalanceResponse},
         deposit_balance_api::{DepositBalanceCommand, DepositBalanceResponse},
+        ledger_command_api::{
+            IdempotentLedgerCommand, LedgerCommand, LedgerCommandResponse, RejectLedgerCommand,
+        },
         transfer_balance_api::{TransferBalanceCommand, TransferBalanceResponse},
         withdraw_balance_api::{WithdrawBalanceCommand, WithdrawBalanceResponse},
     },
@@ -29,6 +33,22 @@ impl Message for TransferBalanceCommand {
     type Result = TransferBalanceResponse;
 }
 
+impl Message for IdempotentLedgerCommand {
+    type Result = LedgerCommandResponse;
+}
+
+impl Message for LedgerCommand {
+    type Result = LedgerCommandResponse;
+}
+
+impl Message for RejectLedgerCommand {
+    type Result = LedgerCommandResponse;
+}
+
+impl Message for BatchCommand {
+    type Result = BatchResponse;
+}
+
 impl Message for BalanceQuery {
     type Result = BalanceResponse;
 }
@@ -103,9 +123,66 @@ balance_handler!(
     transfer,
     "transfer balance error"
 );
+balance_handler!(
+    IdempotentLedgerCommand,
+    LedgerCommandResponse,
+    execute,
+    "ledger command error"
+);
+balance_handler!(
+    LedgerCommand,
+    LedgerCommandResponse,
+    submit,
+    "ledger command error"
+);
+balance_handler!(
+    RejectLedgerCommand,
+    LedgerCommandResponse,
+    reject,
+    "ledger command rejection error"
+);
+balance_handler!(
+    BatchCommand,
+    BatchResponse,
+    execute_batch,
+    "batch command error"
+);
 balance_handler!(
     BalanceQuery,
     BalanceResponse,
     get_balance,
     "balance query error"
 );
+
+#[cfg(test)]
+pub mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use actix::{ActorContext, Addr};
+
+    use super::*;
+    use crate::infrastructure::{
+        balance::{
+            balance_config::open_db,
+            balance_event_repository_rocksdb::BalanceEventRepositoryRocksdb,
+            balance_repository_rocksdb::BalanceRepositoryRocksdb,
+            command_result_repository_rocksdb::CommandResultRepositoryRocksdb,
+        },
+        rocksdb_transaction::RocksdbTransaction,
+    };
+
+    /// A ledger actor that stopped as it started: every message sent to it fails with
+    /// `MailboxError::Closed`.
+    pub fn stopped_balance_api(dir: &Path) -> Addr<BalanceApi> {
+        let db = open_db(dir);
+        BalanceApi::create(|ctx| {
+            ctx.stop();
+            BalanceApi::new(
+                Arc::new(RocksdbTransaction::new(db.clone(), vec![])),
+                Arc::new(BalanceEventRepositoryRocksdb::new(db.clone())),
+                Arc::new(BalanceRepositoryRocksdb::new(db.clone())),
+                Arc::new(CommandResultRepositoryRocksdb::new(db.clone())),
+            )
+        })
+    }
+}
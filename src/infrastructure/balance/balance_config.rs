@@ -1,16 +1,30 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use rust_rocksdb::{DB, DBWithThreadMode, Options, SingleThreaded};
 
 pub const DB_PATH: &str = "offheap/balance.db";
 pub const BALANCES_CF: &str = "balances";
 pub const EVENTS_CF: &str = "events";
+pub const META_CF: &str = "meta";
+pub const COMMAND_RESULTS_CF: &str = "command_results";
 pub const LAST_EVENT_ID: &str = "last_event_id";
+pub const SCHEMA_VERSION: &str = "schema_version";
 
 pub fn new_db_single_threaded_mode() -> Arc<DBWithThreadMode<SingleThreaded>> {
+    open_db(DB_PATH)
+}
+
+pub fn open_db(path: impl AsRef<Path>) -> Arc<DBWithThreadMode<SingleThreaded>> {
     let mut opts = Options::default();
     opts.create_if_missing(true);
     opts.create_missing_column_families(true);
 
-    Arc::new(DB::open_cf(&opts, DB_PATH, [BALANCES_CF, EVENTS_CF]).unwrap())
+    Arc::new(
+        DB::open_cf(
+            &opts,
+            path,
+            [BALANCES_CF, EVENTS_CF, META_CF, COMMAND_RESULTS_CF],
+        )
+        .unwrap(),
+    )
 }
@@ -1,29 +1,54 @@
-use std::{rc::Rc, sync::Arc};
+use std::{
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 
-use bincode::config;
 use chrono::Utc;
 use log::debug;
 use rust_rocksdb::{DBWithThreadMode, SingleThreaded};
 
 use crate::{
     application::{
-        balance::spi::balance_event_repository::BalanceEventRepository,
+        balance::spi::balance_event_repository::{
+            BalanceEventRepository, StoredBalanceEvent, UnreadableBalanceEvent,
+        },
         transaction_spi::TransactionContext,
     },
     core::domain::balance_event::{BalanceEvent, BalanceEventType, EventId},
     infrastructure::{
-        balance::balance_config::{EVENTS_CF, LAST_EVENT_ID},
+        balance::{
+            balance_config::EVENTS_CF,
+            balance_event_store::{
+                decode_balance_event, encode_balance_event, read_last_event_id,
+                rewind_event_sequence, stage_last_event_id,
+            },
+        },
         rocksdb_transaction::RocksdbTransactionContext,
     },
 };
 
 pub struct BalanceEventRepositoryRocksdb {
     db: Arc<DBWithThreadMode<SingleThreaded>>,
+    /// Last id handed out to a staged event. It runs ahead of `LAST_EVENT_ID` while a
+    /// transaction is open, so several events in one batch still get distinct ids, and is
+    /// rewound to it when the transaction does not commit.
+    event_sequence: Arc<AtomicU64>,
 }
 
 impl BalanceEventRepositoryRocksdb {
     pub fn new(db: Arc<DBWithThreadMode<SingleThreaded>>) -> Self {
-        Self { db }
+        let repository = Self {
+            db,
+            event_sequence: Arc::new(AtomicU64::new(0)),
+        };
+        let last_event_id = repository.last_event_id();
+        repository
+            .event_sequence
+            .store(last_event_id, Ordering::Release);
+        repository
     }
 }
 
@@ -34,58 +59,314 @@ impl BalanceEventRepository for BalanceEventRepositoryRocksdb {
         event_byte: Vec<u8>,
         transaction_context: Rc<dyn TransactionContext>,
     ) -> EventId {
-        let event_id = self.last_event_id() + 1;
+        let event_id = self.event_sequence.fetch_add(1, Ordering::AcqRel) + 1;
 
         let balance_event = BalanceEvent {
             id: event_id,
+            schema_version: event_type.schema_version(),
             event_type,
             data: event_byte,
             event_time: Utc::now().timestamp_nanos_opt().unwrap() as u64,
         };
 
         let txn_context = Rc::downcast::<RocksdbTransactionContext>(transaction_context).unwrap();
+        txn_context.on_abort(rewind_event_sequence(
+            self.db.clone(),
+            self.event_sequence.clone(),
+        ));
         let mut batch = txn_context.batch.borrow_mut();
-        let event_bytes = bincode::encode_to_vec(&balance_event, config::standard()).unwrap();
+        let event_bytes = encode_balance_event(&balance_event);
         let id_bytes = event_id.to_be_bytes();
         let cf: &rust_rocksdb::ColumnFamily = self.db.cf_handle(EVENTS_CF).unwrap();
         batch.put_cf(cf, id_bytes, event_bytes);
-        batch.put_cf(cf, LAST_EVENT_ID, event_id.to_be_bytes());
+        stage_last_event_id(&self.db, &mut batch, event_id);
 
         debug!("Saving event in transaction: {balance_event:?}");
 
         event_id
     }
 
-    fn read(&self, offset: u64, limit: u64) -> Vec<BalanceEvent> {
+    fn read(&self, offset: u64, limit: u64) -> Vec<StoredBalanceEvent> {
+        // bounded by the committed id, not `event_sequence`, so readers never see staged events
         let last_event_id = self.last_event_id();
-        let to_offset = (offset + limit - 1).min(last_event_id);
+        let to_offset = offset
+            .saturating_add(limit)
+            .saturating_sub(1)
+            .min(last_event_id);
 
         let cf: &rust_rocksdb::ColumnFamily = self.db.cf_handle(EVENTS_CF).unwrap();
-        let keys: Vec<_> = (offset..=to_offset).map(|key| key.to_be_bytes()).collect();
+        let ids: Vec<EventId> = (offset..=to_offset).collect();
+        let keys: Vec<_> = ids.iter().map(|id| id.to_be_bytes()).collect();
         let cf_keys = keys.iter().map(|key| (cf, key));
 
         let results = self.db.multi_get_cf(cf_keys);
 
-        results
-            .into_iter()
-            .filter_map(|result| {
-                result.ok()?.and_then(|bytes| {
-                    bincode::decode_from_slice(&bytes, config::standard())
-                        .ok()
-                        .map(|(balance_event, _)| balance_event)
-                })
+        ids.into_iter()
+            .zip(results)
+            .filter_map(|(id, result)| {
+                let unreadable = |reason: String| UnreadableBalanceEvent { id, reason };
+                match result {
+                    Ok(Some(bytes)) => Some(
+                        decode_balance_event(&bytes).map_err(|error| unreadable(error.to_string())),
+                    ),
+                    // stores written before the sequence was rolled back on failed commits can
+                    // have gaps in their ids; there is no event to report there
+                    Ok(None) => None,
+                    Err(error) => Some(Err(unreadable(error.to_string()))),
+                }
             })
             .collect()
     }
+
+    fn last_event_id(&self) -> EventId {
+        read_last_event_id(&self.db)
+    }
 }
 
-impl BalanceEventRepositoryRocksdb {
-    fn last_event_id(&self) -> u64 {
-        let last_event_id: Vec<u8> = self
-            .db
-            .get_cf(self.db.cf_handle(EVENTS_CF).unwrap(), LAST_EVENT_ID)
-            .unwrap()
-            .unwrap_or(0_u64.to_be_bytes().to_vec());
-        u64::from_be_bytes(last_event_id.try_into().unwrap())
+#[cfg(test)]
+pub mod tests {
+    use std::{cell::RefCell, panic, path::Path};
+
+    use rust_rocksdb::{DB, Options, WriteBatch};
+
+    use super::*;
+    use crate::{
+        application::{
+            balance::api::balance_event_api::BalanceEventApi, transaction_spi::Transaction,
+        },
+        core::domain::{
+            balance_event::BalanceCreatedEvent, balance_event_upcaster::BalanceEventUpcasterChain,
+        },
+        infrastructure::{
+            balance::balance_config::{BALANCES_CF, COMMAND_RESULTS_CF, META_CF, open_db},
+            rocksdb_transaction::RocksdbTransaction,
+        },
+    };
+
+    fn created_event(id: u64) -> Vec<u8> {
+        bincode::encode_to_vec(BalanceCreatedEvent { id }, bincode::config::standard()).unwrap()
+    }
+
+    pub fn persist_created_events(
+        db: &Arc<DBWithThreadMode<SingleThreaded>>,
+        repository: &BalanceEventRepositoryRocksdb,
+        count: u64,
+    ) {
+        let transaction = RocksdbTransaction::new(db.clone(), vec![]);
+        for balance_id in 1..=count {
+            let transaction_context = transaction.start();
+            repository.persist_in_transaction(
+                BalanceEventType::BalanceCreated,
+                created_event(balance_id),
+                transaction_context.clone(),
+            );
+            transaction_context.commit();
+        }
+    }
+
+    pub fn corrupt_event(db: &DBWithThreadMode<SingleThreaded>, event_id: EventId) {
+        let cf = db.cf_handle(EVENTS_CF).unwrap();
+        db.put_cf(cf, event_id.to_be_bytes(), [0xff, 0xff, 0x01])
+            .unwrap();
+    }
+
+    /// A store under `dir` with `count` committed events, the `unreadable` ones corrupted.
+    pub fn stored_events(
+        dir: &Path,
+        count: u64,
+        unreadable: &[EventId],
+    ) -> (
+        Arc<DBWithThreadMode<SingleThreaded>>,
+        Arc<BalanceEventRepositoryRocksdb>,
+        Arc<BalanceEventApi>,
+    ) {
+        let db = open_db(dir);
+        let repository = Arc::new(BalanceEventRepositoryRocksdb::new(db.clone()));
+        persist_created_events(&db, &repository, count);
+        for event_id in unreadable {
+            corrupt_event(&db, *event_id);
+        }
+        let balance_event_api = Arc::new(BalanceEventApi {
+            balance_event_repository: repository.clone(),
+            balance_event_upcaster_chain: BalanceEventUpcasterChain::new(),
+        });
+        (db, repository, balance_event_api)
+    }
+
+    #[test]
+    fn read_returns_an_unreadable_record_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(dir.path());
+        let repository = BalanceEventRepositoryRocksdb::new(db.clone());
+        persist_created_events(&db, &repository, 3);
+        corrupt_event(&db, 2);
+
+        let events = repository.read(1, 10);
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].as_ref().unwrap().id, 1);
+        assert_eq!(events[1].as_ref().unwrap_err().id, 2);
+        assert_eq!(events[2].as_ref().unwrap().id, 3);
+    }
+
+    #[test]
+    fn balance_event_api_keeps_the_events_around_an_unreadable_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(dir.path());
+        let repository = Arc::new(BalanceEventRepositoryRocksdb::new(db.clone()));
+        persist_created_events(&db, &repository, 3);
+        corrupt_event(&db, 1);
+        let balance_event_api = BalanceEventApi {
+            balance_event_repository: repository,
+            balance_event_upcaster_chain: BalanceEventUpcasterChain::new(),
+        };
+
+        let events = balance_event_api.get_balance_events(1, 10);
+
+        let ids: Vec<_> = events
+            .iter()
+            .map(|result| {
+                result
+                    .as_ref()
+                    .map(|event| event.id)
+                    .map_err(|error| error.id)
+            })
+            .collect();
+        assert_eq!(ids, vec![Err(1), Ok(2), Ok(3)]);
+    }
+
+    #[test]
+    fn event_of_a_future_schema_version_is_unreadable() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, _repository, balance_event_api) = stored_events(dir.path(), 3, &[]);
+        let future_event = BalanceEvent {
+            id: 2,
+            event_type: BalanceEventType::BalanceCreated,
+            schema_version: BalanceCreatedEvent::SCHEMA_VERSION + 1,
+            event_time: 0,
+            data: created_event(2),
+        };
+        let cf = db.cf_handle(EVENTS_CF).unwrap();
+        db.put_cf(cf, 2u64.to_be_bytes(), encode_balance_event(&future_event))
+            .unwrap();
+
+        let events = balance_event_api.get_balance_events(1, 10);
+
+        assert!(events[0].is_ok());
+        assert_eq!(
+            events[1].as_ref().unwrap_err().reason,
+            "Unknown schema version 2 of BalanceCreated"
+        );
+        assert!(events[2].is_ok());
+    }
+
+    fn stored_ids(repository: &BalanceEventRepositoryRocksdb) -> Vec<EventId> {
+        repository
+            .read(1, 10)
+            .into_iter()
+            .map(|result| result.unwrap().id)
+            .collect()
+    }
+
+    #[test]
+    fn read_to_the_end_of_the_id_range_does_not_overflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_db, repository, _) = stored_events(dir.path(), 3, &[]);
+
+        let ids = |offset, limit| -> Vec<EventId> {
+            repository
+                .read(offset, limit)
+                .into_iter()
+                .map(|result| result.unwrap().id)
+                .collect()
+        };
+        assert_eq!(ids(2, u64::MAX), vec![2, 3]);
+        assert_eq!(ids(u64::MAX, u64::MAX), Vec::<EventId>::new());
+        assert_eq!(ids(1, 0), Vec::<EventId>::new());
+    }
+
+    #[test]
+    fn events_of_one_transaction_get_consecutive_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(dir.path());
+        let repository = BalanceEventRepositoryRocksdb::new(db.clone());
+        let transaction_context = RocksdbTransaction::new(db.clone(), vec![]).start();
+
+        let event_ids: Vec<_> = (1..=3)
+            .map(|balance_id| {
+                repository.persist_in_transaction(
+                    BalanceEventType::BalanceCreated,
+                    created_event(balance_id),
+                    transaction_context.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(repository.last_event_id(), 0);
+        transaction_context.commit();
+
+        assert_eq!(event_ids, vec![1, 2, 3]);
+        assert_eq!(repository.last_event_id(), 3);
+        assert_eq!(stored_ids(&repository), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn failed_commit_gives_the_staged_ids_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(dir.path());
+        let repository = BalanceEventRepositoryRocksdb::new(db.clone());
+        persist_created_events(&db, &repository, 1);
+
+        // a read-only handle on the same store refuses every write
+        let read_only_db = DB::open_cf_for_read_only(
+            &Options::default(),
+            dir.path(),
+            [BALANCES_CF, EVENTS_CF, META_CF, COMMAND_RESULTS_CF],
+            false,
+        )
+        .unwrap();
+        let failing_context = Rc::new(RocksdbTransactionContext {
+            batch: Rc::new(RefCell::new(WriteBatch::default())),
+            db: Arc::new(read_only_db),
+            commit_notifies: Arc::new(vec![]),
+            abort_hooks: RefCell::new(vec![]),
+        });
+        for balance_id in 2..=3 {
+            repository.persist_in_transaction(
+                BalanceEventType::BalanceCreated,
+                created_event(balance_id),
+                failing_context.clone(),
+            );
+        }
+        let commit = panic::catch_unwind(panic::AssertUnwindSafe(|| failing_context.commit()));
+        assert!(commit.is_err());
+
+        persist_created_events(&db, &repository, 1);
+        assert_eq!(repository.last_event_id(), 2);
+        assert_eq!(stored_ids(&repository), vec![1, 2]);
+    }
+
+    #[test]
+    fn uncommitted_transaction_gives_the_staged_ids_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(dir.path());
+        let repository = BalanceEventRepositoryRocksdb::new(db.clone());
+        let transaction = RocksdbTransaction::new(db.clone(), vec![]);
+
+        let abandoned_context = transaction.start();
+        repository.persist_in_transaction(
+            BalanceEventType::BalanceCreated,
+            created_event(1),
+            abandoned_context.clone(),
+        );
+        drop(abandoned_context);
+
+        let transaction_context = transaction.start();
+        let event_id = repository.persist_in_transaction(
+            BalanceEventType::BalanceCreated,
+            created_event(1),
+            transaction_context.clone(),
+        );
+        transaction_context.commit();
+        assert_eq!(event_id, 1);
     }
 }
@@ -0,0 +1,257 @@
+use std::{
+    rc::Rc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use chrono::Utc;
+use log::{debug, error};
+use rust_rocksdb::{DBWithThreadMode, SingleThreaded};
+
+use crate::{
+    application::{
+        balance::spi::balance_event_repository::{
+            BalanceEventRepository, StoredBalanceEvent, UnreadableBalanceEvent,
+        },
+        transaction_spi::TransactionContext,
+    },
+    core::domain::balance_event::{BalanceEvent, BalanceEventType, EventId},
+    infrastructure::{
+        balance::balance_event_store::{
+            decode_balance_event, encode_balance_event, read_last_event_id, rewind_event_sequence,
+            stage_last_event_id,
+        },
+        event_log::{
+            segment_log::{SegmentLog, read_frames},
+            segment_log_config::SegmentLogConfig,
+        },
+        rocksdb_transaction::RocksdbTransactionContext,
+    },
+};
+
+/// Event store backed by an append-only segment log.
+///
+/// The event is appended to the log before the balances batch commits, and the batch carries
+/// `LAST_EVENT_ID` as the commit marker. Reads stop at the marker. When the batch does not
+/// commit, and on startup after a crash, the log is truncated back to the marker, so events
+/// and balances are durable together.
+pub struct BalanceEventRepositorySegmentLog {
+    db: Arc<DBWithThreadMode<SingleThreaded>>,
+    segment_log: Arc<Mutex<SegmentLog>>,
+    event_sequence: Arc<AtomicU64>,
+}
+
+impl BalanceEventRepositorySegmentLog {
+    pub fn new(db: Arc<DBWithThreadMode<SingleThreaded>>, config: SegmentLogConfig) -> Self {
+        let last_event_id = read_last_event_id(&db);
+        let segment_log = SegmentLog::open(config, last_event_id).unwrap();
+        Self {
+            db,
+            segment_log: Arc::new(Mutex::new(segment_log)),
+            event_sequence: Arc::new(AtomicU64::new(last_event_id)),
+        }
+    }
+
+    /// Shared with the retention job.
+    pub fn segment_log(&self) -> Arc<Mutex<SegmentLog>> {
+        self.segment_log.clone()
+    }
+}
+
+impl BalanceEventRepository for BalanceEventRepositorySegmentLog {
+    fn persist_in_transaction(
+        &self,
+        event_type: BalanceEventType,
+        event_byte: Vec<u8>,
+        transaction_context: Rc<dyn TransactionContext>,
+    ) -> EventId {
+        let event_id = self.event_sequence.fetch_add(1, Ordering::AcqRel) + 1;
+
+        let balance_event = BalanceEvent {
+            id: event_id,
+            schema_version: event_type.schema_version(),
+            event_type,
+            data: event_byte,
+            event_time: Utc::now().timestamp_nanos_opt().unwrap() as u64,
+        };
+
+        self.segment_log
+            .lock()
+            .unwrap()
+            .append(event_id, &encode_balance_event(&balance_event))
+            .unwrap();
+
+        let txn_context = Rc::downcast::<RocksdbTransactionContext>(transaction_context).unwrap();
+        txn_context.on_abort(rewind_event_sequence(
+            self.db.clone(),
+            self.event_sequence.clone(),
+        ));
+        let (db, segment_log) = (self.db.clone(), self.segment_log.clone());
+        txn_context.on_abort(move || {
+            let committed_id = read_last_event_id(&db);
+            if let Err(truncate_error) = segment_log.lock().unwrap().truncate_after(committed_id) {
+                // the next startup truncates it too
+                error!("Failed to truncate the event log after {committed_id}: {truncate_error}");
+            }
+        });
+        let mut batch = txn_context.batch.borrow_mut();
+        stage_last_event_id(&self.db, &mut batch, event_id);
+
+        debug!("Saving event in transaction: {balance_event:?}");
+
+        event_id
+    }
+
+    fn read(&self, offset: u64, limit: u64) -> Vec<StoredBalanceEvent> {
+        let last_event_id = self.last_event_id();
+        let to_offset = offset
+            .saturating_add(limit)
+            .saturating_sub(1)
+            .min(last_event_id);
+        if to_offset < offset {
+            return vec![];
+        }
+
+        let cursors = self.segment_log.lock().unwrap().cursors(offset);
+        read_frames(cursors, offset, to_offset)
+            .into_iter()
+            .map(|frame| match frame {
+                Ok(frame) => decode_balance_event(&frame.payload).map_err(|decode_error| {
+                    UnreadableBalanceEvent {
+                        id: frame.id,
+                        reason: decode_error.to_string(),
+                    }
+                }),
+                Err(corrupt_frame) => Err(UnreadableBalanceEvent {
+                    id: corrupt_frame.id,
+                    reason: "CRC mismatch".to_string(),
+                }),
+            })
+            .collect()
+    }
+
+    fn last_event_id(&self) -> EventId {
+        read_last_event_id(&self.db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, panic, path::Path};
+
+    use rust_rocksdb::{DB, Options, WriteBatch};
+
+    use super::*;
+    use crate::{
+        application::transaction_spi::Transaction,
+        core::domain::balance_event::BalanceCreatedEvent,
+        infrastructure::{
+            balance::balance_config::{
+                BALANCES_CF, COMMAND_RESULTS_CF, EVENTS_CF, META_CF, open_db,
+            },
+            rocksdb_transaction::RocksdbTransaction,
+        },
+    };
+
+    fn persist_created_event(
+        repository: &BalanceEventRepositorySegmentLog,
+        transaction_context: Rc<dyn TransactionContext>,
+    ) -> EventId {
+        let event =
+            bincode::encode_to_vec(BalanceCreatedEvent { id: 1 }, bincode::config::standard())
+                .unwrap();
+        repository.persist_in_transaction(
+            BalanceEventType::BalanceCreated,
+            event,
+            transaction_context,
+        )
+    }
+
+    fn segment_log_config(dir: &Path) -> SegmentLogConfig {
+        SegmentLogConfig {
+            dir: dir.join("events"),
+            segment_bytes: 64 << 20,
+            index_interval_bytes: 4096,
+            retention_segments: 0,
+            fsync: false,
+        }
+    }
+
+    fn stored_ids(repository: &BalanceEventRepositorySegmentLog) -> Vec<EventId> {
+        repository
+            .read(1, 10)
+            .into_iter()
+            .map(|result| result.unwrap().id)
+            .collect()
+    }
+
+    #[test]
+    fn failed_commit_truncates_the_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(dir.path().join("balance.db"));
+        let config = segment_log_config(dir.path());
+        let repository = BalanceEventRepositorySegmentLog::new(db.clone(), config.clone());
+        let transaction = RocksdbTransaction::new(db.clone(), vec![]);
+        let transaction_context = transaction.start();
+        persist_created_event(&repository, transaction_context.clone());
+        transaction_context.commit();
+
+        // a read-only handle on the same store refuses every write
+        let read_only_db = DB::open_cf_for_read_only(
+            &Options::default(),
+            dir.path().join("balance.db"),
+            [BALANCES_CF, EVENTS_CF, META_CF, COMMAND_RESULTS_CF],
+            false,
+        )
+        .unwrap();
+        let failing_context = Rc::new(RocksdbTransactionContext {
+            batch: Rc::new(RefCell::new(WriteBatch::default())),
+            db: Arc::new(read_only_db),
+            commit_notifies: Arc::new(vec![]),
+            abort_hooks: RefCell::new(vec![]),
+        });
+        persist_created_event(&repository, failing_context.clone());
+        let commit = panic::catch_unwind(panic::AssertUnwindSafe(|| failing_context.commit()));
+        assert!(commit.is_err());
+
+        let transaction_context = transaction.start();
+        assert_eq!(
+            persist_created_event(&repository, transaction_context.clone()),
+            2
+        );
+        transaction_context.commit();
+        assert_eq!(stored_ids(&repository), vec![1, 2]);
+
+        // the orphan frame of the failed commit is gone from disk too
+        drop(repository);
+        let repository = BalanceEventRepositorySegmentLog::new(db.clone(), config);
+        assert_eq!(stored_ids(&repository), vec![1, 2]);
+    }
+
+    #[test]
+    fn read_to_the_end_of_the_id_range_does_not_overflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(dir.path().join("balance.db"));
+        let repository =
+            BalanceEventRepositorySegmentLog::new(db.clone(), segment_log_config(dir.path()));
+        let transaction = RocksdbTransaction::new(db, vec![]);
+        for _ in 0..3 {
+            let transaction_context = transaction.start();
+            persist_created_event(&repository, transaction_context.clone());
+            transaction_context.commit();
+        }
+
+        let ids = |offset, limit| -> Vec<EventId> {
+            repository
+                .read(offset, limit)
+                .into_iter()
+                .map(|result| result.unwrap().id)
+                .collect()
+        };
+        assert_eq!(ids(2, u64::MAX), vec![2, 3]);
+        assert_eq!(ids(u64::MAX, u64::MAX), Vec::<EventId>::new());
+        assert_eq!(ids(1, 0), Vec::<EventId>::new());
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use bincode::Decode;
+use rust_rocksdb::{DBWithThreadMode, SingleThreaded, WriteBatch};
+
+use crate::{
+    core::domain::balance_event::{BalanceEvent, BalanceEventType, EventId},
+    infrastructure::{
+        balance::balance_config::{EVENTS_CF, LAST_EVENT_ID},
+        storage::record_format::{
+            RecordFormatError, RecordVersion, decode_payload, encode_record, split_header,
+        },
+    },
+};
+
+/// Version 2 added `BalanceEvent::schema_version`.
+const BALANCE_EVENT_RECORD_VERSION: RecordVersion = 2;
+const BALANCE_EVENT_RECORD_VERSION_V1: RecordVersion = 1;
+
+/// Layout of `BalanceEvent` before per-event-type schema versions; every payload of that era
+/// is schema version 1. Events are immutable, so these records are converted on read.
+#[derive(Decode)]
+struct BalanceEventRecordV1 {
+    id: EventId,
+    event_type: BalanceEventType,
+    event_time: u64,
+    data: Vec<u8>,
+}
+
+pub fn encode_balance_event(balance_event: &BalanceEvent) -> Vec<u8> {
+    encode_record(BALANCE_EVENT_RECORD_VERSION, balance_event)
+}
+
+pub fn decode_balance_event(bytes: &[u8]) -> Result<BalanceEvent, RecordFormatError> {
+    let (version, payload) = split_header(bytes)?;
+    match version {
+        BALANCE_EVENT_RECORD_VERSION => decode_payload(payload),
+        BALANCE_EVENT_RECORD_VERSION_V1 => {
+            let record: BalanceEventRecordV1 = decode_payload(payload)?;
+            Ok(BalanceEvent {
+                id: record.id,
+                event_type: record.event_type,
+                schema_version: 1,
+                event_time: record.event_time,
+                data: record.data,
+            })
+        }
+        version => Err(RecordFormatError::UnsupportedVersion(version)),
+    }
+}
+
+/// `LAST_EVENT_ID` is the commit marker of the event store: it is written in the same batch
+/// as the balances, and readers never go past it.
+pub fn read_last_event_id(db: &DBWithThreadMode<SingleThreaded>) -> EventId {
+    let last_event_id: Vec<u8> = db
+        .get_cf(db.cf_handle(EVENTS_CF).unwrap(), LAST_EVENT_ID)
+        .unwrap()
+        .unwrap_or(0_u64.to_be_bytes().to_vec());
+    EventId::from_be_bytes(last_event_id.try_into().unwrap())
+}
+
+pub fn stage_last_event_id(
+    db: &DBWithThreadMode<SingleThreaded>,
+    batch: &mut WriteBatch,
+    event_id: EventId,
+) {
+    batch.put_cf(
+        db.cf_handle(EVENTS_CF).unwrap(),
+        LAST_EVENT_ID,
+        event_id.to_be_bytes(),
+    );
+}
+
+/// Abort hook of a transaction that staged events: ids handed out since the last commit go
+/// back to the sequence, so a failed commit leaves no gap in the event ids.
+pub fn rewind_event_sequence(
+    db: Arc<DBWithThreadMode<SingleThreaded>>,
+    event_sequence: Arc<AtomicU64>,
+) -> impl FnOnce() {
+    move || event_sequence.store(read_last_event_id(&db), Ordering::Release)
+}
@@ -1,8 +1,7 @@
 use std::{rc::Rc, sync::Arc};
 
-use bincode::config;
 use log::info;
-use rust_rocksdb::{DBWithThreadMode, SingleThreaded};
+use rust_rocksdb::{DBWithThreadMode, Direction, IteratorMode, SingleThreaded};
 
 use crate::{
     application::{
@@ -10,10 +9,16 @@ use crate::{
     },
     core::domain::balance::{Balance, BalanceId},
     infrastructure::{
-        balance::balance_config::BALANCES_CF, rocksdb_transaction::RocksdbTransactionContext,
+        balance::balance_config::BALANCES_CF,
+        rocksdb_transaction::RocksdbTransactionContext,
+        storage::record_format::{
+            RecordFormatError, RecordVersion, decode_payload, encode_record, split_header,
+        },
     },
 };
 
+const BALANCE_RECORD_VERSION: RecordVersion = 1;
+
 pub struct BalanceRepositoryRocksdb {
     db: Arc<DBWithThreadMode<SingleThreaded>>,
 }
@@ -30,7 +35,7 @@ impl BalanceRepository for BalanceRepositoryRocksdb {
         balance: Balance,
         transaction_context: Rc<dyn TransactionContext>,
     ) {
-        let balance_bytes = bincode::encode_to_vec(&balance, config::standard()).unwrap();
+        let balance_bytes = encode_balance(&balance);
         let id_bytes = balance.id.to_be_bytes();
         let txn_context = Rc::downcast::<RocksdbTransactionContext>(transaction_context).unwrap();
         let mut batch = txn_context.batch.borrow_mut();
@@ -42,27 +47,60 @@ impl BalanceRepository for BalanceRepositoryRocksdb {
         let id_bytes = id.to_be_bytes();
         let cf: &rust_rocksdb::ColumnFamily = self.db.cf_handle(BALANCES_CF).unwrap();
         let balance_bytes: Option<Vec<u8>> = self.db.get_cf(cf, id_bytes).unwrap();
-        match balance_bytes {
-            Some(bytes) => {
-                let (balance, _) = bincode::decode_from_slice(&bytes, config::standard()).unwrap();
-                Some(balance)
-            }
-            None => None,
-        }
+        balance_bytes.map(|bytes| {
+            decode_balance(&bytes)
+                .unwrap_or_else(|error| panic!("Failed to decode balance {id}: {error}"))
+        })
     }
 
     fn load_all(&self) -> Vec<Balance> {
         let mut balances = Vec::new();
         let cf: &rust_rocksdb::ColumnFamily = self.db.cf_handle(BALANCES_CF).unwrap();
-        let iter = self.db.iterator_cf(cf, rust_rocksdb::IteratorMode::Start);
+        let iter = self.db.iterator_cf(cf, IteratorMode::Start);
 
         for result in iter {
-            let (_, value) = result.unwrap();
-            let (balance, _) = bincode::decode_from_slice(&value, config::standard()).unwrap();
+            let (key, value) = result.unwrap();
+            let balance = decode_balance(&value).unwrap_or_else(|error| {
+                panic!("Failed to decode balance {key:?}: {error}, check storage migrations")
+            });
             balances.push(balance);
         }
 
         info!("load all balances: {:?}", balances.len());
         balances
     }
+
+    fn scan(&self, after: Option<BalanceId>) -> Box<dyn Iterator<Item = Balance> + '_> {
+        let start = match after {
+            Some(after) => match after.checked_add(1) {
+                Some(start) => start,
+                None => return Box::new(std::iter::empty()),
+            },
+            None => 0,
+        };
+        let start_bytes = start.to_be_bytes();
+        let cf: &rust_rocksdb::ColumnFamily = self.db.cf_handle(BALANCES_CF).unwrap();
+        // ids are stored big-endian, so key order is id order
+        let iter = self
+            .db
+            .iterator_cf(cf, IteratorMode::From(&start_bytes, Direction::Forward));
+        Box::new(iter.map(|result| {
+            let (key, value) = result.unwrap();
+            decode_balance(&value).unwrap_or_else(|error| {
+                panic!("Failed to decode balance {key:?}: {error}, check storage migrations")
+            })
+        }))
+    }
+}
+
+fn encode_balance(balance: &Balance) -> Vec<u8> {
+    encode_record(BALANCE_RECORD_VERSION, balance)
+}
+
+fn decode_balance(bytes: &[u8]) -> Result<Balance, RecordFormatError> {
+    let (version, payload) = split_header(bytes)?;
+    match version {
+        BALANCE_RECORD_VERSION => decode_payload(payload),
+        version => Err(RecordFormatError::UnsupportedVersion(version)),
+    }
 }
@@ -0,0 +1,69 @@
+use std::{rc::Rc, sync::Arc};
+
+use rust_rocksdb::{DBWithThreadMode, SingleThreaded};
+
+use crate::{
+    application::{
+        balance::{
+            api::ledger_command_api::{CommandId, CommandResult},
+            spi::command_result_repository::CommandResultRepository,
+        },
+        transaction_spi::TransactionContext,
+    },
+    infrastructure::{
+        balance::balance_config::COMMAND_RESULTS_CF,
+        rocksdb_transaction::RocksdbTransactionContext,
+        storage::record_format::{
+            RecordFormatError, RecordVersion, decode_payload, encode_record, split_header,
+        },
+    },
+};
+
+const COMMAND_RESULT_RECORD_VERSION: RecordVersion = 1;
+
+/// Command results keyed by command id. They are kept forever, a command id is never reused.
+pub struct CommandResultRepositoryRocksdb {
+    db: Arc<DBWithThreadMode<SingleThreaded>>,
+}
+
+impl CommandResultRepositoryRocksdb {
+    pub fn new(db: Arc<DBWithThreadMode<SingleThreaded>>) -> Self {
+        Self { db }
+    }
+}
+
+impl CommandResultRepository for CommandResultRepositoryRocksdb {
+    fn persist_in_transaction(
+        &self,
+        command_id: &CommandId,
+        result: &CommandResult,
+        transaction_context: Rc<dyn TransactionContext>,
+    ) {
+        let txn_context = Rc::downcast::<RocksdbTransactionContext>(transaction_context).unwrap();
+        let mut batch = txn_context.batch.borrow_mut();
+        let cf: &rust_rocksdb::ColumnFamily = self.db.cf_handle(COMMAND_RESULTS_CF).unwrap();
+        batch.put_cf(
+            cf,
+            command_id.as_bytes(),
+            encode_record(COMMAND_RESULT_RECORD_VERSION, result),
+        );
+    }
+
+    fn get(&self, command_id: &CommandId) -> Option<CommandResult> {
+        let cf: &rust_rocksdb::ColumnFamily = self.db.cf_handle(COMMAND_RESULTS_CF).unwrap();
+        let result_bytes: Option<Vec<u8>> = self.db.get_cf(cf, command_id.as_bytes()).unwrap();
+        result_bytes.map(|bytes| {
+            decode_command_result(&bytes).unwrap_or_else(|error| {
+                panic!("Failed to decode result of command {command_id}: {error}")
+            })
+        })
+    }
+}
+
+fn decode_command_result(bytes: &[u8]) -> Result<CommandResult, RecordFormatError> {
+    let (version, payload) = split_header(bytes)?;
+    match version {
+        COMMAND_RESULT_RECORD_VERSION => decode_payload(payload),
+        version => Err(RecordFormatError::UnsupportedVersion(version)),
+    }
+}
@@ -1,4 +1,7 @@
 pub mod balance_actor;
 pub mod balance_config;
 pub mod balance_event_repository_rocksdb;
+pub mod balance_event_repository_segment_log;
+pub mod balance_event_store;
 pub mod balance_repository_rocksdb;
+pub mod command_result_repository_rocksdb;
@@ -0,0 +1,3 @@
+pub mod segment;
+pub mod segment_log;
+pub mod segment_log_config;
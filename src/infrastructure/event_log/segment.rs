@@ -0,0 +1,257 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, ErrorKind, Read},
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+
+/// Frame layout: `[payload length: u32][crc32 of id + payload: u32][id: u64][payload]`,
+/// all integers big-endian.
+pub const FRAME_HEADER_SIZE: usize = 16;
+const MAX_FRAME_PAYLOAD_SIZE: usize = 16 << 20;
+const SEGMENT_SUFFIX: &str = ".log";
+
+#[derive(Debug)]
+pub struct Frame {
+    pub id: u64,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn size(&self) -> u64 {
+        (FRAME_HEADER_SIZE + self.payload.len()) as u64
+    }
+}
+
+/// A complete frame whose CRC does not match its id and payload. The frames after it can
+/// still be read.
+#[derive(Debug)]
+pub struct CorruptFrame {
+    pub id: u64,
+    pub size: u64,
+}
+
+pub type FrameResult = Result<Frame, CorruptFrame>;
+
+pub fn encode_frame(id: u64, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(FRAME_HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&frame_crc(id, payload).to_be_bytes());
+    bytes.extend_from_slice(&id.to_be_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// Returns `Ok(None)` at the end of the segment, including a torn frame left by a crash.
+pub fn read_frame(reader: &mut impl Read) -> io::Result<Option<FrameResult>> {
+    let mut header = [0_u8; FRAME_HEADER_SIZE];
+    if !read_fully(reader, &mut header)? {
+        return Ok(None);
+    }
+    let len = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+    let crc = u32::from_be_bytes(header[4..8].try_into().unwrap());
+    let id = u64::from_be_bytes(header[8..16].try_into().unwrap());
+    if len > MAX_FRAME_PAYLOAD_SIZE {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Frame of event {id} is too large: {len} bytes"),
+        ));
+    }
+
+    let mut payload = vec![0_u8; len];
+    if !read_fully(reader, &mut payload)? {
+        return Ok(None);
+    }
+    if frame_crc(id, &payload) != crc {
+        return Ok(Some(Err(CorruptFrame {
+            id,
+            size: (FRAME_HEADER_SIZE + len) as u64,
+        })));
+    }
+    Ok(Some(Ok(Frame { id, payload })))
+}
+
+fn read_fully(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+fn frame_crc(id: u64, payload: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&id.to_be_bytes());
+    hasher.update(payload);
+    hasher.finalize()
+}
+
+pub fn segment_path(dir: &Path, base_id: u64) -> PathBuf {
+    dir.join(format!("{base_id:020}{SEGMENT_SUFFIX}"))
+}
+
+pub fn list_segment_base_ids(dir: &Path) -> io::Result<Vec<u64>> {
+    let mut base_ids: Vec<u64> = fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name();
+            file_name
+                .to_str()?
+                .strip_suffix(SEGMENT_SUFFIX)?
+                .parse::<u64>()
+                .ok()
+        })
+        .collect();
+    base_ids.sort_unstable();
+    Ok(base_ids)
+}
+
+/// One segment file holding the contiguous ids `base_id..=last_id`.
+///
+/// The sparse index keeps the file position of one frame every `index_interval_bytes`,
+/// so a read seeks to the closest indexed frame and scans forward from there.
+pub struct Segment {
+    pub base_id: u64,
+    pub path: PathBuf,
+    pub size: u64,
+    pub last_id: u64,
+    index: Vec<(u64, u64)>,
+    index_interval_bytes: u64,
+    bytes_since_index: u64,
+}
+
+impl Segment {
+    pub fn create(dir: &Path, base_id: u64, index_interval_bytes: u64) -> io::Result<Self> {
+        let path = segment_path(dir, base_id);
+        OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            base_id,
+            path,
+            size: 0,
+            last_id: base_id - 1,
+            index: vec![],
+            index_interval_bytes,
+            bytes_since_index: 0,
+        })
+    }
+
+    /// Rebuilds the index and drops everything after the last frame with an id up to
+    /// `committed_id`: torn writes and events whose balances never committed. A committed frame
+    /// with a CRC mismatch is kept, like `read_frames` returns it in place, so one bad frame
+    /// does not take the committed events after it.
+    pub fn recover(
+        dir: &Path,
+        base_id: u64,
+        committed_id: u64,
+        index_interval_bytes: u64,
+    ) -> io::Result<Self> {
+        let mut segment = Self::create(dir, base_id, index_interval_bytes)?;
+        let file = File::open(&segment.path)?;
+        let file_size = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        loop {
+            match read_frame(&mut reader) {
+                Ok(Some(Ok(frame))) if frame.id <= committed_id => {
+                    segment.on_append(frame.id, frame.size());
+                }
+                // the id can be the corrupt part, the position in the segment cannot
+                Ok(Some(Err(corrupt_frame))) if segment.last_id < committed_id => {
+                    warn!(
+                        "{}: CRC mismatch for event {}, kept",
+                        segment.path.display(),
+                        segment.last_id + 1
+                    );
+                    segment.on_append(segment.last_id + 1, corrupt_frame.size);
+                }
+                Ok(_) => break,
+                Err(error) if error.kind() == ErrorKind::InvalidData => {
+                    warn!("{}: {error}", segment.path.display());
+                    break;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        if file_size > segment.size {
+            warn!(
+                "{}: truncating {} uncommitted bytes",
+                segment.path.display(),
+                file_size - segment.size
+            );
+            OpenOptions::new()
+                .write(true)
+                .open(&segment.path)?
+                .set_len(segment.size)?;
+        }
+        Ok(segment)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last_id < self.base_id
+    }
+
+    pub fn on_append(&mut self, id: u64, frame_size: u64) {
+        if self.index.is_empty() || self.bytes_since_index >= self.index_interval_bytes {
+            self.index.push((id, self.size));
+            self.bytes_since_index = 0;
+        }
+        self.size += frame_size;
+        self.bytes_since_index += frame_size;
+        self.last_id = id;
+    }
+
+    /// File position of the closest indexed frame at or before `id`.
+    pub fn position_of(&self, id: u64) -> u64 {
+        let entry = self
+            .index
+            .partition_point(|(indexed_id, _)| *indexed_id <= id);
+        match entry {
+            0 => 0,
+            entry => self.index[entry - 1].1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn read_frame_returns_the_encoded_frame() {
+        let bytes = encode_frame(7, b"payload");
+
+        let frame = read_frame(&mut Cursor::new(bytes))
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(frame.id, 7);
+        assert_eq!(frame.payload, b"payload");
+    }
+
+    #[test]
+    fn read_frame_rejects_a_crc_mismatch() {
+        let mut bytes = encode_frame(7, b"payload");
+        *bytes.last_mut().unwrap() ^= 0xff;
+        let mut reader = Cursor::new(bytes);
+
+        let corrupt_frame = read_frame(&mut reader).unwrap().unwrap().unwrap_err();
+
+        assert_eq!(corrupt_frame.id, 7);
+        // the whole frame was consumed, the next one can be read
+        assert!(read_frame(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn read_frame_treats_a_torn_frame_as_the_end() {
+        let bytes = encode_frame(7, b"payload");
+
+        for len in [3, FRAME_HEADER_SIZE, bytes.len() - 1] {
+            let torn = &bytes[..len];
+            assert!(read_frame(&mut Cursor::new(torn)).unwrap().is_none());
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, ErrorKind, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+use log::{error, info};
+
+use crate::infrastructure::event_log::{
+    segment::{
+        FrameResult, Segment, encode_frame, list_segment_base_ids, read_frame, segment_path,
+    },
+    segment_log_config::SegmentLogConfig,
+};
+
+/// Where a read starts in one segment file.
+pub struct SegmentCursor {
+    pub path: PathBuf,
+    pub position: u64,
+}
+
+/// Append-only log of id-ordered records split into rolling segment files.
+///
+/// The log itself has no notion of commit: the caller owns the commit marker and passes it to
+/// `open`, which truncates anything written after it.
+pub struct SegmentLog {
+    config: SegmentLogConfig,
+    segments: Vec<Segment>,
+    active_file: File,
+}
+
+impl SegmentLog {
+    pub fn open(config: SegmentLogConfig, committed_id: u64) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+
+        let mut segments = vec![];
+        for base_id in list_segment_base_ids(&config.dir)? {
+            if base_id > committed_id {
+                // every record of this segment is past the commit marker
+                fs::remove_file(segment_path(&config.dir, base_id))?;
+                continue;
+            }
+            segments.push(Segment::recover(
+                &config.dir,
+                base_id,
+                committed_id,
+                config.index_interval_bytes,
+            )?);
+        }
+        if segments.is_empty() {
+            segments.push(Segment::create(
+                &config.dir,
+                committed_id + 1,
+                config.index_interval_bytes,
+            )?);
+        }
+
+        let active_file = Self::open_active(segments.last().unwrap())?;
+        info!(
+            "Opened event log {} with {} segments, last id: {}",
+            config.dir.display(),
+            segments.len(),
+            segments.last().unwrap().last_id
+        );
+        Ok(Self {
+            config,
+            segments,
+            active_file,
+        })
+    }
+
+    pub fn append(&mut self, id: u64, payload: &[u8]) -> io::Result<()> {
+        let active = self.segments.last().unwrap();
+        if active.size >= self.config.segment_bytes && !active.is_empty() {
+            self.roll(id)?;
+        }
+
+        let frame = encode_frame(id, payload);
+        self.active_file.write_all(&frame)?;
+        if self.config.fsync {
+            self.active_file.sync_data()?;
+        }
+        self.segments
+            .last_mut()
+            .unwrap()
+            .on_append(id, frame.len() as u64);
+        Ok(())
+    }
+
+    /// Drops every record after `committed_id`, like `open` does after a crash. Used when the
+    /// commit that should have covered the last appends fails.
+    pub fn truncate_after(&mut self, committed_id: u64) -> io::Result<()> {
+        while self.segments.len() > 1 && self.segments.last().unwrap().base_id > committed_id {
+            let segment = self.segments.pop().unwrap();
+            fs::remove_file(&segment.path)?;
+        }
+        let active = self.segments.last_mut().unwrap();
+        if active.last_id > committed_id {
+            *active = Segment::recover(
+                &self.config.dir,
+                active.base_id,
+                committed_id,
+                self.config.index_interval_bytes,
+            )?;
+        }
+        self.active_file = Self::open_active(self.segments.last().unwrap())?;
+        Ok(())
+    }
+
+    /// Cursors over the segments holding `from_id` and everything after it.
+    /// Ids removed by retention are skipped.
+    pub fn cursors(&self, from_id: u64) -> Vec<SegmentCursor> {
+        let first = self
+            .segments
+            .partition_point(|segment| segment.base_id <= from_id)
+            .saturating_sub(1);
+        self.segments[first..]
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| SegmentCursor {
+                path: segment.path.clone(),
+                position: if i == 0 {
+                    segment.position_of(from_id)
+                } else {
+                    0
+                },
+            })
+            .collect()
+    }
+
+    fn roll(&mut self, base_id: u64) -> io::Result<()> {
+        if self.config.fsync {
+            self.active_file.sync_all()?;
+        }
+        let segment = Segment::create(&self.config.dir, base_id, self.config.index_interval_bytes)?;
+        self.active_file = Self::open_active(&segment)?;
+        self.segments.push(segment);
+        Ok(())
+    }
+
+    pub fn retention_segments(&self) -> usize {
+        self.config.retention_segments
+    }
+
+    /// Deletes the oldest segments beyond `retention_segments` and returns how many went.
+    /// Run by the retention job rather than on append, so the writer never waits on it.
+    pub fn apply_retention(&mut self) -> io::Result<usize> {
+        let retention_segments = self.config.retention_segments;
+        if retention_segments == 0 || self.segments.len() <= retention_segments {
+            return Ok(0);
+        }
+        let expired_count = self.segments.len() - retention_segments;
+        for expired in self.segments.drain(..expired_count) {
+            info!(
+                "Deleting event log segment {} (ids {}..={})",
+                expired.path.display(),
+                expired.base_id,
+                expired.last_id
+            );
+            fs::remove_file(&expired.path)?;
+        }
+        Ok(expired_count)
+    }
+
+    fn open_active(segment: &Segment) -> io::Result<File> {
+        OpenOptions::new().append(true).open(&segment.path)
+    }
+}
+
+/// Scans frames with ids in `from_id..=to_id` sequentially, outside the writer's lock.
+/// A corrupt frame is returned in place; an I/O error ends the scan.
+pub fn read_frames(cursors: Vec<SegmentCursor>, from_id: u64, to_id: u64) -> Vec<FrameResult> {
+    let mut frames = vec![];
+    for cursor in cursors {
+        let file = match File::open(&cursor.path) {
+            Ok(file) => file,
+            // removed by retention after the cursors were taken
+            Err(error) if error.kind() == ErrorKind::NotFound => continue,
+            Err(error) => panic!("Failed to open {}: {error}", cursor.path.display()),
+        };
+        let mut reader = BufReader::new(file);
+        reader.seek(SeekFrom::Start(cursor.position)).unwrap();
+
+        loop {
+            match read_frame(&mut reader) {
+                Ok(Some(frame)) => {
+                    let id = match &frame {
+                        Ok(frame) => frame.id,
+                        Err(corrupt_frame) => corrupt_frame.id,
+                    };
+                    if id > to_id {
+                        return frames;
+                    }
+                    if id >= from_id {
+                        frames.push(frame);
+                    }
+                }
+                Ok(None) => break,
+                Err(read_error) => {
+                    error!("{}: {read_error}", cursor.path.display());
+                    return frames;
+                }
+            }
+        }
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::infrastructure::event_log::segment::FRAME_HEADER_SIZE;
+
+    const PAYLOAD_SIZE: usize = 20;
+    /// Two frames per segment.
+    const SEGMENT_BYTES: u64 = 2 * (FRAME_HEADER_SIZE + PAYLOAD_SIZE) as u64;
+
+    fn config(dir: &Path, retention_segments: usize) -> SegmentLogConfig {
+        SegmentLogConfig {
+            dir: dir.to_path_buf(),
+            segment_bytes: SEGMENT_BYTES,
+            index_interval_bytes: 1,
+            retention_segments,
+            fsync: false,
+        }
+    }
+
+    fn append_ids(segment_log: &mut SegmentLog, ids: impl IntoIterator<Item = u64>) {
+        for id in ids {
+            segment_log.append(id, &[id as u8; PAYLOAD_SIZE]).unwrap();
+        }
+    }
+
+    fn read_ids(segment_log: &SegmentLog, from_id: u64, to_id: u64) -> Vec<u64> {
+        read_frames(segment_log.cursors(from_id), from_id, to_id)
+            .into_iter()
+            .map(|frame| {
+                let frame = frame.unwrap();
+                assert_eq!(frame.payload, [frame.id as u8; PAYLOAD_SIZE]);
+                frame.id
+            })
+            .collect()
+    }
+
+    #[test]
+    fn segments_roll_at_segment_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut segment_log = SegmentLog::open(config(dir.path(), 0), 0).unwrap();
+
+        append_ids(&mut segment_log, 1..=7);
+
+        assert_eq!(list_segment_base_ids(dir.path()).unwrap(), vec![1, 3, 5, 7]);
+        assert_eq!(read_ids(&segment_log, 1, 7), (1..=7).collect::<Vec<_>>());
+        assert_eq!(read_ids(&segment_log, 4, 6), vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn retention_keeps_the_newest_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut segment_log = SegmentLog::open(config(dir.path(), 2), 0).unwrap();
+
+        append_ids(&mut segment_log, 1..=7);
+        assert_eq!(list_segment_base_ids(dir.path()).unwrap(), vec![1, 3, 5, 7]);
+
+        assert_eq!(segment_log.apply_retention().unwrap(), 2);
+
+        assert_eq!(list_segment_base_ids(dir.path()).unwrap(), vec![5, 7]);
+        assert_eq!(read_ids(&segment_log, 1, 7), vec![5, 6, 7]);
+        assert_eq!(segment_log.apply_retention().unwrap(), 0);
+    }
+
+    #[test]
+    fn open_truncates_what_was_written_after_the_commit_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut segment_log = SegmentLog::open(config(dir.path(), 0), 0).unwrap();
+        append_ids(&mut segment_log, 1..=6);
+        drop(segment_log);
+
+        let mut segment_log = SegmentLog::open(config(dir.path(), 0), 3).unwrap();
+
+        assert_eq!(list_segment_base_ids(dir.path()).unwrap(), vec![1, 3]);
+        assert_eq!(read_ids(&segment_log, 1, 6), vec![1, 2, 3]);
+        append_ids(&mut segment_log, 4..=5);
+        assert_eq!(read_ids(&segment_log, 1, 5), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn open_truncates_a_torn_frame() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut segment_log = SegmentLog::open(config(dir.path(), 0), 0).unwrap();
+        append_ids(&mut segment_log, 1..=1);
+        drop(segment_log);
+        let path = segment_path(dir.path(), 1);
+        let torn_frame = &encode_frame(2, &[2; PAYLOAD_SIZE])[..FRAME_HEADER_SIZE + 3];
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(torn_frame)
+            .unwrap();
+
+        // the marker says 2 committed, but only 1 made it to disk whole
+        let mut segment_log = SegmentLog::open(config(dir.path(), 0), 2).unwrap();
+
+        let frame_size = (FRAME_HEADER_SIZE + PAYLOAD_SIZE) as u64;
+        assert_eq!(fs::metadata(&path).unwrap().len(), frame_size);
+        append_ids(&mut segment_log, 2..=2);
+        assert_eq!(read_ids(&segment_log, 1, 2), vec![1, 2]);
+    }
+
+    #[test]
+    fn read_frames_returns_a_corrupt_frame_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut segment_log = SegmentLog::open(config(dir.path(), 0), 0).unwrap();
+        append_ids(&mut segment_log, 1..=2);
+        let path = segment_path(dir.path(), 1);
+        let mut bytes = fs::read(&path).unwrap();
+        // last payload byte of the first frame
+        bytes[FRAME_HEADER_SIZE + PAYLOAD_SIZE - 1] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        let frames = read_frames(segment_log.cursors(1), 1, 2);
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].as_ref().unwrap_err().id, 1);
+        assert_eq!(frames[1].as_ref().unwrap().id, 2);
+    }
+
+    fn corrupt_payload(path: &Path, frame: usize) {
+        let frame_size = FRAME_HEADER_SIZE + PAYLOAD_SIZE;
+        let mut bytes = fs::read(path).unwrap();
+        bytes[frame * frame_size + FRAME_HEADER_SIZE] ^= 0xff;
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn open_keeps_the_committed_frames_after_a_corrupt_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(dir.path(), 0);
+        config.segment_bytes = 10 * SEGMENT_BYTES;
+        let mut segment_log = SegmentLog::open(config.clone(), 0).unwrap();
+        append_ids(&mut segment_log, 1..=5);
+        drop(segment_log);
+        let path = segment_path(dir.path(), 1);
+        corrupt_payload(&path, 1);
+
+        let mut segment_log = SegmentLog::open(config, 5).unwrap();
+
+        let frames = read_frames(segment_log.cursors(1), 1, 5);
+        let ids: Vec<_> = frames
+            .iter()
+            .map(|frame| {
+                frame
+                    .as_ref()
+                    .map(|frame| frame.id)
+                    .map_err(|corrupt| corrupt.id)
+            })
+            .collect();
+        assert_eq!(ids, vec![Ok(1), Err(2), Ok(3), Ok(4), Ok(5)]);
+        append_ids(&mut segment_log, 6..=6);
+        assert_eq!(read_frames(segment_log.cursors(6), 6, 6).len(), 1);
+    }
+
+    #[test]
+    fn truncate_after_keeps_the_committed_frames_after_a_corrupt_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(dir.path(), 0);
+        config.segment_bytes = 10 * SEGMENT_BYTES;
+        let mut segment_log = SegmentLog::open(config, 0).unwrap();
+        append_ids(&mut segment_log, 1..=5);
+        corrupt_payload(&segment_path(dir.path(), 1), 0);
+
+        segment_log.truncate_after(3).unwrap();
+
+        let frames = read_frames(segment_log.cursors(1), 1, 5);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].as_ref().unwrap_err().id, 1);
+        assert_eq!(frames[2].as_ref().unwrap().id, 3);
+    }
+
+    #[test]
+    fn truncate_after_drops_appends_past_the_commit_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut segment_log = SegmentLog::open(config(dir.path(), 0), 0).unwrap();
+        append_ids(&mut segment_log, 1..=5);
+
+        segment_log.truncate_after(2).unwrap();
+
+        assert_eq!(list_segment_base_ids(dir.path()).unwrap(), vec![1]);
+        assert_eq!(read_ids(&segment_log, 1, 5), vec![1, 2]);
+        append_ids(&mut segment_log, 3..=4);
+        assert_eq!(read_ids(&segment_log, 1, 4), vec![1, 2, 3, 4]);
+    }
+}
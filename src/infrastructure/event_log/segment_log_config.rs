@@ -0,0 +1,39 @@
+use std::{env, path::PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct SegmentLogConfig {
+    pub dir: PathBuf,
+    pub segment_bytes: u64,
+    pub index_interval_bytes: u64,
+    /// Number of segments kept on disk, the active one included; `0` keeps everything.
+    pub retention_segments: usize,
+    /// Syncs every append to disk before the balances commit. Without it, a power loss can
+    /// lose events whose balances were committed.
+    pub fsync: bool,
+}
+
+impl SegmentLogConfig {
+    pub fn from_env() -> Self {
+        Self {
+            dir: PathBuf::from(
+                env::var("EVENT_LOG_DIR").unwrap_or("offheap/balance_events".to_string()),
+            ),
+            segment_bytes: env::var("EVENT_LOG_SEGMENT_BYTES")
+                .unwrap_or("67108864".to_string())
+                .parse::<u64>()
+                .unwrap_or(64 << 20),
+            index_interval_bytes: env::var("EVENT_LOG_INDEX_INTERVAL_BYTES")
+                .unwrap_or("4096".to_string())
+                .parse::<u64>()
+                .unwrap_or(4096),
+            retention_segments: env::var("EVENT_LOG_RETENTION_SEGMENTS")
+                .unwrap_or("0".to_string())
+                .parse::<usize>()
+                .unwrap_or(0),
+            fsync: env::var("EVENT_LOG_FSYNC")
+                .unwrap_or("true".to_string())
+                .parse::<bool>()
+                .unwrap_or(true),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use chrono::{DateTime, SecondsFormat};
+use serde::Serialize;
+
+use crate::{
+    application::balance::api::balance_event_api::BalanceEventData,
+    core::domain::balance_event::{
+        BalanceCreatedEvent, BalanceDepositedEvent, BalanceEventPayload, BalanceTransferredEvent,
+        BalanceWithdrawnEvent,
+    },
+    infrastructure::event_sink::event_subscription::SinkEnv,
+};
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+pub const CLOUDEVENTS_CONTENT_TYPE: &str = "application/cloudevents+json";
+pub const CLOUDEVENTS_BATCH_CONTENT_TYPE: &str = "application/cloudevents-batch+json";
+const CLOUDEVENTS_SPEC_VERSION: &str = "1.0";
+const CLOUDEVENTS_TYPE_PREFIX: &str = "actor-bank.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventEnvelopeFormat {
+    /// `BalanceEventData` as is.
+    Plain,
+    /// Structured-mode CloudEvents 1.0, with the event body as `data`.
+    CloudEvents,
+}
+
+#[derive(Serialize)]
+struct CloudEvent<'a> {
+    specversion: &'static str,
+    id: String,
+    source: &'a str,
+    #[serde(rename = "type")]
+    event_type: String,
+    time: String,
+    datacontenttype: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dataschema: Option<String>,
+    data: CloudEventData<'a>,
+}
+
+/// The event body without its `event_type` tag, which CloudEvents carries in `type`.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum CloudEventData<'a> {
+    Created(&'a BalanceCreatedEvent),
+    Deposited(&'a BalanceDepositedEvent),
+    Withdrawn(&'a BalanceWithdrawnEvent),
+    Transferred(&'a BalanceTransferredEvent),
+}
+
+impl<'a> From<&'a BalanceEventPayload> for CloudEventData<'a> {
+    fn from(payload: &'a BalanceEventPayload) -> Self {
+        match payload {
+            BalanceEventPayload::BalanceCreated(event) => CloudEventData::Created(event),
+            BalanceEventPayload::BalanceDeposited(event) => CloudEventData::Deposited(event),
+            BalanceEventPayload::BalanceWithdrawn(event) => CloudEventData::Withdrawn(event),
+            BalanceEventPayload::BalanceTransferred(event) => CloudEventData::Transferred(event),
+        }
+    }
+}
+
+/// How a sink renders each published event, set per subscription.
+#[derive(Debug, Clone)]
+pub struct EventEnvelope {
+    pub format: EventEnvelopeFormat,
+    pub source: String,
+    /// Base URL where the files of `schemas/` are served, used for `dataschema`.
+    pub schema_base_url: Option<String>,
+}
+
+impl EventEnvelope {
+    pub fn from_env(sink_env: &SinkEnv) -> Self {
+        let format = match sink_env
+            .var("BALANCE_EVENT_FORMAT")
+            .unwrap_or("plain".to_string())
+            .as_str()
+        {
+            "cloudevents" => EventEnvelopeFormat::CloudEvents,
+            "plain" => EventEnvelopeFormat::Plain,
+            other => panic!("Unknown BALANCE_EVENT_FORMAT: {other}"),
+        };
+        Self {
+            format,
+            source: sink_env
+                .var("BALANCE_EVENT_SOURCE")
+                .unwrap_or("/actor-bank".to_string()),
+            schema_base_url: sink_env.var("BALANCE_EVENT_SCHEMA_BASE_URL").ok(),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self.format {
+            EventEnvelopeFormat::Plain => JSON_CONTENT_TYPE,
+            EventEnvelopeFormat::CloudEvents => CLOUDEVENTS_CONTENT_TYPE,
+        }
+    }
+
+    pub fn batch_content_type(&self) -> &'static str {
+        match self.format {
+            EventEnvelopeFormat::Plain => JSON_CONTENT_TYPE,
+            EventEnvelopeFormat::CloudEvents => CLOUDEVENTS_BATCH_CONTENT_TYPE,
+        }
+    }
+
+    pub fn encode(&self, event: &BalanceEventData) -> String {
+        match self.format {
+            EventEnvelopeFormat::Plain => serde_json::to_string(event).unwrap(),
+            EventEnvelopeFormat::CloudEvents => {
+                serde_json::to_string(&self.cloud_event(event)).unwrap()
+            }
+        }
+    }
+
+    /// JSON array of the encoded events, a CloudEvents batch in CloudEvents format.
+    pub fn encode_batch(&self, events: &[BalanceEventData]) -> String {
+        let encoded: Vec<String> = events.iter().map(|event| self.encode(event)).collect();
+        format!("[{}]", encoded.join(","))
+    }
+
+    fn cloud_event<'a>(&'a self, event: &'a BalanceEventData) -> CloudEvent<'a> {
+        let event_type = event.event_type();
+        let time = DateTime::from_timestamp_nanos(event.event_time as i64)
+            .to_rfc3339_opts(SecondsFormat::Nanos, true);
+        CloudEvent {
+            specversion: CLOUDEVENTS_SPEC_VERSION,
+            id: event.id.to_string(),
+            source: &self.source,
+            event_type: format!("{CLOUDEVENTS_TYPE_PREFIX}{}", event_type.name()),
+            time,
+            datacontenttype: JSON_CONTENT_TYPE,
+            dataschema: self.schema_base_url.as_ref().map(|base_url| {
+                format!(
+                    "{}/{}.v{}.schema.json",
+                    base_url.trim_end_matches('/'),
+                    event_type.name(),
+                    event.schema_version
+                )
+            }),
+            data: CloudEventData::from(&event.payload),
+        }
+    }
+}
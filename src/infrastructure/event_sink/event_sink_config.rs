@@ -0,0 +1,144 @@
+use std::env;
+
+use log::error;
+
+#[cfg(feature = "kafka")]
+use crate::infrastructure::event_sink::{
+    kafka_dead_letter_queue::KafkaDeadLetterQueue,
+    kafka_event_sink::{
+        KafkaEventEncoding, KafkaEventSink, KafkaEventSinkConfig, KafkaValueEncoding,
+    },
+    protobuf_event_encoding::BALANCE_EVENT_PROTO_SCHEMA,
+    schema_registry::{FileSchemaRegistry, SchemaRegistry, value_subject},
+};
+use crate::{
+    application::balance::spi::{dead_letter_queue::DeadLetterQueue, event_sink::EventSink},
+    infrastructure::event_sink::{
+        event_envelope::EventEnvelope,
+        event_subscription::{EventSubscription, SinkEnv},
+        file_dead_letter_queue::FileDeadLetterQueue,
+        file_event_sink::{FileEventSink, FileEventSinkConfig},
+        http_event_sink::{HttpEventSink, HttpEventSinkConfig},
+        stdout_event_sink::StdoutEventSink,
+    },
+};
+
+/// Sink of `subscription`, configured from its environment.
+pub fn new_event_sink(subscription: &EventSubscription) -> Result<Box<dyn EventSink>, String> {
+    let sink_env = &subscription.sink_env;
+    let envelope = EventEnvelope::from_env(sink_env);
+    match subscription.sink.as_str() {
+        "kafka" => new_kafka_event_sink(sink_env, envelope),
+        "file" => Ok(Box::new(FileEventSink::new(
+            FileEventSinkConfig::from_env(sink_env),
+            envelope,
+        ))),
+        "stdout" => Ok(Box::new(StdoutEventSink { envelope })),
+        "http" => Ok(Box::new(HttpEventSink::new(
+            HttpEventSinkConfig::from_env(sink_env),
+            envelope,
+        ))),
+        other => panic!("Unknown balance event sink: {other}"),
+    }
+}
+
+/// With `BALANCE_EVENT_ENCODING=protobuf` the value schema is registered under
+/// `<topic>-value` before the first send, so every record carries a known schema id.
+#[cfg(feature = "kafka")]
+fn new_kafka_event_sink(
+    sink_env: &SinkEnv,
+    envelope: EventEnvelope,
+) -> Result<Box<dyn EventSink>, String> {
+    let config = KafkaEventSinkConfig::from_env(sink_env);
+    let value_encoding = match config.encoding {
+        KafkaEventEncoding::Json => KafkaValueEncoding::Json(envelope),
+        KafkaEventEncoding::Protobuf => FileSchemaRegistry::from_env()
+            .register(&value_subject(&config.topic), BALANCE_EVENT_PROTO_SCHEMA)
+            .map(KafkaValueEncoding::Protobuf)
+            .map_err(|registry_error| registry_error.to_string())?,
+    };
+    KafkaEventSink::new(config, value_encoding)
+        .map(|sink| Box::new(sink) as Box<dyn EventSink>)
+        .map_err(|kafka_error| format!("Producer creation error: {kafka_error:?}"))
+}
+
+#[cfg(not(feature = "kafka"))]
+fn new_kafka_event_sink(
+    _sink_env: &SinkEnv,
+    _envelope: EventEnvelope,
+) -> Result<Box<dyn EventSink>, String> {
+    Err("built without the `kafka` feature".to_string())
+}
+
+/// `BALANCE_EVENT_DEAD_LETTER` is `file` or `kafka`; unset, rejected events are retried forever.
+pub fn new_dead_letter_queue() -> Option<Box<dyn DeadLetterQueue>> {
+    let dead_letter = env::var("BALANCE_EVENT_DEAD_LETTER").unwrap_or_default();
+    match dead_letter.as_str() {
+        "" => None,
+        "file" => Some(Box::new(FileDeadLetterQueue::from_env())),
+        "kafka" => new_kafka_dead_letter_queue(),
+        other => panic!("Unknown balance event dead letter queue: {other}"),
+    }
+}
+
+#[cfg(feature = "kafka")]
+fn new_kafka_dead_letter_queue() -> Option<Box<dyn DeadLetterQueue>> {
+    match KafkaDeadLetterQueue::from_env() {
+        Ok(dead_letter_queue) => Some(Box::new(dead_letter_queue)),
+        Err(kafka_error) => {
+            error!("Balance event dead letter queue disabled: {kafka_error:?}");
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "kafka"))]
+fn new_kafka_dead_letter_queue() -> Option<Box<dyn DeadLetterQueue>> {
+    error!("Balance event dead letter queue disabled: built without the `kafka` feature");
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::event_sink::event_subscription::EventFilter;
+
+    fn subscription(sink: &str) -> EventSubscription {
+        EventSubscription::new(
+            "selection".to_string(),
+            sink.to_string(),
+            EventFilter::default(),
+            SinkEnv::for_subscription("selection"),
+        )
+    }
+
+    #[test]
+    fn stdout_sink_is_built_in_every_feature_set() {
+        let sink = new_event_sink(&subscription("stdout")).unwrap();
+
+        assert_eq!(sink.name(), "stdout");
+    }
+
+    #[cfg(feature = "kafka")]
+    #[test]
+    fn kafka_sink_is_built_with_the_kafka_feature() {
+        // creating the producer does not wait for the broker
+        let sink = new_event_sink(&subscription("kafka")).unwrap();
+
+        assert_eq!(sink.name(), "kafka");
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    #[test]
+    fn kafka_sink_is_refused_without_the_kafka_feature() {
+        let message = new_event_sink(&subscription("kafka")).err().unwrap();
+
+        assert_eq!(message, "built without the `kafka` feature");
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown balance event sink: carrier-pigeon")]
+    fn unknown_sink_is_refused() {
+        let _ = new_event_sink(&subscription("carrier-pigeon"));
+    }
+}
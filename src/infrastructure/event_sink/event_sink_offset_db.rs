@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use rust_rocksdb::{DB, DBWithThreadMode, Options, SingleThreaded};
+
+use crate::core::domain::balance_event::EventId;
+
+/// Key used when Kafka was the only sink, kept so existing deployments resume where they were.
+const KAFKA_OFFSET_KEY: &str = "offset";
+
+/// Emitted offsets of the subscriptions whose sink does not own its offset, one key per
+/// subscription name.
+pub struct EventSinkOffsetDB {
+    db: DBWithThreadMode<SingleThreaded>,
+}
+
+impl Default for EventSinkOffsetDB {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventSinkOffsetDB {
+    pub fn new() -> Self {
+        Self::open("offheap/balance_event_offset.db")
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        let db = DB::open(&opts, path).unwrap();
+        Self { db }
+    }
+
+    pub fn get_offset(&self, subscription_name: &str) -> EventId {
+        let offset = self
+            .db
+            .get(Self::offset_key(subscription_name))
+            .unwrap()
+            .unwrap_or_else(|| vec![0_u8; 8]);
+        EventId::from_be_bytes(offset.try_into().unwrap())
+    }
+
+    pub fn set_offset(&self, subscription_name: &str, offset: EventId) {
+        self.db
+            .put(Self::offset_key(subscription_name), offset.to_be_bytes())
+            .unwrap();
+    }
+
+    fn offset_key(subscription_name: &str) -> String {
+        match subscription_name {
+            "kafka" => KAFKA_OFFSET_KEY.to_string(),
+            subscription_name => format!("offset.{subscription_name}"),
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    collections::BTreeSet,
+    env,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use log::info;
+
+use crate::{
+    application::balance::api::balance_event_api::BalanceEventData,
+    core::domain::{
+        balance::BalanceId,
+        balance_event::{BalanceEventType, EventId},
+    },
+};
+
+#[cfg(feature = "kafka")]
+const DEFAULT_EVENT_SINKS: &str = "kafka";
+#[cfg(not(feature = "kafka"))]
+const DEFAULT_EVENT_SINKS: &str = "";
+
+const SUBSCRIPTION_ENV_PREFIX: &str = "BALANCE_EVENT_SUBSCRIPTION_";
+
+/// Environment seen by the sink of one subscription: `BALANCE_EVENT_SUBSCRIPTION_<NAME>_<KEY>`
+/// when set, `<KEY>` otherwise, so a subscription only spells out what differs.
+#[derive(Debug, Clone, Default)]
+pub struct SinkEnv {
+    subscription_name: Option<String>,
+    prefix: Option<String>,
+}
+
+impl SinkEnv {
+    /// Plain variables only, as used before subscriptions existed.
+    pub fn global() -> Self {
+        Self {
+            subscription_name: None,
+            prefix: None,
+        }
+    }
+
+    pub fn for_subscription(name: &str) -> Self {
+        Self {
+            subscription_name: Some(name.to_string()),
+            prefix: Some(subscription_env_prefix(name)),
+        }
+    }
+
+    pub fn subscription_name(&self) -> Option<&str> {
+        self.subscription_name.as_deref()
+    }
+
+    /// Like `var` without the fallback to the shared `<KEY>`, for settings two subscriptions
+    /// must not share.
+    pub fn own_var(&self, key: &str) -> Result<String, env::VarError> {
+        match &self.prefix {
+            Some(prefix) => env::var(format!("{prefix}{key}")),
+            None => env::var(key),
+        }
+    }
+
+    pub fn var(&self, key: &str) -> Result<String, env::VarError> {
+        if let Some(prefix) = &self.prefix
+            && let Ok(value) = env::var(format!("{prefix}{key}"))
+        {
+            return Ok(value);
+        }
+        env::var(key)
+    }
+}
+
+/// `orders-audit` reads `BALANCE_EVENT_SUBSCRIPTION_ORDERS_AUDIT_*`.
+fn subscription_env_prefix(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{SUBSCRIPTION_ENV_PREFIX}{name}_")
+}
+
+/// Events a subscription receives; an unset criterion lets every event through.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub event_types: Option<Vec<BalanceEventType>>,
+    /// Matches either side of a transfer.
+    pub balance_ids: Option<BTreeSet<BalanceId>>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &BalanceEventData) -> bool {
+        let type_matches = self
+            .event_types
+            .as_ref()
+            .is_none_or(|event_types| event_types.contains(&event.event_type()));
+        let balance_matches = self.balance_ids.as_ref().is_none_or(|balance_ids| {
+            balance_ids.contains(&event.payload.balance_id())
+                || event
+                    .payload
+                    .counterparty_balance_id()
+                    .is_some_and(|balance_id| balance_ids.contains(&balance_id))
+        });
+        type_matches && balance_matches
+    }
+
+    /// Comma separated lists, as in the subscription settings and the stream query.
+    pub fn parse(event_types: Option<&str>, balance_ids: Option<&str>) -> Result<Self, String> {
+        let event_types = event_types
+            .map(|event_types| {
+                split_list(event_types)
+                    .map(|name| {
+                        BalanceEventType::from_name(name)
+                            .ok_or_else(|| format!("Unknown balance event type: {name}"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+        let balance_ids = balance_ids
+            .map(|balance_ids| {
+                split_list(balance_ids)
+                    .map(|balance_id| {
+                        balance_id
+                            .parse::<BalanceId>()
+                            .map_err(|_| format!("Invalid balance id: {balance_id}"))
+                    })
+                    .collect::<Result<BTreeSet<_>, _>>()
+            })
+            .transpose()?;
+        Ok(Self {
+            event_types,
+            balance_ids,
+        })
+    }
+
+    fn from_env(prefix: &str) -> Self {
+        Self::parse(
+            env::var(format!("{prefix}EVENT_TYPES")).ok().as_deref(),
+            env::var(format!("{prefix}BALANCE_IDS")).ok().as_deref(),
+        )
+        .unwrap_or_else(|message| panic!("{message}"))
+    }
+}
+
+/// A named consumer of the event log: one sink, one filter and its own offset.
+///
+/// Pausing and offset resets are requested here by the admin endpoints and carried out by the
+/// emitter job between two batches, so they never race a batch in flight.
+#[derive(Debug)]
+pub struct EventSubscription {
+    pub name: String,
+    /// `kafka`, `file`, `stdout` or `http`.
+    pub sink: String,
+    pub filter: EventFilter,
+    pub sink_env: SinkEnv,
+    paused: AtomicBool,
+    offset_reset: Mutex<Option<EventId>>,
+}
+
+impl EventSubscription {
+    pub fn new(name: String, sink: String, filter: EventFilter, sink_env: SinkEnv) -> Self {
+        Self {
+            name,
+            sink,
+            filter,
+            sink_env,
+            paused: AtomicBool::new(false),
+            offset_reset: Mutex::new(None),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// The subscription resumes after `offset`, replaying everything past it.
+    pub fn request_offset_reset(&self, offset: EventId) {
+        *self.offset_reset.lock().unwrap() = Some(offset);
+    }
+
+    pub fn take_offset_reset(&self) -> Option<EventId> {
+        self.offset_reset.lock().unwrap().take()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct EventSubscriptions {
+    subscriptions: Vec<Arc<EventSubscription>>,
+}
+
+impl EventSubscriptions {
+    /// `BALANCE_EVENT_SUBSCRIPTIONS` is a comma separated list of subscription names, each
+    /// configured by `BALANCE_EVENT_SUBSCRIPTION_<NAME>_SINK`, `_EVENT_TYPES`, `_BALANCE_IDS`
+    /// and `_PAUSED`. Unset, every sink of `BALANCE_EVENT_SINKS` is a subscription named after
+    /// it, which keeps the offsets stored before subscriptions existed.
+    pub fn from_env() -> Self {
+        let subscriptions: Vec<EventSubscription> = match env::var("BALANCE_EVENT_SUBSCRIPTIONS") {
+            Ok(names) => split_list(&names)
+                .map(Self::subscription_from_env)
+                .collect(),
+            Err(_) => split_list(
+                &env::var("BALANCE_EVENT_SINKS").unwrap_or(DEFAULT_EVENT_SINKS.to_string()),
+            )
+            .map(|sink| {
+                EventSubscription::new(
+                    sink.to_string(),
+                    sink.to_string(),
+                    EventFilter::default(),
+                    SinkEnv::global(),
+                )
+            })
+            .collect(),
+        };
+
+        let mut names = BTreeSet::new();
+        for subscription in &subscriptions {
+            if !names.insert(subscription.name.as_str()) {
+                panic!(
+                    "Duplicate balance event subscription: {}",
+                    subscription.name
+                );
+            }
+        }
+        Self {
+            subscriptions: subscriptions.into_iter().map(Arc::new).collect(),
+        }
+    }
+
+    fn subscription_from_env(name: &str) -> EventSubscription {
+        let prefix = subscription_env_prefix(name);
+        let sink = env::var(format!("{prefix}SINK"))
+            .unwrap_or_else(|_| panic!("{prefix}SINK is required by subscription {name}"));
+        let subscription = EventSubscription::new(
+            name.to_string(),
+            sink,
+            EventFilter::from_env(&prefix),
+            SinkEnv::for_subscription(name),
+        );
+        let paused = env::var(format!("{prefix}PAUSED"))
+            .unwrap_or("false".to_string())
+            .parse::<bool>()
+            .unwrap_or(false);
+        if paused {
+            info!("Balance event subscription {name} starts paused");
+            subscription.set_paused(true);
+        }
+        subscription
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<EventSubscription>> {
+        self.subscriptions.iter()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<EventSubscription>> {
+        self.subscriptions
+            .iter()
+            .find(|subscription| subscription.name == name)
+    }
+}
+
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
@@ -0,0 +1,60 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::application::balance::spi::{
+    dead_letter_queue::{DeadLetter, DeadLetterQueue},
+    event_sink::{EventSinkError, EventSinkFuture},
+};
+
+/// Appends one JSON line per dead letter to `BALANCE_EVENT_DEAD_LETTER_FILE`.
+pub struct FileDeadLetterQueue {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileDeadLetterQueue {
+    pub fn from_env() -> Self {
+        Self {
+            path: PathBuf::from(
+                env::var("BALANCE_EVENT_DEAD_LETTER_FILE")
+                    .unwrap_or("offheap/balance_event_dead_letter.jsonl".to_string()),
+            ),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn append(&self, dead_letter: &DeadLetter) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        if let Some(dir) = self.path.parent().filter(|dir| dir != &Path::new("")) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut line = serde_json::to_string(dead_letter).unwrap();
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        // the sink offset moves past the event once this returns
+        file.sync_data()
+    }
+}
+
+impl DeadLetterQueue for FileDeadLetterQueue {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn store<'a>(&'a self, dead_letter: &'a DeadLetter<'a>) -> EventSinkFuture<'a> {
+        let result = self
+            .append(dead_letter)
+            .map_err(|io_error| EventSinkError::DeliveryFailed(io_error.to_string()));
+        Box::pin(std::future::ready(result))
+    }
+}
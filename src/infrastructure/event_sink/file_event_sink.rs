@@ -0,0 +1,139 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use log::info;
+
+use crate::{
+    application::balance::{
+        api::balance_event_api::BalanceEventData,
+        spi::event_sink::{EventSink, EventSinkError, EventSinkFuture},
+    },
+    core::domain::balance_event::EventId,
+    infrastructure::event_sink::{event_envelope::EventEnvelope, event_subscription::SinkEnv},
+};
+
+pub struct FileEventSinkConfig {
+    pub dir: PathBuf,
+    pub max_file_bytes: u64,
+}
+
+impl FileEventSinkConfig {
+    /// A subscription without its own `BALANCE_EVENT_FILE_SINK_DIR` writes to a directory
+    /// named after it under the shared one, so two subscriptions never append to the same files.
+    pub fn from_env(sink_env: &SinkEnv) -> Self {
+        let shared_dir = PathBuf::from(
+            sink_env
+                .var("BALANCE_EVENT_FILE_SINK_DIR")
+                .unwrap_or("offheap/balance_event_sink".to_string()),
+        );
+        Self {
+            dir: match (
+                sink_env.own_var("BALANCE_EVENT_FILE_SINK_DIR"),
+                sink_env.subscription_name(),
+            ) {
+                (Ok(dir), _) => PathBuf::from(dir),
+                (Err(_), Some(name)) => shared_dir.join(name),
+                (Err(_), None) => shared_dir,
+            },
+            max_file_bytes: sink_env
+                .var("BALANCE_EVENT_FILE_SINK_MAX_BYTES")
+                .unwrap_or("67108864".to_string())
+                .parse::<u64>()
+                .unwrap_or(64 << 20),
+        }
+    }
+}
+
+struct ActiveFile {
+    file: File,
+    size: u64,
+}
+
+/// Appends one JSON line per event to `balance-events-<first event id>.jsonl` files,
+/// starting a new file once the current one reaches `max_file_bytes`.
+pub struct FileEventSink {
+    config: FileEventSinkConfig,
+    envelope: EventEnvelope,
+    active_file: Mutex<Option<ActiveFile>>,
+}
+
+impl FileEventSink {
+    pub fn new(config: FileEventSinkConfig, envelope: EventEnvelope) -> Self {
+        fs::create_dir_all(&config.dir).unwrap();
+        Self {
+            config,
+            envelope,
+            active_file: Mutex::new(None),
+        }
+    }
+
+    fn write(&self, events: &[BalanceEventData]) -> io::Result<()> {
+        let Some(first_event) = events.first() else {
+            return Ok(());
+        };
+
+        let mut active_file = self.active_file.lock().unwrap();
+        let needs_rotation = match active_file.as_ref() {
+            Some(active) => active.size >= self.config.max_file_bytes,
+            None => true,
+        };
+        if needs_rotation {
+            *active_file = Some(self.open_file(first_event.id)?);
+        }
+        let active = active_file.as_mut().unwrap();
+
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&self.envelope.encode(event));
+            lines.push('\n');
+        }
+        active.file.write_all(lines.as_bytes())?;
+        // the offset advances after this returns, so the lines must be on disk first
+        active.file.sync_data()?;
+        active.size += lines.len() as u64;
+        Ok(())
+    }
+
+    fn open_file(&self, first_event_id: EventId) -> io::Result<ActiveFile> {
+        let path = self
+            .config
+            .dir
+            .join(format!("balance-events-{first_event_id:020}.jsonl"));
+        info!("Writing balance events to {}", path.display());
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(ActiveFile { file, size })
+    }
+}
+
+impl EventSink for FileEventSink {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn publish<'a>(&'a self, events: &'a [BalanceEventData]) -> EventSinkFuture<'a> {
+        let result = self
+            .write(events)
+            .map_err(|io_error| EventSinkError::DeliveryFailed(io_error.to_string()));
+        Box::pin(std::future::ready(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscriptions_default_to_a_directory_of_their_own() {
+        let global_dir = FileEventSinkConfig::from_env(&SinkEnv::global()).dir;
+        let audit_dir = FileEventSinkConfig::from_env(&SinkEnv::for_subscription("audit")).dir;
+        let archive_dir = FileEventSinkConfig::from_env(&SinkEnv::for_subscription("archive")).dir;
+
+        assert_eq!(audit_dir, global_dir.join("audit"));
+        assert_eq!(archive_dir, global_dir.join("archive"));
+    }
+}
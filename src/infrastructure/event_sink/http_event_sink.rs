@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use reqwest::{StatusCode, header::CONTENT_TYPE};
+
+use crate::{
+    application::balance::{
+        api::balance_event_api::BalanceEventData,
+        spi::event_sink::{EventSink, EventSinkError, EventSinkFuture},
+    },
+    infrastructure::event_sink::{event_envelope::EventEnvelope, event_subscription::SinkEnv},
+};
+
+pub struct HttpEventSinkConfig {
+    pub url: String,
+    pub timeout: Duration,
+}
+
+impl HttpEventSinkConfig {
+    pub fn from_env(sink_env: &SinkEnv) -> Self {
+        Self {
+            url: sink_env
+                .var("BALANCE_EVENT_HTTP_SINK_URL")
+                .expect("BALANCE_EVENT_HTTP_SINK_URL is required by the http sink"),
+            timeout: Duration::from_millis(
+                sink_env
+                    .var("BALANCE_EVENT_HTTP_SINK_TIMEOUT_MS")
+                    .unwrap_or("5000".to_string())
+                    .parse::<u64>()
+                    .unwrap_or(5000),
+            ),
+        }
+    }
+}
+
+/// POSTs each batch as a JSON array to the configured URL; any non-2xx status fails the batch.
+/// A 4xx answer to a single event rejects that event.
+pub struct HttpEventSink {
+    config: HttpEventSinkConfig,
+    envelope: EventEnvelope,
+    client: reqwest::Client,
+}
+
+impl HttpEventSink {
+    pub fn new(config: HttpEventSinkConfig, envelope: EventEnvelope) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .expect("HTTP client creation error");
+        Self {
+            config,
+            envelope,
+            client,
+        }
+    }
+}
+
+impl EventSink for HttpEventSink {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    fn publish<'a>(&'a self, events: &'a [BalanceEventData]) -> EventSinkFuture<'a> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .post(&self.config.url)
+                .header(CONTENT_TYPE, self.envelope.batch_content_type())
+                .body(self.envelope.encode_batch(events))
+                .send()
+                .await
+                .map_err(|http_error| EventSinkError::DeliveryFailed(http_error.to_string()))?;
+            let status = response.status();
+            if status.is_success() {
+                return Ok(());
+            }
+
+            let message = format!("{} responded with {status}", self.config.url);
+            // a client error for a single event is about that event, not the endpoint
+            let rejected = status.is_client_error()
+                && status != StatusCode::REQUEST_TIMEOUT
+                && status != StatusCode::TOO_MANY_REQUESTS;
+            match events {
+                [event] if rejected => Err(EventSinkError::Rejected {
+                    event_id: event.id,
+                    message,
+                }),
+                _ => Err(EventSinkError::DeliveryFailed(message)),
+            }
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use std::{env, time::Duration};
+
+use rdkafka::{
+    ClientConfig,
+    error::KafkaResult,
+    producer::{FutureProducer, FutureRecord},
+};
+
+use crate::application::balance::spi::{
+    dead_letter_queue::{DeadLetter, DeadLetterQueue},
+    event_sink::{EventSinkError, EventSinkFuture},
+};
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Produces each dead letter as JSON to `BALANCE_EVENT_DEAD_LETTER_TOPIC`, keyed by event id.
+pub struct KafkaDeadLetterQueue {
+    topic: String,
+    producer: FutureProducer,
+}
+
+impl KafkaDeadLetterQueue {
+    pub fn from_env() -> KafkaResult<Self> {
+        let producer = ClientConfig::new()
+            .set(
+                "bootstrap.servers",
+                env::var("KAFKA_BROKERS").unwrap_or("localhost:9092".to_string()),
+            )
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .create()?;
+        Ok(Self {
+            topic: env::var("BALANCE_EVENT_DEAD_LETTER_TOPIC")
+                .unwrap_or("balance.event.dead-letter".to_string()),
+            producer,
+        })
+    }
+}
+
+impl DeadLetterQueue for KafkaDeadLetterQueue {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    fn store<'a>(&'a self, dead_letter: &'a DeadLetter<'a>) -> EventSinkFuture<'a> {
+        Box::pin(async move {
+            let key = dead_letter.event.id.to_string();
+            let payload = serde_json::to_string(dead_letter).unwrap();
+            let record = FutureRecord::to(&self.topic).key(&key).payload(&payload);
+            self.producer
+                .send(record, SEND_TIMEOUT)
+                .await
+                .map(|_| ())
+                .map_err(|(kafka_error, _)| {
+                    EventSinkError::DeliveryFailed(format!("{kafka_error:?}"))
+                })
+        })
+    }
+}
@@ -0,0 +1,879 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use log::{error, info};
+use rdkafka::{
+    ClientConfig,
+    error::{KafkaError, KafkaResult},
+    message::{Header, OwnedHeaders},
+    producer::{DeliveryFuture, FutureProducer, FutureRecord, Producer},
+    types::RDKafkaErrorCode,
+};
+use tokio::time;
+
+use crate::{
+    application::balance::{
+        api::balance_event_api::BalanceEventData,
+        spi::event_sink::{EventSink, EventSinkError, EventSinkFuture},
+    },
+    core::domain::balance_event::EventId,
+    infrastructure::event_sink::{
+        event_envelope::EventEnvelope,
+        event_subscription::SinkEnv,
+        kafka_transactional_offset::{encode_offset, read_committed_offset},
+        protobuf_event_encoding::{PROTOBUF_CONTENT_TYPE, encode_framed},
+        schema_registry::SchemaId,
+    },
+};
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(1);
+const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(30);
+const METADATA_TIMEOUT: Duration = Duration::from_secs(10);
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(10);
+const DEFAULT_TRANSACTIONAL_ID: &str = "actor-bank.balance-event-emitter";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KafkaEventEncoding {
+    Json,
+    Protobuf,
+}
+
+/// How the sink writes record values.
+pub enum KafkaValueEncoding {
+    /// The event as rendered by the shared `EventEnvelope`.
+    Json(EventEnvelope),
+    /// `schemas/proto/balance_event.v1.proto` in schema registry framing.
+    Protobuf(SchemaId),
+}
+
+/// How records are spread over the partitions of the topic. Kafka only orders records
+/// within a partition, so this decides which events a consumer sees in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KafkaPartitioning {
+    /// Keyed by the balance id, the source balance for transfers.
+    BalanceId,
+    /// Keyed by the balance id, with transfers published once keyed by each side.
+    BalanceIdBothSides,
+    /// Keyed by the event id.
+    EventId,
+    /// No key, partitions taken in turn.
+    RoundRobin,
+}
+
+pub struct KafkaEventSinkConfig {
+    pub brokers: String,
+    pub topic: String,
+    pub encoding: KafkaEventEncoding,
+    pub partitioning: KafkaPartitioning,
+    pub batch_size: String,
+    pub linger_ms: String,
+    pub exactly_once: bool,
+    pub transactional_id: String,
+    /// Single-partition and compacted. The sink does not create it: a topic created by the
+    /// broker on first use would keep every offset record forever.
+    pub offset_topic: String,
+}
+
+impl KafkaEventSinkConfig {
+    pub fn from_env(sink_env: &SinkEnv) -> Self {
+        Self {
+            brokers: sink_env
+                .var("KAFKA_BROKERS")
+                .unwrap_or("localhost:9092".to_string()),
+            topic: sink_env
+                .var("BALANCE_EVENT_TOPIC")
+                .unwrap_or("balance.event".to_string()),
+            encoding: match sink_env
+                .var("BALANCE_EVENT_ENCODING")
+                .unwrap_or("json".to_string())
+                .as_str()
+            {
+                "protobuf" => KafkaEventEncoding::Protobuf,
+                _ => KafkaEventEncoding::Json,
+            },
+            partitioning: match sink_env
+                .var("BALANCE_EVENT_PARTITIONING")
+                .unwrap_or("balance_id".to_string())
+                .as_str()
+            {
+                "balance_id_both_sides" => KafkaPartitioning::BalanceIdBothSides,
+                "event_id" => KafkaPartitioning::EventId,
+                "round_robin" => KafkaPartitioning::RoundRobin,
+                _ => KafkaPartitioning::BalanceId,
+            },
+            batch_size: sink_env
+                .var("KAFKA_BALANCE_EVENT_BATCH_NUM_MESSAGES")
+                .unwrap_or("10000".to_string()),
+            linger_ms: sink_env
+                .var("KAFKA_BALANCE_EVENT_LINGER_MS")
+                .unwrap_or("50".to_string()),
+            exactly_once: sink_env
+                .var("BALANCE_EVENT_EMITTER_EXACTLY_ONCE")
+                .unwrap_or("false".to_string())
+                .parse::<bool>()
+                .unwrap_or(false),
+            // also keys the offset record, so every subscription needs its own
+            transactional_id: sink_env
+                .own_var("BALANCE_EVENT_EMITTER_TRANSACTIONAL_ID")
+                .unwrap_or_else(|_| match sink_env.subscription_name() {
+                    Some(name) => format!("{DEFAULT_TRANSACTIONAL_ID}.{name}"),
+                    None => DEFAULT_TRANSACTIONAL_ID.to_string(),
+                }),
+            offset_topic: sink_env
+                .var("BALANCE_EVENT_OFFSET_TOPIC")
+                .unwrap_or("balance.event.offset".to_string()),
+        }
+    }
+}
+
+/// Publishes to `BALANCE_EVENT_TOPIC`.
+///
+/// In exactly-once mode each batch and its offset are produced in one Kafka transaction
+/// and the offset is owned by the sink; otherwise the emitter stores it.
+pub struct KafkaEventSink {
+    config: KafkaEventSinkConfig,
+    value_encoding: KafkaValueEncoding,
+    producer: Mutex<FutureProducer>,
+    /// Partition count of the topic, only looked up for round-robin.
+    partition_count: u32,
+    next_partition: AtomicU32,
+}
+
+impl KafkaEventSink {
+    pub fn new(
+        config: KafkaEventSinkConfig,
+        value_encoding: KafkaValueEncoding,
+    ) -> KafkaResult<Self> {
+        let producer = Self::new_producer(&config)?;
+        let partition_count = match config.partitioning {
+            KafkaPartitioning::RoundRobin => Self::fetch_partition_count(&producer, &config.topic)?,
+            _ => 0,
+        };
+        if config.exactly_once {
+            info!(
+                "Kafka event sink runs exactly-once as {}",
+                config.transactional_id
+            );
+        }
+        Ok(Self {
+            config,
+            value_encoding,
+            producer: Mutex::new(producer),
+            partition_count,
+            next_partition: AtomicU32::new(0),
+        })
+    }
+
+    fn fetch_partition_count(producer: &FutureProducer, topic: &str) -> KafkaResult<u32> {
+        let metadata = producer
+            .client()
+            .fetch_metadata(Some(topic), METADATA_TIMEOUT)?;
+        match metadata
+            .topics()
+            .first()
+            .map(|topic| topic.partitions().len())
+        {
+            Some(partition_count) if partition_count > 0 => Ok(partition_count as u32),
+            _ => Err(KafkaError::MetadataFetch(
+                RDKafkaErrorCode::UnknownTopicOrPartition,
+            )),
+        }
+    }
+
+    fn new_producer(config: &KafkaEventSinkConfig) -> KafkaResult<FutureProducer> {
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", &config.brokers)
+            // .set("queue.buffering.max.messages", "1000000")
+            .set("batch.num.messages", &config.batch_size)
+            .set("linger.ms", &config.linger_ms)
+            .set("compression.type", "lz4")
+            .set("acks", "all")
+            .set("enable.idempotence", "true");
+        if config.exactly_once {
+            client_config.set("transactional.id", &config.transactional_id);
+        }
+
+        let producer: FutureProducer = client_config.create()?;
+        if config.exactly_once {
+            // fences any previous instance and aborts its open transaction
+            producer.init_transactions(TRANSACTION_TIMEOUT)?;
+        }
+        Ok(producer)
+    }
+}
+
+impl EventSink for KafkaEventSink {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    fn publish<'a>(&'a self, events: &'a [BalanceEventData]) -> EventSinkFuture<'a> {
+        Box::pin(async move {
+            let producer = self.producer.lock().unwrap().clone();
+            self.send_all(&producer, events)
+                .await
+                .map_err(SendFailure::into_sink_error)
+        })
+    }
+
+    fn publish_with_offset<'a>(
+        &'a self,
+        events: &'a [BalanceEventData],
+        offset: EventId,
+    ) -> EventSinkFuture<'a> {
+        Box::pin(async move {
+            if !self.config.exactly_once {
+                return Err(EventSinkError::OffsetUnavailable(
+                    "the offset is only owned in exactly-once mode".to_string(),
+                ));
+            }
+            let producer = self.producer.lock().unwrap().clone();
+            self.send_in_transaction(&producer, events, offset)
+                .await
+                .map_err(|send_failure| {
+                    self.reset_producer(&producer);
+                    // the aborted transaction drops the whole batch, so a rejection only
+                    // pinpoints the record when it is the first one
+                    match send_failure.last_delivered {
+                        None => send_failure.into_sink_error(),
+                        Some(_) => {
+                            EventSinkError::DeliveryFailed(format!("{:?}", send_failure.error))
+                        }
+                    }
+                })
+        })
+    }
+
+    fn owns_offset(&self) -> bool {
+        self.config.exactly_once
+    }
+
+    fn read_owned_offset(&self) -> Result<Option<EventId>, EventSinkError> {
+        read_committed_offset(
+            &self.config.brokers,
+            &self.config.offset_topic,
+            &self.config.transactional_id,
+        )
+        .map_err(|kafka_error| EventSinkError::OffsetUnavailable(format!("{kafka_error:?}")))
+    }
+}
+
+/// Where a batch stopped: `last_delivered` and every event before it are acknowledged,
+/// `failed_event_id` is the first event that is not.
+struct SendFailure {
+    last_delivered: Option<EventId>,
+    failed_event_id: EventId,
+    error: KafkaError,
+}
+
+impl SendFailure {
+    /// Failure of the batch as a whole, such as an aborted transaction.
+    fn whole_batch(error: KafkaError, events: &[BalanceEventData]) -> Self {
+        Self {
+            last_delivered: None,
+            failed_event_id: events.first().map(|event| event.id).unwrap_or_default(),
+            error,
+        }
+    }
+
+    /// Errors the broker or librdkafka raise for the record itself, which no retry fixes.
+    fn is_rejection(&self) -> bool {
+        matches!(
+            self.error,
+            KafkaError::MessageProduction(
+                RDKafkaErrorCode::MessageSizeTooLarge
+                    | RDKafkaErrorCode::InvalidMessage
+                    | RDKafkaErrorCode::InvalidMessageSize
+                    | RDKafkaErrorCode::InvalidRecord
+                    | RDKafkaErrorCode::PolicyViolation
+            )
+        )
+    }
+
+    fn into_sink_error(self) -> EventSinkError {
+        let message = format!("{:?}", self.error);
+        if self.is_rejection() {
+            return EventSinkError::Rejected {
+                event_id: self.failed_event_id,
+                message,
+            };
+        }
+        match self.last_delivered {
+            Some(last_delivered) => EventSinkError::PartiallyDelivered {
+                last_delivered,
+                message,
+            },
+            None => EventSinkError::DeliveryFailed(message),
+        }
+    }
+}
+
+impl KafkaEventSink {
+    /// Enqueues every record of the batch before awaiting any acknowledgement, so the whole
+    /// batch is in flight at once. On failure, returns the last event before the first failed
+    /// one whose records were all acknowledged.
+    async fn send_all(
+        &self,
+        producer: &FutureProducer,
+        events: &[BalanceEventData],
+    ) -> Result<(), SendFailure> {
+        let mut deliveries = Vec::with_capacity(events.len());
+        let mut enqueue_failure = None;
+        for event in events {
+            match self.enqueue(producer, event).await {
+                Ok(event_deliveries) => deliveries.push((event.id, event_deliveries)),
+                Err(kafka_error) => {
+                    enqueue_failure = Some((event.id, kafka_error));
+                    break;
+                }
+            }
+        }
+
+        let mut last_delivered = None;
+        for (event_id, event_deliveries) in deliveries {
+            for delivery in event_deliveries {
+                let error = match delivery.await {
+                    Ok(Ok(_)) => continue,
+                    Ok(Err((kafka_error, _))) => kafka_error,
+                    Err(_) => KafkaError::Canceled,
+                };
+                return Err(SendFailure {
+                    last_delivered,
+                    failed_event_id: event_id,
+                    error,
+                });
+            }
+            last_delivered = Some(event_id);
+        }
+
+        match enqueue_failure {
+            Some((failed_event_id, error)) => Err(SendFailure {
+                last_delivered,
+                failed_event_id,
+                error,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Produces the batch and `offset` to the offset topic in one transaction.
+    async fn send_in_transaction(
+        &self,
+        producer: &FutureProducer,
+        events: &[BalanceEventData],
+        offset: EventId,
+    ) -> Result<(), SendFailure> {
+        let transaction_failure = |kafka_error| SendFailure::whole_batch(kafka_error, events);
+
+        producer.begin_transaction().map_err(transaction_failure)?;
+        self.send_all(producer, events).await?;
+
+        let offset_payload = encode_offset(offset);
+        let offset_record = FutureRecord::to(&self.config.offset_topic)
+            .key(self.config.transactional_id.as_str())
+            .payload(&offset_payload[..]);
+        producer
+            .send(offset_record, SEND_TIMEOUT)
+            .await
+            .map_err(|(kafka_error, _)| transaction_failure(kafka_error))?;
+
+        producer
+            .commit_transaction(TRANSACTION_TIMEOUT)
+            .map_err(transaction_failure)
+    }
+
+    /// Aborts the failed transaction; a producer in a fatal state is replaced by a new one,
+    /// whose `init_transactions` aborts the transaction instead.
+    fn reset_producer(&self, producer: &FutureProducer) {
+        if producer.abort_transaction(TRANSACTION_TIMEOUT).is_ok() {
+            return;
+        }
+        match Self::new_producer(&self.config) {
+            Ok(new_producer) => *self.producer.lock().unwrap() = new_producer,
+            Err(create_error) => error!("Failed to recreate producer: {create_error:?}"),
+        }
+    }
+
+    /// Key and partition of each record published for the event. Only
+    /// `BalanceIdBothSides` publishes more than one, for transfers.
+    fn routes(&self, event: &BalanceEventData) -> Vec<(Option<String>, Option<i32>)> {
+        match self.config.partitioning {
+            KafkaPartitioning::BalanceId => {
+                vec![(Some(event.payload.balance_id().to_string()), None)]
+            }
+            KafkaPartitioning::BalanceIdBothSides => {
+                let balance_id = event.payload.balance_id();
+                let counterparty_balance_id = event
+                    .payload
+                    .counterparty_balance_id()
+                    .filter(|counterparty_balance_id| *counterparty_balance_id != balance_id);
+                std::iter::once(balance_id)
+                    .chain(counterparty_balance_id)
+                    .map(|key_balance_id| (Some(key_balance_id.to_string()), None))
+                    .collect()
+            }
+            KafkaPartitioning::EventId => vec![(Some(event.id.to_string()), None)],
+            KafkaPartitioning::RoundRobin => {
+                let partition =
+                    self.next_partition.fetch_add(1, Ordering::Relaxed) % self.partition_count;
+                vec![(None, Some(partition as i32))]
+            }
+        }
+    }
+
+    async fn enqueue(
+        &self,
+        producer: &FutureProducer,
+        event: &BalanceEventData,
+    ) -> KafkaResult<Vec<DeliveryFuture>> {
+        let (payload, content_type) = match &self.value_encoding {
+            KafkaValueEncoding::Json(envelope) => {
+                (envelope.encode(event).into_bytes(), envelope.content_type())
+            }
+            KafkaValueEncoding::Protobuf(schema_id) => {
+                (encode_framed(*schema_id, event), PROTOBUF_CONTENT_TYPE)
+            }
+        };
+        let schema_version = event.schema_version.to_string();
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: "event_type",
+                value: Some(event.event_type().name()),
+            })
+            .insert(Header {
+                key: "schema_version",
+                value: Some(schema_version.as_str()),
+            })
+            .insert(Header {
+                key: "content-type",
+                value: Some(content_type),
+            });
+
+        let mut deliveries = Vec::with_capacity(1);
+        for (key, partition) in self.routes(event) {
+            let mut record = FutureRecord::<String, Vec<u8>>::to(&self.config.topic)
+                .payload(&payload)
+                .headers(headers.clone());
+            if let Some(key) = &key {
+                record = record.key(key);
+            }
+            if let Some(partition) = partition {
+                record = record.partition(partition);
+            }
+            deliveries.push(Self::enqueue_record(producer, record).await?);
+        }
+        Ok(deliveries)
+    }
+
+    /// Hands the record to librdkafka without waiting for the broker, retrying for up to
+    /// `SEND_TIMEOUT` while its local queue is full.
+    async fn enqueue_record(
+        producer: &FutureProducer,
+        mut record: FutureRecord<'_, String, Vec<u8>>,
+    ) -> KafkaResult<DeliveryFuture> {
+        let deadline = Instant::now() + SEND_TIMEOUT;
+        loop {
+            match producer.send_result(record) {
+                Ok(delivery) => return Ok(delivery),
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned))
+                    if Instant::now() < deadline =>
+                {
+                    record = returned;
+                    time::sleep(QUEUE_FULL_BACKOFF).await;
+                }
+                Err((kafka_error, _)) => return Err(kafka_error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use rdkafka::{
+        Message, Offset, TopicPartitionList,
+        consumer::{BaseConsumer, Consumer},
+        mocking::MockCluster,
+        producer::DefaultProducerContext,
+    };
+
+    use super::*;
+    use crate::{
+        core::domain::balance_event::{
+            BalanceCreatedEvent, BalanceDepositedEvent, BalanceEventPayload,
+            BalanceTransferredEvent, BalanceWithdrawnEvent,
+        },
+        infrastructure::event_sink::event_envelope::EventEnvelopeFormat,
+    };
+
+    const TOPIC: &str = "balance.event";
+    const OFFSET_TOPIC: &str = "balance.event.offset";
+
+    fn config(brokers: String, partitioning: KafkaPartitioning) -> KafkaEventSinkConfig {
+        KafkaEventSinkConfig {
+            brokers,
+            topic: TOPIC.to_string(),
+            encoding: KafkaEventEncoding::Json,
+            partitioning,
+            batch_size: "10000".to_string(),
+            linger_ms: "0".to_string(),
+            exactly_once: true,
+            transactional_id: format!("{DEFAULT_TRANSACTIONAL_ID}.test"),
+            offset_topic: OFFSET_TOPIC.to_string(),
+        }
+    }
+
+    fn json() -> KafkaValueEncoding {
+        KafkaValueEncoding::Json(EventEnvelope {
+            format: EventEnvelopeFormat::Plain,
+            source: "/actor-bank".to_string(),
+            schema_base_url: None,
+        })
+    }
+
+    /// Exactly-once sink against a local stand-in broker, which has to outlive it.
+    fn exactly_once_sink() -> (MockCluster<'static, DefaultProducerContext>, KafkaEventSink) {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic(TOPIC, 1, 1).unwrap();
+        cluster.create_topic(OFFSET_TOPIC, 1, 1).unwrap();
+        let config = config(cluster.bootstrap_servers(), KafkaPartitioning::BalanceId);
+        let sink = KafkaEventSink::new(config, json()).unwrap();
+        (cluster, sink)
+    }
+
+    /// At-least-once sink spreading records over a three-partition topic.
+    fn partitioned_sink(
+        partitioning: KafkaPartitioning,
+    ) -> (MockCluster<'static, DefaultProducerContext>, KafkaEventSink) {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic(TOPIC, 3, 1).unwrap();
+        let mut config = config(cluster.bootstrap_servers(), partitioning);
+        config.exactly_once = false;
+        let sink = KafkaEventSink::new(config, json()).unwrap();
+        (cluster, sink)
+    }
+
+    fn event(id: EventId, payload: BalanceEventPayload) -> BalanceEventData {
+        BalanceEventData {
+            id,
+            schema_version: payload.event_type().schema_version(),
+            event_time: 0,
+            payload,
+        }
+    }
+
+    fn created_event(id: EventId) -> BalanceEventData {
+        event(
+            id,
+            BalanceEventPayload::BalanceCreated(BalanceCreatedEvent { id }),
+        )
+    }
+
+    /// Every event type touching balance 7, the transfer going from 7 to 8.
+    fn events_of_balance_7() -> Vec<BalanceEventData> {
+        vec![
+            event(
+                1,
+                BalanceEventPayload::BalanceCreated(BalanceCreatedEvent { id: 7 }),
+            ),
+            event(
+                2,
+                BalanceEventPayload::BalanceDeposited(BalanceDepositedEvent { id: 7, amount: 5 }),
+            ),
+            event(
+                3,
+                BalanceEventPayload::BalanceWithdrawn(BalanceWithdrawnEvent { id: 7, amount: 2 }),
+            ),
+            event(
+                4,
+                BalanceEventPayload::BalanceTransferred(BalanceTransferredEvent {
+                    from_id: 7,
+                    to_id: 8,
+                    amount: 1,
+                }),
+            ),
+        ]
+    }
+
+    fn keys(sink: &KafkaEventSink, event: &BalanceEventData) -> Vec<String> {
+        sink.routes(event)
+            .into_iter()
+            .map(|(key, _)| key.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn balance_id_keys_every_event_of_a_balance_alike() {
+        let (_cluster, sink) = partitioned_sink(KafkaPartitioning::BalanceId);
+
+        for event in events_of_balance_7() {
+            assert_eq!(keys(&sink, &event), vec!["7"], "event {}", event.id);
+        }
+    }
+
+    #[test]
+    fn balance_id_both_sides_publishes_a_transfer_under_each_balance() {
+        let (_cluster, sink) = partitioned_sink(KafkaPartitioning::BalanceIdBothSides);
+        let events = events_of_balance_7();
+
+        for event in &events[..3] {
+            assert_eq!(keys(&sink, event), vec!["7"], "event {}", event.id);
+        }
+        assert_eq!(keys(&sink, &events[3]), vec!["7", "8"]);
+        let deposit_to_8 = event(
+            5,
+            BalanceEventPayload::BalanceDeposited(BalanceDepositedEvent { id: 8, amount: 1 }),
+        );
+        assert_eq!(keys(&sink, &deposit_to_8), vec!["8"]);
+    }
+
+    #[test]
+    fn balance_id_both_sides_publishes_a_transfer_to_itself_once() {
+        let (_cluster, sink) = partitioned_sink(KafkaPartitioning::BalanceIdBothSides);
+        let self_transfer = event(
+            1,
+            BalanceEventPayload::BalanceTransferred(BalanceTransferredEvent {
+                from_id: 7,
+                to_id: 7,
+                amount: 1,
+            }),
+        );
+
+        assert_eq!(keys(&sink, &self_transfer), vec!["7"]);
+    }
+
+    #[test]
+    fn event_id_keys_each_event_by_its_id() {
+        let (_cluster, sink) = partitioned_sink(KafkaPartitioning::EventId);
+
+        for event in events_of_balance_7() {
+            assert_eq!(keys(&sink, &event), vec![event.id.to_string()]);
+        }
+    }
+
+    #[test]
+    fn round_robin_takes_the_partitions_in_turn() {
+        let (_cluster, sink) = partitioned_sink(KafkaPartitioning::RoundRobin);
+
+        let routes: Vec<_> = events_of_balance_7()
+            .iter()
+            .flat_map(|event| sink.routes(event))
+            .collect();
+
+        assert_eq!(
+            routes,
+            vec![
+                (None, Some(0)),
+                (None, Some(1)),
+                (None, Some(2)),
+                (None, Some(0))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn events_of_each_balance_arrive_in_order_on_one_partition() {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic(TOPIC, 3, 1).unwrap();
+        let mut config = config(cluster.bootstrap_servers(), KafkaPartitioning::BalanceId);
+        config.exactly_once = false;
+        let sink = KafkaEventSink::new(config, json()).unwrap();
+        // deposits to balances 1 to 4 in turn
+        let events: Vec<_> = (1..=20)
+            .map(|event_id| {
+                let balance_id = (event_id - 1) % 4 + 1;
+                event(
+                    event_id,
+                    BalanceEventPayload::BalanceDeposited(BalanceDepositedEvent {
+                        id: balance_id,
+                        amount: 1,
+                    }),
+                )
+            })
+            .collect();
+
+        sink.publish(&events).await.unwrap();
+
+        let mut partitions_by_balance: BTreeMap<String, BTreeSet<i32>> = BTreeMap::new();
+        let mut event_ids_by_balance: BTreeMap<String, Vec<EventId>> = BTreeMap::new();
+        for partition in 0..3 {
+            for (key, event_id) in partition_records(&cluster.bootstrap_servers(), partition) {
+                partitions_by_balance
+                    .entry(key.clone())
+                    .or_default()
+                    .insert(partition);
+                event_ids_by_balance.entry(key).or_default().push(event_id);
+            }
+        }
+        assert_eq!(event_ids_by_balance.len(), 4);
+        for (balance_id, event_ids) in event_ids_by_balance {
+            let first_event_id = balance_id.parse::<EventId>().unwrap();
+            let expected: Vec<_> = (first_event_id..=20).step_by(4).collect();
+            assert_eq!(event_ids, expected, "balance {balance_id}");
+            assert_eq!(partitions_by_balance[&balance_id].len(), 1);
+        }
+        // the keys spread the balances, they do not all share a partition
+        let used_partitions: BTreeSet<_> = partitions_by_balance.values().flatten().collect();
+        assert!(used_partitions.len() > 1);
+    }
+
+    #[tokio::test]
+    async fn transaction_commits_the_batch_end_offset() {
+        let (_cluster, sink) = exactly_once_sink();
+
+        // events 2 and 3 were filtered out of the batch
+        sink.publish_with_offset(&[created_event(1)], 3)
+            .await
+            .unwrap();
+
+        assert_eq!(sink.read_owned_offset().unwrap(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn empty_transaction_moves_the_offset() {
+        let (_cluster, sink) = exactly_once_sink();
+        sink.publish_with_offset(&[created_event(1)], 1)
+            .await
+            .unwrap();
+
+        sink.publish_with_offset(&[], 5).await.unwrap();
+
+        assert_eq!(sink.read_owned_offset().unwrap(), Some(5));
+    }
+
+    #[tokio::test]
+    async fn no_owned_offset_before_the_first_transaction() {
+        let (_cluster, sink) = exactly_once_sink();
+
+        assert_eq!(sink.read_owned_offset().unwrap(), None);
+    }
+
+    /// Key and event id of the records of one partition of the topic, as a `read_committed`
+    /// consumer sees them.
+    fn partition_records(brokers: &str, partition: i32) -> Vec<(String, EventId)> {
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("group.id", "committed-event-reader")
+            .set("enable.partition.eof", "true")
+            .set("isolation.level", "read_committed")
+            .create()
+            .unwrap();
+        let mut assignment = TopicPartitionList::new();
+        assignment
+            .add_partition_offset(TOPIC, partition, Offset::Beginning)
+            .unwrap();
+        consumer.assign(&assignment).unwrap();
+
+        let mut records = vec![];
+        loop {
+            match consumer.poll(Duration::from_secs(10)) {
+                Some(Ok(message)) => {
+                    let key = String::from_utf8_lossy(message.key().unwrap_or_default());
+                    let event: serde_json::Value =
+                        serde_json::from_slice(message.payload().unwrap()).unwrap();
+                    records.push((key.into_owned(), event["id"].as_u64().unwrap()));
+                }
+                Some(Err(KafkaError::PartitionEOF(_))) => return records,
+                other => panic!("Unexpected poll result: {other:?}"),
+            }
+        }
+    }
+
+    fn committed_event_ids(brokers: &str) -> Vec<EventId> {
+        partition_records(brokers, 0)
+            .into_iter()
+            .map(|(_, event_id)| event_id)
+            .collect()
+    }
+
+    /// Publishes the events after the owned offset up to `last_event_id`, like the emitter
+    /// does when it starts.
+    async fn resume(sink: &KafkaEventSink, last_event_id: EventId) {
+        let offset = sink.read_owned_offset().unwrap().unwrap_or(0);
+        let events: Vec<_> = (offset + 1..=last_event_id).map(created_event).collect();
+        sink.publish_with_offset(&events, last_event_id)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn restart_before_the_commit_neither_duplicates_nor_loses_events() {
+        let (cluster, sink) = exactly_once_sink();
+        sink.publish_with_offset(&[created_event(1), created_event(2)], 2)
+            .await
+            .unwrap();
+        // the instance dies with a transaction open and its records sent
+        let producer = sink.producer.lock().unwrap().clone();
+        producer.begin_transaction().unwrap();
+        let sent = sink
+            .send_all(&producer, &[created_event(3), created_event(4)])
+            .await;
+        assert!(sent.is_ok());
+        std::mem::forget(sink);
+
+        let config = config(cluster.bootstrap_servers(), KafkaPartitioning::BalanceId);
+        let restarted_sink = KafkaEventSink::new(config, json()).unwrap();
+        assert_eq!(restarted_sink.read_owned_offset().unwrap(), Some(2));
+        resume(&restarted_sink, 5).await;
+
+        assert_eq!(restarted_sink.read_owned_offset().unwrap(), Some(5));
+        // the mock broker lists no aborted transactions in fetch responses, so it hands the
+        // aborted 3 and 4 to read_committed consumers too; a real broker filters them out
+        let event_ids = committed_event_ids(&cluster.bootstrap_servers());
+        assert_eq!(event_ids[..2], [1, 2]);
+        assert_eq!(event_ids[event_ids.len() - 3..], [3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn restart_after_the_commit_neither_duplicates_nor_loses_events() {
+        let (cluster, sink) = exactly_once_sink();
+        sink.publish_with_offset(&[created_event(1), created_event(2)], 2)
+            .await
+            .unwrap();
+        drop(sink);
+
+        let config = config(cluster.bootstrap_servers(), KafkaPartitioning::BalanceId);
+        let restarted_sink = KafkaEventSink::new(config, json()).unwrap();
+        assert_eq!(restarted_sink.read_owned_offset().unwrap(), Some(2));
+        resume(&restarted_sink, 4).await;
+
+        assert_eq!(
+            committed_event_ids(&cluster.bootstrap_servers()),
+            vec![1, 2, 3, 4]
+        );
+    }
+
+    #[tokio::test]
+    async fn offset_is_only_owned_in_exactly_once_mode() {
+        let cluster = MockCluster::new(1).unwrap();
+        let mut config = config(cluster.bootstrap_servers(), KafkaPartitioning::BalanceId);
+        config.exactly_once = false;
+        let sink = KafkaEventSink::new(config, json()).unwrap();
+
+        assert!(!sink.owns_offset());
+        assert!(sink.publish_with_offset(&[], 1).await.is_err());
+    }
+
+    #[test]
+    fn transactional_id_defaults_to_the_subscription_name() {
+        let subscription_config =
+            KafkaEventSinkConfig::from_env(&SinkEnv::for_subscription("audit"));
+        let global_config = KafkaEventSinkConfig::from_env(&SinkEnv::global());
+
+        assert_eq!(
+            subscription_config.transactional_id,
+            "actor-bank.balance-event-emitter.audit"
+        );
+        assert_eq!(
+            global_config.transactional_id,
+            "actor-bank.balance-event-emitter"
+        );
+    }
+}
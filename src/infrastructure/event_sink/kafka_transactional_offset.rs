@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use rdkafka::{
+    ClientConfig, Message, Offset, TopicPartitionList,
+    consumer::{BaseConsumer, Consumer},
+    error::{KafkaError, KafkaResult},
+    types::RDKafkaErrorCode,
+};
+
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn encode_offset(offset: u64) -> [u8; 8] {
+    offset.to_be_bytes()
+}
+
+/// Reads the last emitted event id committed under `transactional_id` in the compacted,
+/// single-partition offset topic, `None` when nothing was committed under it yet.
+///
+/// Call it after `init_transactions`: that aborts any transaction left open by a previous
+/// instance, which would otherwise hold back the read_committed end of the partition.
+pub fn read_committed_offset(
+    brokers: &str,
+    offset_topic: &str,
+    transactional_id: &str,
+) -> KafkaResult<Option<u64>> {
+    let consumer: BaseConsumer = ClientConfig::new()
+        .set("bootstrap.servers", brokers)
+        .set("group.id", format!("{transactional_id}.offset-reader"))
+        .set("enable.auto.commit", "false")
+        .set("enable.partition.eof", "true")
+        .set("isolation.level", "read_committed")
+        .create()?;
+
+    let mut assignment = TopicPartitionList::new();
+    assignment.add_partition_offset(offset_topic, 0, Offset::Beginning)?;
+    consumer.assign(&assignment)?;
+
+    let mut offset = None;
+    loop {
+        match consumer.poll(READ_TIMEOUT) {
+            Some(Ok(message)) => {
+                if message.key() != Some(transactional_id.as_bytes()) {
+                    continue;
+                }
+                if let Some(Ok(bytes)) = message.payload().map(<[u8; 8]>::try_from) {
+                    offset = Some(u64::from_be_bytes(bytes));
+                }
+            }
+            Some(Err(KafkaError::PartitionEOF(_))) => return Ok(offset),
+            Some(Err(error)) => return Err(error),
+            None => {
+                return Err(KafkaError::MessageConsumption(
+                    RDKafkaErrorCode::OperationTimedOut,
+                ));
+            }
+        }
+    }
+}
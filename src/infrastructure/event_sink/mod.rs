@@ -0,0 +1,18 @@
+pub mod event_envelope;
+pub mod event_sink_config;
+pub mod event_sink_offset_db;
+pub mod event_subscription;
+pub mod file_dead_letter_queue;
+pub mod file_event_sink;
+pub mod http_event_sink;
+#[cfg(feature = "kafka")]
+pub mod kafka_dead_letter_queue;
+#[cfg(feature = "kafka")]
+pub mod kafka_event_sink;
+#[cfg(feature = "kafka")]
+pub mod kafka_transactional_offset;
+#[cfg(any(feature = "kafka", feature = "grpc"))]
+pub mod protobuf_event_encoding;
+#[cfg(feature = "kafka")]
+pub mod schema_registry;
+pub mod stdout_event_sink;
@@ -0,0 +1,229 @@
+use prost::Message;
+
+#[cfg(feature = "kafka")]
+use crate::infrastructure::event_sink::schema_registry::SchemaId;
+use crate::{
+    application::balance::api::balance_event_api::BalanceEventData,
+    core::domain::balance_event::BalanceEventPayload,
+};
+
+/// Source of the schema registered for the topic, kept next to the JSON Schemas.
+#[cfg(feature = "kafka")]
+pub const BALANCE_EVENT_PROTO_SCHEMA: &str =
+    include_str!("../../../schemas/proto/balance_event.v1.proto");
+
+#[cfg(feature = "kafka")]
+pub const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Schema registry wire format: magic byte, big-endian schema id, then the protobuf message
+/// indexes (a single `0` for the first message of the schema) and the message itself.
+#[cfg(feature = "kafka")]
+const MAGIC_BYTE: u8 = 0;
+#[cfg(feature = "kafka")]
+const FIRST_MESSAGE_INDEX: u8 = 0;
+#[cfg(feature = "kafka")]
+const FRAME_HEADER_SIZE: usize = 6;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BalanceEventProto {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(uint32, tag = "2")]
+    pub schema_version: u32,
+    #[prost(uint64, tag = "3")]
+    pub event_time: u64,
+    #[prost(oneof = "balance_event_proto::Payload", tags = "10, 11, 12, 13")]
+    pub payload: Option<balance_event_proto::Payload>,
+}
+
+pub mod balance_event_proto {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Payload {
+        #[prost(message, tag = "10")]
+        BalanceCreated(super::BalanceCreatedProto),
+        #[prost(message, tag = "11")]
+        BalanceDeposited(super::BalanceDepositedProto),
+        #[prost(message, tag = "12")]
+        BalanceWithdrawn(super::BalanceWithdrawnProto),
+        #[prost(message, tag = "13")]
+        BalanceTransferred(super::BalanceTransferredProto),
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BalanceCreatedProto {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BalanceDepositedProto {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BalanceWithdrawnProto {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BalanceTransferredProto {
+    #[prost(uint64, tag = "1")]
+    pub from_id: u64,
+    #[prost(uint64, tag = "2")]
+    pub to_id: u64,
+    #[prost(string, tag = "3")]
+    pub amount: String,
+}
+
+impl From<&BalanceEventData> for BalanceEventProto {
+    fn from(event: &BalanceEventData) -> Self {
+        use balance_event_proto::Payload;
+
+        let payload = match &event.payload {
+            BalanceEventPayload::BalanceCreated(created) => {
+                Payload::BalanceCreated(BalanceCreatedProto { id: created.id })
+            }
+            BalanceEventPayload::BalanceDeposited(deposited) => {
+                Payload::BalanceDeposited(BalanceDepositedProto {
+                    id: deposited.id,
+                    amount: deposited.amount.to_string(),
+                })
+            }
+            BalanceEventPayload::BalanceWithdrawn(withdrawn) => {
+                Payload::BalanceWithdrawn(BalanceWithdrawnProto {
+                    id: withdrawn.id,
+                    amount: withdrawn.amount.to_string(),
+                })
+            }
+            BalanceEventPayload::BalanceTransferred(transferred) => {
+                Payload::BalanceTransferred(BalanceTransferredProto {
+                    from_id: transferred.from_id,
+                    to_id: transferred.to_id,
+                    amount: transferred.amount.to_string(),
+                })
+            }
+        };
+        Self {
+            id: event.id,
+            schema_version: event.schema_version as u32,
+            event_time: event.event_time,
+            payload: Some(payload),
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+pub fn encode_framed(schema_id: SchemaId, event: &BalanceEventData) -> Vec<u8> {
+    let message = BalanceEventProto::from(event);
+    let mut bytes = Vec::with_capacity(FRAME_HEADER_SIZE + message.encoded_len());
+    bytes.push(MAGIC_BYTE);
+    bytes.extend_from_slice(&schema_id.to_be_bytes());
+    bytes.push(FIRST_MESSAGE_INDEX);
+    message.encode(&mut bytes).unwrap();
+    bytes
+}
+
+#[cfg(all(test, feature = "kafka"))]
+mod tests {
+    use super::*;
+    use crate::{
+        core::domain::balance_event::{
+            BalanceCreatedEvent, BalanceDepositedEvent, BalanceTransferredEvent,
+            BalanceWithdrawnEvent,
+        },
+        infrastructure::event_sink::schema_registry::{
+            FileSchemaRegistry, SchemaRegistry, value_subject,
+        },
+    };
+
+    fn events() -> Vec<BalanceEventData> {
+        let amount = u128::from(u64::MAX) + 1;
+        [
+            BalanceEventPayload::BalanceCreated(BalanceCreatedEvent { id: 1 }),
+            BalanceEventPayload::BalanceDeposited(BalanceDepositedEvent { id: 1, amount }),
+            BalanceEventPayload::BalanceWithdrawn(BalanceWithdrawnEvent { id: 1, amount: 7 }),
+            BalanceEventPayload::BalanceTransferred(BalanceTransferredEvent {
+                from_id: 1,
+                to_id: 2,
+                amount: 3,
+            }),
+        ]
+        .into_iter()
+        .zip(1..)
+        .map(|(payload, id)| BalanceEventData {
+            id,
+            schema_version: payload.event_type().schema_version(),
+            event_time: 1_700_000_000_000_000_000 + id,
+            payload,
+        })
+        .collect()
+    }
+
+    /// What a consumer reading `balance_event.v1.proto` gets back.
+    fn decoded_payload(payload: balance_event_proto::Payload) -> BalanceEventPayload {
+        use balance_event_proto::Payload;
+
+        match payload {
+            Payload::BalanceCreated(created) => {
+                BalanceEventPayload::BalanceCreated(BalanceCreatedEvent { id: created.id })
+            }
+            Payload::BalanceDeposited(deposited) => {
+                BalanceEventPayload::BalanceDeposited(BalanceDepositedEvent {
+                    id: deposited.id,
+                    amount: deposited.amount.parse().unwrap(),
+                })
+            }
+            Payload::BalanceWithdrawn(withdrawn) => {
+                BalanceEventPayload::BalanceWithdrawn(BalanceWithdrawnEvent {
+                    id: withdrawn.id,
+                    amount: withdrawn.amount.parse().unwrap(),
+                })
+            }
+            Payload::BalanceTransferred(transferred) => {
+                BalanceEventPayload::BalanceTransferred(BalanceTransferredEvent {
+                    from_id: transferred.from_id,
+                    to_id: transferred.to_id,
+                    amount: transferred.amount.parse().unwrap(),
+                })
+            }
+        }
+    }
+
+    #[test]
+    fn framed_events_round_trip_under_the_registered_schema_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = FileSchemaRegistry::new(dir.path().join("registry.json"));
+        registry
+            .register("other-value", "syntax = \"proto3\";")
+            .unwrap();
+        let schema_id = registry
+            .register(&value_subject("balance.event"), BALANCE_EVENT_PROTO_SCHEMA)
+            .unwrap();
+
+        for event in events() {
+            let bytes = encode_framed(schema_id, &event);
+
+            assert_eq!(bytes[0], MAGIC_BYTE);
+            assert_eq!(
+                SchemaId::from_be_bytes(bytes[1..5].try_into().unwrap()),
+                schema_id
+            );
+            assert_eq!(bytes[5], FIRST_MESSAGE_INDEX);
+            let message = BalanceEventProto::decode(&bytes[FRAME_HEADER_SIZE..]).unwrap();
+            assert_eq!(message.id, event.id);
+            assert_eq!(message.schema_version, event.schema_version as u32);
+            assert_eq!(message.event_time, event.event_time);
+            assert_eq!(
+                serde_json::to_string(&decoded_payload(message.payload.unwrap())).unwrap(),
+                serde_json::to_string(&event.payload).unwrap()
+            );
+        }
+    }
+}
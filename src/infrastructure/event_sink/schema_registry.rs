@@ -0,0 +1,209 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+pub type SchemaId = u32;
+
+#[derive(Debug)]
+pub enum SchemaRegistryError {
+    Unavailable(String),
+    CorruptRegistry(String),
+}
+
+impl fmt::Display for SchemaRegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaRegistryError::Unavailable(message) => {
+                write!(f, "Schema registry unavailable: {message}")
+            }
+            SchemaRegistryError::CorruptRegistry(message) => {
+                write!(f, "Corrupt schema registry: {message}")
+            }
+        }
+    }
+}
+
+impl Error for SchemaRegistryError {}
+
+impl From<io::Error> for SchemaRegistryError {
+    fn from(io_error: io::Error) -> Self {
+        SchemaRegistryError::Unavailable(io_error.to_string())
+    }
+}
+
+/// The subset of a Confluent-compatible schema registry the sinks need.
+pub trait SchemaRegistry: Send + Sync {
+    /// Returns the id of `schema` under `subject`, registering it as a new version when the
+    /// subject does not have it yet. Registering the same schema again returns the same id.
+    fn register(&self, subject: &str, schema: &str) -> Result<SchemaId, SchemaRegistryError>;
+}
+
+/// `<topic>-value`, the default subject naming strategy of the schema registry.
+pub fn value_subject(topic: &str) -> String {
+    format!("{topic}-value")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RegisteredSchema {
+    id: SchemaId,
+    version: u32,
+    schema: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RegistryFile {
+    subjects: BTreeMap<String, Vec<RegisteredSchema>>,
+}
+
+impl RegistryFile {
+    fn next_schema_id(&self) -> SchemaId {
+        self.subjects
+            .values()
+            .flatten()
+            .map(|registered| registered.id)
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+}
+
+/// Stand-in for a schema registry, kept as one JSON file. Ids are global across subjects
+/// like in the real registry, so records framed with them stay valid after switching over.
+pub struct FileSchemaRegistry {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileSchemaRegistry {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(PathBuf::from(
+            env::var("BALANCE_EVENT_SCHEMA_REGISTRY_PATH")
+                .unwrap_or("offheap/schema_registry.json".to_string()),
+        ))
+    }
+
+    fn load(&self) -> Result<RegistryFile, SchemaRegistryError> {
+        if !self.path.exists() {
+            return Ok(RegistryFile::default());
+        }
+        let bytes = fs::read(&self.path)?;
+        serde_json::from_slice(&bytes).map_err(|json_error| {
+            SchemaRegistryError::CorruptRegistry(format!("{}: {json_error}", self.path.display()))
+        })
+    }
+
+    /// Writes a sibling file and renames it over the registry, so a crash never leaves
+    /// a half written registry behind.
+    fn store(&self, registry: &RegistryFile) -> Result<(), SchemaRegistryError> {
+        if let Some(dir) = self.path.parent().filter(|dir| dir != &Path::new("")) {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(registry).unwrap())?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+impl SchemaRegistry for FileSchemaRegistry {
+    fn register(&self, subject: &str, schema: &str) -> Result<SchemaId, SchemaRegistryError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut registry = self.load()?;
+
+        if let Some(registered) = registry.subjects.get(subject).and_then(|versions| {
+            versions
+                .iter()
+                .find(|registered| registered.schema == schema)
+        }) {
+            return Ok(registered.id);
+        }
+
+        let id = registry.next_schema_id();
+        let versions = registry.subjects.entry(subject.to_string()).or_default();
+        let version = versions.len() as u32 + 1;
+        versions.push(RegisteredSchema {
+            id,
+            version,
+            schema: schema.to_string(),
+        });
+        self.store(&registry)?;
+
+        info!("Registered schema {id} as version {version} of {subject}");
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registering_a_schema_again_returns_its_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = FileSchemaRegistry::new(dir.path().join("registry.json"));
+
+        let id = registry.register("balance.event-value", "v1").unwrap();
+
+        assert_eq!(registry.register("balance.event-value", "v1").unwrap(), id);
+    }
+
+    #[test]
+    fn ids_are_global_across_subjects() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = FileSchemaRegistry::new(dir.path().join("registry.json"));
+
+        let first_id = registry.register("balance.event-value", "v1").unwrap();
+        let second_id = registry.register("balance.event-value", "v2").unwrap();
+        let other_subject_id = registry.register("audit-value", "v1").unwrap();
+
+        assert_eq!((first_id, second_id, other_subject_id), (1, 2, 3));
+    }
+
+    #[test]
+    fn registered_ids_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("registry.json");
+        let id = FileSchemaRegistry::new(path.clone())
+            .register("balance.event-value", "v1")
+            .unwrap();
+
+        let registry = FileSchemaRegistry::new(path);
+
+        assert_eq!(registry.register("balance.event-value", "v1").unwrap(), id);
+        assert_eq!(
+            registry.register("balance.event-value", "v2").unwrap(),
+            id + 1
+        );
+    }
+
+    #[test]
+    fn corrupt_registry_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("registry.json");
+        fs::write(&path, "{").unwrap();
+
+        let registry_error = FileSchemaRegistry::new(path)
+            .register("balance.event-value", "v1")
+            .unwrap_err();
+
+        assert!(matches!(
+            registry_error,
+            SchemaRegistryError::CorruptRegistry(_)
+        ));
+    }
+}
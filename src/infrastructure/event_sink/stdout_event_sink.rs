@@ -0,0 +1,30 @@
+use std::io::{self, Write};
+
+use crate::{
+    application::balance::{
+        api::balance_event_api::BalanceEventData,
+        spi::event_sink::{EventSink, EventSinkError, EventSinkFuture},
+    },
+    infrastructure::event_sink::event_envelope::EventEnvelope,
+};
+
+/// Writes one JSON line per event to stdout.
+pub struct StdoutEventSink {
+    pub envelope: EventEnvelope,
+}
+
+impl EventSink for StdoutEventSink {
+    fn name(&self) -> &str {
+        "stdout"
+    }
+
+    fn publish<'a>(&'a self, events: &'a [BalanceEventData]) -> EventSinkFuture<'a> {
+        let mut stdout = io::stdout().lock();
+        let result = events
+            .iter()
+            .try_for_each(|event| writeln!(stdout, "{}", self.envelope.encode(event)))
+            .and_then(|_| stdout.flush())
+            .map_err(|io_error| EventSinkError::DeliveryFailed(io_error.to_string()));
+        Box::pin(std::future::ready(result))
+    }
+}
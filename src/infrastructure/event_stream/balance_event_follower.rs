@@ -0,0 +1,206 @@
+use std::{sync::Arc, time::Duration};
+
+use log::{error, info};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc::{self, error::TrySendError},
+    },
+    task,
+    time::{self, Instant, Interval},
+};
+
+use crate::{
+    application::balance::api::balance_event_api::{BalanceEventApi, BalanceEventData},
+    core::domain::balance_event::EventId,
+    infrastructure::{
+        event_sink::event_subscription::EventFilter,
+        event_stream::balance_event_stream::BalanceEventStream,
+    },
+};
+
+const REPLAY_PAGE_SIZE: u64 = 1000;
+
+pub enum FollowedEvent {
+    Event(Arc<BalanceEventData>),
+    /// Nothing happened for a heartbeat interval.
+    Heartbeat,
+}
+
+pub struct FollowOptions {
+    pub filter: EventFilter,
+    /// Stored events after this one are sent before the live ones; `None` starts at the next
+    /// commit.
+    pub resume_after: Option<EventId>,
+    /// Events waiting for the client; a live client further behind is dropped.
+    pub buffer: usize,
+    pub heartbeat: Option<Duration>,
+}
+
+/// Feeds one remote client, such as an SSE connection or a gRPC subscription, until it goes
+/// away or stops keeping up.
+///
+/// A client that falls behind is not buffered without limit: its channel is closed, and it
+/// resumes from the last event id it received.
+struct BalanceEventFollower {
+    balance_event_api: Arc<BalanceEventApi>,
+    events: broadcast::Receiver<Arc<BalanceEventData>>,
+    filter: EventFilter,
+    sender: mpsc::Sender<FollowedEvent>,
+    heartbeat: Option<Duration>,
+    /// Live events up to this one were already replayed from the store.
+    last_event_id: EventId,
+}
+
+impl BalanceEventFollower {
+    async fn run(mut self, resume_after: Option<EventId>) {
+        if let Some(last_event_id) = resume_after
+            && !self.replay(last_event_id).await
+        {
+            return;
+        }
+        let mut heartbeat = self
+            .heartbeat
+            .map(|period| time::interval_at(Instant::now() + period, period));
+        loop {
+            let open = tokio::select! {
+                event = self.events.recv() => match event {
+                    Ok(event) => self.push(event),
+                    Err(RecvError::Lagged(missed)) => {
+                        info!("Event stream client {missed} events behind, disconnected");
+                        false
+                    }
+                    Err(RecvError::Closed) => false,
+                },
+                _ = next_heartbeat(&mut heartbeat) => self.try_send(FollowedEvent::Heartbeat),
+                _ = self.sender.closed() => false,
+            };
+            if !open {
+                return;
+            }
+        }
+    }
+
+    /// Stored events are sent at the pace of the client, nothing is dropped. Pages are read on
+    /// the blocking pool, off the thread serving the client's connection.
+    async fn replay(&mut self, mut last_event_id: EventId) -> bool {
+        loop {
+            let balance_event_api = self.balance_event_api.clone();
+            let page = task::spawn_blocking(move || {
+                balance_event_api.get_balance_events(last_event_id + 1, REPLAY_PAGE_SIZE)
+            })
+            .await;
+            let events = match page {
+                Ok(events) => events,
+                Err(join_error) => {
+                    error!("Event stream replay failed: {join_error}");
+                    return false;
+                }
+            };
+            let is_full_page = events.len() as u64 == REPLAY_PAGE_SIZE;
+            for result in events {
+                let event = match result {
+                    Ok(event) => event,
+                    Err(unreadable) => {
+                        error!("Event stream replay skips {unreadable}");
+                        last_event_id = unreadable.id;
+                        continue;
+                    }
+                };
+                last_event_id = event.id;
+                if self.filter.matches(&event)
+                    && self
+                        .sender
+                        .send(FollowedEvent::Event(Arc::new(event)))
+                        .await
+                        .is_err()
+                {
+                    return false;
+                }
+            }
+            self.last_event_id = last_event_id;
+            if !is_full_page {
+                return true;
+            }
+        }
+    }
+
+    fn push(&mut self, event: Arc<BalanceEventData>) -> bool {
+        if event.id <= self.last_event_id || !self.filter.matches(&event) {
+            return true;
+        }
+        self.try_send(FollowedEvent::Event(event))
+    }
+
+    fn try_send(&self, followed_event: FollowedEvent) -> bool {
+        match self.sender.try_send(followed_event) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                info!("Event stream client not reading, disconnected");
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+async fn next_heartbeat(heartbeat: &mut Option<Interval>) {
+    match heartbeat {
+        Some(heartbeat) => {
+            heartbeat.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+impl BalanceEventStream {
+    /// Starts feeding a client on the current runtime; the returned channel closes when the
+    /// client is dropped.
+    pub fn follow(&self, options: FollowOptions) -> mpsc::Receiver<FollowedEvent> {
+        // subscribed before reading the store, so no event falls between the replay and the
+        // stream
+        let events = self.subscribe();
+        let (sender, receiver) = mpsc::channel(options.buffer);
+        let follower = BalanceEventFollower {
+            balance_event_api: self.balance_event_api.clone(),
+            events,
+            filter: options.filter,
+            sender,
+            heartbeat: options.heartbeat,
+            last_event_id: 0,
+        };
+        tokio::spawn(follower.run(options.resume_after));
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::infrastructure::balance::balance_event_repository_rocksdb::tests::stored_events;
+
+    #[tokio::test]
+    async fn replay_skips_an_unreadable_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_db, _repository, balance_event_api) = stored_events(dir.path(), 4, &[2]);
+        let stream = BalanceEventStream::new(balance_event_api, Arc::new(Notify::new()));
+
+        let mut receiver = stream.follow(FollowOptions {
+            filter: EventFilter::default(),
+            resume_after: Some(0),
+            buffer: 16,
+            heartbeat: None,
+        });
+
+        let mut event_ids = vec![];
+        for _ in 0..3 {
+            match receiver.recv().await.unwrap() {
+                FollowedEvent::Event(event) => event_ids.push(event.id),
+                FollowedEvent::Heartbeat => panic!("heartbeats are off"),
+            }
+        }
+        assert_eq!(event_ids, vec![1, 3, 4]);
+    }
+}
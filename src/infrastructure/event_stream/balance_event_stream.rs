@@ -0,0 +1,146 @@
+use std::{env, sync::Arc};
+
+use log::{error, info};
+use tokio::sync::{Notify, broadcast};
+
+use crate::{
+    application::balance::api::balance_event_api::{BalanceEventApi, BalanceEventData},
+    core::domain::balance_event::EventId,
+};
+
+const READ_LIMIT: u64 = 1000;
+
+/// Committed events pushed to live subscribers of the process, such as WebSocket clients.
+///
+/// Woken by each commit of the ledger, it reads the new events from the store and broadcasts
+/// them in order. Nothing is kept for subscribers: one that falls more than
+/// `BALANCE_EVENT_STREAM_CAPACITY` events behind is told how many it missed, and catches up
+/// from `/balance-events` if it cares.
+pub struct BalanceEventStream {
+    pub balance_event_api: Arc<BalanceEventApi>,
+    commit_notify: Arc<Notify>,
+    sender: broadcast::Sender<Arc<BalanceEventData>>,
+}
+
+impl BalanceEventStream {
+    pub fn new(balance_event_api: Arc<BalanceEventApi>, commit_notify: Arc<Notify>) -> Self {
+        let capacity = env::var("BALANCE_EVENT_STREAM_CAPACITY")
+            .unwrap_or("4096".to_string())
+            .parse::<usize>()
+            .unwrap_or(4096);
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            balance_event_api,
+            commit_notify,
+            sender,
+        }
+    }
+
+    /// Receives the events committed from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<BalanceEventData>> {
+        self.sender.subscribe()
+    }
+
+    /// Starts after the last event stored at the time it is called.
+    pub async fn run(&self) {
+        let mut last_event_id = self.balance_event_api.last_event_id();
+        info!("Balance event stream started after event {last_event_id}");
+        loop {
+            self.commit_notify.notified().await;
+            last_event_id = self.broadcast_from(last_event_id);
+        }
+    }
+
+    /// Broadcasts the events after `last_event_id` and returns the last one broadcast.
+    fn broadcast_from(&self, mut last_event_id: EventId) -> EventId {
+        if self.sender.receiver_count() == 0 {
+            return self.balance_event_api.last_event_id();
+        }
+        loop {
+            let events = self
+                .balance_event_api
+                .get_balance_events(last_event_id + 1, READ_LIMIT);
+            let is_full_batch = events.len() as u64 == READ_LIMIT;
+            for result in events {
+                match result {
+                    Ok(event) => {
+                        last_event_id = event.id;
+                        // an error only means the last subscriber just left
+                        let _ = self.sender.send(Arc::new(event));
+                    }
+                    Err(unreadable) => {
+                        // the emitter reports it too; skipping keeps the stream alive
+                        error!("Balance event stream skips {unreadable}");
+                        last_event_id = unreadable.id;
+                    }
+                }
+            }
+            if !is_full_batch {
+                return last_event_id;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::{
+        balance::balance_event_repository_rocksdb::tests::{persist_created_events, stored_events},
+        event_sink::event_subscription::EventFilter,
+        event_stream::balance_event_follower::{FollowOptions, FollowedEvent},
+    };
+
+    async fn next_event_id(receiver: &mut tokio::sync::mpsc::Receiver<FollowedEvent>) -> EventId {
+        match receiver.recv().await.unwrap() {
+            FollowedEvent::Event(event) => event.id,
+            FollowedEvent::Heartbeat => panic!("heartbeats are off"),
+        }
+    }
+
+    #[test]
+    fn broadcast_skips_an_unreadable_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_db, _repository, balance_event_api) = stored_events(dir.path(), 3, &[2]);
+        let stream = BalanceEventStream::new(balance_event_api, Arc::new(Notify::new()));
+        let mut receiver = stream.subscribe();
+
+        assert_eq!(stream.broadcast_from(0), 3);
+
+        assert_eq!(receiver.try_recv().unwrap().id, 1);
+        assert_eq!(receiver.try_recv().unwrap().id, 3);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn broadcast_without_subscribers_skips_to_the_last_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_db, _repository, balance_event_api) = stored_events(dir.path(), 3, &[2]);
+        let stream = BalanceEventStream::new(balance_event_api, Arc::new(Notify::new()));
+
+        assert_eq!(stream.broadcast_from(0), 3);
+    }
+
+    #[tokio::test]
+    async fn resumed_client_gets_the_stored_events_then_the_live_ones_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, repository, balance_event_api) = stored_events(dir.path(), 5, &[]);
+        let stream = BalanceEventStream::new(balance_event_api, Arc::new(Notify::new()));
+        let mut receiver = stream.follow(FollowOptions {
+            filter: EventFilter::default(),
+            resume_after: Some(3),
+            buffer: 16,
+            heartbeat: None,
+        });
+
+        assert_eq!(next_event_id(&mut receiver).await, 4);
+        assert_eq!(next_event_id(&mut receiver).await, 5);
+
+        persist_created_events(&db, &repository, 1);
+        // broadcast from before the resume point: the replayed events are not sent again
+        stream.broadcast_from(3);
+
+        assert_eq!(next_event_id(&mut receiver).await, 6);
+        assert!(receiver.try_recv().is_err());
+    }
+}
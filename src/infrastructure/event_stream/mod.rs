@@ -0,0 +1,2 @@
+pub mod balance_event_follower;
+pub mod balance_event_stream;
@@ -1,5 +1,9 @@
 pub mod app_ioc;
 pub mod balance;
+pub mod event_log;
+pub mod event_sink;
+pub mod event_stream;
 pub mod rocksdb_transaction;
 pub mod scheduler;
 pub mod server_config;
+pub mod storage;
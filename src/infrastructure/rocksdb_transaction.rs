@@ -1,16 +1,26 @@
 use std::{cell::RefCell, rc::Rc, sync::Arc};
 
 use rust_rocksdb::{DBWithThreadMode, SingleThreaded, WriteBatch, WriteBatchWithTransaction};
+use tokio::sync::Notify;
 
 use crate::application::transaction_spi::{Transaction, TransactionContext};
 
 pub struct RocksdbTransaction {
     pub db: Arc<DBWithThreadMode<SingleThreaded>>,
+    /// Notified after every commit, so readers of the event store can wake up instead of polling.
+    /// One per reader: `notify_one` wakes a single waiter.
+    pub commit_notifies: Arc<Vec<Arc<Notify>>>,
 }
 
 impl RocksdbTransaction {
-    pub fn new(db: Arc<DBWithThreadMode<SingleThreaded>>) -> Self {
-        Self { db }
+    pub fn new(
+        db: Arc<DBWithThreadMode<SingleThreaded>>,
+        commit_notifies: Vec<Arc<Notify>>,
+    ) -> Self {
+        Self {
+            db,
+            commit_notifies: Arc::new(commit_notifies),
+        }
     }
 }
 
@@ -20,6 +30,8 @@ impl Transaction for RocksdbTransaction {
         let transaction_context = RocksdbTransactionContext {
             batch,
             db: self.db.clone(),
+            commit_notifies: self.commit_notifies.clone(),
+            abort_hooks: RefCell::new(vec![]),
         };
         Rc::new(transaction_context)
     }
@@ -28,14 +40,44 @@ impl Transaction for RocksdbTransaction {
 pub struct RocksdbTransactionContext {
     pub batch: Rc<RefCell<WriteBatchWithTransaction<false>>>,
     pub db: Arc<DBWithThreadMode<SingleThreaded>>,
+    pub commit_notifies: Arc<Vec<Arc<Notify>>>,
+    /// Undo in-memory state staged alongside the batch, run when the batch is rolled back,
+    /// fails to commit, or is dropped uncommitted.
+    pub abort_hooks: RefCell<Vec<Box<dyn FnOnce()>>>,
+}
+
+impl RocksdbTransactionContext {
+    pub fn on_abort(&self, hook: impl FnOnce() + 'static) {
+        self.abort_hooks.borrow_mut().push(Box::new(hook));
+    }
+
+    fn abort(&self) {
+        for hook in self.abort_hooks.take() {
+            hook();
+        }
+    }
 }
 
 impl TransactionContext for RocksdbTransactionContext {
     fn commit(&self) {
-        self.db.write(self.batch.take()).unwrap();
+        if let Err(error) = self.db.write(self.batch.take()) {
+            self.abort();
+            panic!("Failed to commit transaction: {error}");
+        }
+        self.abort_hooks.take();
+        for commit_notify in self.commit_notifies.iter() {
+            commit_notify.notify_one();
+        }
     }
 
     fn rollback(&self) {
-        // none
+        self.batch.take();
+        self.abort();
+    }
+}
+
+impl Drop for RocksdbTransactionContext {
+    fn drop(&mut self) {
+        self.abort();
     }
 }
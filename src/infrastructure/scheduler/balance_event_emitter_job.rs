@@ -1,121 +1,913 @@
-use std::{env, sync::Arc, time::Duration};
-
-use log::error;
-use rdkafka::{
-    ClientConfig,
-    producer::{FutureProducer, FutureRecord},
+use std::{
+    env,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
-use rust_rocksdb::{DB, DBWithThreadMode, Options, SingleThreaded};
+
+use chrono::Utc;
+use log::{error, info, warn};
+use tokio::sync::Notify;
 
 use crate::{
-    application::balance::api::balance_event_api::BalanceEventApi,
-    infrastructure::app_ioc::AppState,
+    application::balance::{
+        api::balance_event_api::{BalanceEventApi, BalanceEventData, BalanceEventResult},
+        spi::{
+            dead_letter_queue::{DeadLetter, DeadLetterQueue},
+            event_sink::{EventSink, EventSinkError},
+        },
+    },
+    core::domain::balance_event::EventId,
+    infrastructure::{
+        app_ioc::AppState,
+        event_sink::{
+            event_sink_config::{new_dead_letter_queue, new_event_sink},
+            event_sink_offset_db::EventSinkOffsetDB,
+            event_subscription::EventSubscription,
+        },
+        scheduler::{
+            balance_event_emitter_metrics::{BalanceEventEmitterMetrics, CircuitState},
+            scheduled_job::{JobFuture, JobSchedule, ScheduledJob},
+        },
+    },
 };
 
-const OFFSET_KEY: &[u8] = b"offset";
+pub const BALANCE_EVENT_EMITTER_JOB: &str = "balance_event_emitter";
 
 struct BalanceEventEmitterConfig {
-    pub brokers: String,
-    pub topic: String,
     pub pooling_size: u64,
-    pub batch_size: String,
-    pub linger_ms: String,
+    pub interval: Duration,
+    pub backoff_initial: Duration,
+    pub backoff_max: Duration,
+    pub circuit_failure_threshold: u32,
+    pub circuit_open: Duration,
+    pub dead_letter_after: u32,
 }
 
 impl BalanceEventEmitterConfig {
     pub fn new() -> Self {
         Self {
-            brokers: env::var("KAFKA_BROKERS").unwrap_or("localhost:9092".to_string()),
-            topic: env::var("BALANCE_EVENT_TOPIC").unwrap_or("balance.event".to_string()),
             pooling_size: env::var("BALANCE_EVENT_EMITTER_JOB_POOLING_SIZE")
                 .unwrap_or("1000".to_string())
                 .parse::<u64>()
                 .unwrap_or(1000),
-            batch_size: env::var("KAFKA_BALANCE_EVENT_BATCH_NUM_MESSAGES")
-                .unwrap_or("10000".to_string()),
-            linger_ms: env::var("KAFKA_BALANCE_EVENT_LINGER_MS").unwrap_or("50".to_string()),
+            interval: Duration::from_millis(
+                env::var("BALANCE_EVENT_PUBLISH_EVENT_INTERVAL_MS")
+                    .unwrap_or("100".to_string())
+                    .parse::<u64>()
+                    .unwrap_or(100),
+            ),
+            backoff_initial: Duration::from_millis(
+                env::var("BALANCE_EVENT_EMITTER_BACKOFF_INITIAL_MS")
+                    .unwrap_or("100".to_string())
+                    .parse::<u64>()
+                    .unwrap_or(100),
+            ),
+            backoff_max: Duration::from_millis(
+                env::var("BALANCE_EVENT_EMITTER_BACKOFF_MAX_MS")
+                    .unwrap_or("30000".to_string())
+                    .parse::<u64>()
+                    .unwrap_or(30000),
+            ),
+            circuit_failure_threshold: env::var("BALANCE_EVENT_EMITTER_CIRCUIT_FAILURE_THRESHOLD")
+                .unwrap_or("10".to_string())
+                .parse::<u32>()
+                .unwrap_or(10),
+            circuit_open: Duration::from_millis(
+                env::var("BALANCE_EVENT_EMITTER_CIRCUIT_OPEN_MS")
+                    .unwrap_or("60000".to_string())
+                    .parse::<u64>()
+                    .unwrap_or(60000),
+            ),
+            dead_letter_after: env::var("BALANCE_EVENT_DEAD_LETTER_AFTER")
+                .unwrap_or("3".to_string())
+                .parse::<u32>()
+                .unwrap_or(3),
         }
     }
-}
 
-struct BalanceEventOffsetDB {
-    db: DBWithThreadMode<SingleThreaded>,
-}
-
-impl BalanceEventOffsetDB {
-    fn new() -> Self {
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        let db = DB::open(&opts, "offheap/balance_event_offset.db").unwrap();
-        Self { db }
+    /// `backoff_initial` doubled after each further failure, up to `backoff_max`.
+    fn backoff(&self, consecutive_failures: u32) -> Duration {
+        let doublings = consecutive_failures.saturating_sub(1).min(16);
+        self.backoff_initial
+            .saturating_mul(1 << doublings)
+            .min(self.backoff_max)
     }
+}
 
-    fn get_offset(&self) -> u64 {
-        let offset = self
-            .db
-            .get(OFFSET_KEY)
-            .unwrap()
-            .unwrap_or_else(|| vec![0_u8; 8]);
-        u64::from_be_bytes(offset.try_into().unwrap())
-    }
+/// Retry state of one sink.
+#[derive(Default)]
+struct SinkHealth {
+    consecutive_failures: u32,
+    circuit: CircuitState,
+    /// No batch goes to the sink before this instant.
+    retry_at: Option<Instant>,
+    /// Event the sink rejected last, and how many times in a row.
+    rejected: Option<(EventId, u32)>,
+}
 
-    fn set_offset(&self, offset: u64) {
-        self.db.put(OFFSET_KEY, offset.to_be_bytes()).unwrap();
-    }
+struct SinkState {
+    subscription: Arc<EventSubscription>,
+    sink: Box<dyn EventSink>,
+    /// Last event id delivered to the sink; `None` until loaded, or after a failed batch
+    /// whose outcome has to be read back from the sink.
+    offset: Mutex<Option<EventId>>,
+    health: Mutex<SinkHealth>,
 }
 
 pub struct BalanceEventEmitterJob {
     balance_event_api: Arc<BalanceEventApi>,
-    offset_db: BalanceEventOffsetDB,
-    producer: FutureProducer,
+    offset_db: EventSinkOffsetDB,
+    sinks: Vec<SinkState>,
+    dead_letter_queue: Option<Box<dyn DeadLetterQueue>>,
+    metrics: Arc<BalanceEventEmitterMetrics>,
+    event_commit_notify: Arc<Notify>,
     config: BalanceEventEmitterConfig,
 }
 
+impl SinkState {
+    fn name(&self) -> &str {
+        &self.subscription.name
+    }
+}
+
 impl BalanceEventEmitterJob {
+    /// A subscription whose sink cannot be created is logged and left out.
     pub fn new(ioc: Arc<AppState>) -> Self {
-        let offset_db = BalanceEventOffsetDB::new();
-        let config = BalanceEventEmitterConfig::new();
-
-        let producer = ClientConfig::new()
-            .set("bootstrap.servers", &config.brokers)
-            // .set("queue.buffering.max.messages", "1000000")
-            // .set("batch.num.messages", &config.batch_size)
-            // .set("linger.ms", &config.linger_ms)
-            .set("compression.type", "lz4")
-            .set("acks", "1")
-            .create()
-            .expect("Producer creation error");
+        let sinks = ioc
+            .balance_event_subscriptions
+            .iter()
+            .filter_map(|subscription| match new_event_sink(subscription) {
+                Ok(sink) => {
+                    info!(
+                        "Balance event subscription {} enabled on {}",
+                        subscription.name,
+                        sink.name()
+                    );
+                    Some(SinkState {
+                        subscription: subscription.clone(),
+                        sink,
+                        offset: Mutex::new(None),
+                        health: Mutex::new(SinkHealth::default()),
+                    })
+                }
+                Err(message) => {
+                    error!(
+                        "Balance event subscription {} disabled: {message}",
+                        subscription.name
+                    );
+                    None
+                }
+            })
+            .collect();
+        let dead_letter_queue = new_dead_letter_queue();
+        if let Some(dead_letter_queue) = &dead_letter_queue {
+            info!(
+                "Balance event dead letter queue enabled: {}",
+                dead_letter_queue.name()
+            );
+        }
 
         Self {
             balance_event_api: ioc.balance_event_api.clone(),
-            offset_db,
-            config,
-            producer,
+            offset_db: EventSinkOffsetDB::new(),
+            sinks,
+            dead_letter_queue,
+            metrics: ioc.balance_event_emitter_metrics.clone(),
+            event_commit_notify: ioc.event_commit_notify.clone(),
+            config: BalanceEventEmitterConfig::new(),
         }
     }
 }
 
+/// Runs every `BALANCE_EVENT_PUBLISH_EVENT_INTERVAL_MS` by default, and right after each commit.
+impl ScheduledJob for BalanceEventEmitterJob {
+    fn name(&self) -> &'static str {
+        BALANCE_EVENT_EMITTER_JOB
+    }
+
+    fn default_schedule(&self) -> JobSchedule {
+        JobSchedule::FixedDelay(self.config.interval)
+    }
+
+    fn run(&self) -> JobFuture<'_> {
+        Box::pin(self.publish_event())
+    }
+
+    fn wake(&self) -> Option<Arc<Notify>> {
+        Some(self.event_commit_notify.clone())
+    }
+}
+
 impl BalanceEventEmitterJob {
-    pub async fn publish_event(&self) {
-        let latest_sent_event_id = self.offset_db.get_offset();
-        let next_event_id = latest_sent_event_id + 1;
-        let events = self
+    pub fn has_sinks(&self) -> bool {
+        !self.sinks.is_empty()
+    }
+
+    /// Each subscription moves on its own offset, a failing sink does not hold back the others.
+    /// Returns whether a sink was handed a full batch, in which case more events are likely
+    /// waiting and the caller should not sleep, or the failures of the sinks that failed.
+    pub async fn publish_event(&self) -> Result<bool, String> {
+        let mut has_more = false;
+        let mut failures = vec![];
+        for sink_state in &self.sinks {
+            match self.publish_to(sink_state).await {
+                Ok(sink_has_more) => has_more |= sink_has_more,
+                Err(sink_error) => failures.push(format!("{}: {sink_error}", sink_state.name())),
+            }
+        }
+        if failures.is_empty() {
+            Ok(has_more)
+        } else {
+            Err(failures.join("; "))
+        }
+    }
+
+    /// Whether the sink was handed a full batch; an error has already been counted against
+    /// the sink.
+    async fn publish_to(&self, sink_state: &SinkState) -> Result<bool, EventSinkError> {
+        let sink = sink_state.sink.as_ref();
+        let name = sink_state.name();
+        if let Some(offset) = sink_state.subscription.take_offset_reset() {
+            info!("{name} reset to replay events after {offset}");
+            if let Err(sink_error) = self.commit_offset(sink_state, offset).await {
+                error!("Failed to reset offset of {name}: {sink_error}");
+                sink_state.subscription.request_offset_reset(offset);
+                self.on_failure(sink_state, &sink_error);
+                return Err(sink_error);
+            }
+        }
+        if sink_state.subscription.is_paused() || !self.is_ready(sink_state) {
+            return Ok(false);
+        }
+        let latest_sent_event_id = match self.load_offset(sink_state) {
+            Ok(offset) => offset,
+            Err(sink_error) => {
+                error!("Failed to read offset of {name}: {sink_error}");
+                self.on_failure(sink_state, &sink_error);
+                return Err(sink_error);
+            }
+        };
+        // after repeated failures, send events one at a time so the sink can tell which one
+        // it refuses
+        let limit = if sink_state.health.lock().unwrap().consecutive_failures
+            >= self.config.dead_letter_after
+        {
+            1
+        } else {
+            self.config.pooling_size
+        };
+        let batch = self
             .balance_event_api
-            .get_balance_events(next_event_id, self.config.pooling_size);
-
-        for event in events {
-            let payload = serde_json::to_string(&event).unwrap();
-            let key = event.id.to_string();
-            let record = FutureRecord::to(&self.config.topic)
-                .key(&key)
-                .payload(&payload);
-
-            let result = self.producer.send(record, Duration::from_secs(1)).await;
-            if result.is_err() {
-                error!("Failed to send event: {:?}", result.err());
-                break;
+            .get_balance_events(latest_sent_event_id + 1, limit);
+        let Some(last_event_id) = batch.last().map(event_id) else {
+            return Ok(false);
+        };
+        let is_full_batch = batch.len() as u64 == limit;
+        // unreadable and filtered out events are skipped, the offset still moves past them
+        let mut events = self.readable_events(sink_state, batch);
+        events.retain(|event| sink_state.subscription.filter.matches(event));
+        if events.is_empty() {
+            if let Err(sink_error) = self.commit_offset(sink_state, last_event_id).await {
+                error!("Failed to commit offset of {name}: {sink_error}");
+                *sink_state.offset.lock().unwrap() = None;
+                self.on_failure(sink_state, &sink_error);
+                return Err(sink_error);
+            }
+            return Ok(is_full_batch);
+        }
+
+        let published = if sink.owns_offset() {
+            sink.publish_with_offset(&events, last_event_id).await
+        } else {
+            sink.publish(&events).await
+        };
+        let sink_error = match published {
+            Ok(()) => {
+                self.advance_offset(sink_state, last_event_id);
+                self.on_success(sink_state);
+                return Ok(is_full_batch);
+            }
+            Err(sink_error) => sink_error,
+        };
+
+        error!("Failed to publish events to {name}: {sink_error}");
+        match &sink_error {
+            EventSinkError::PartiallyDelivered { last_delivered, .. } => {
+                self.advance_offset(sink_state, *last_delivered);
+            }
+            EventSinkError::Rejected { event_id, message } => {
+                // a sink owning its offset only keeps this in memory: it rejects the first
+                // event it was handed, so the events before it were unreadable or filtered out
+                // and are skipped again when a restart reads the offset back
+                if *event_id > latest_sent_event_id + 1 {
+                    self.advance_offset(sink_state, event_id - 1);
+                }
+                let rejected_event = events.iter().find(|event| event.id == *event_id);
+                if let Some(rejected_event) = rejected_event
+                    && self.should_dead_letter(sink_state, *event_id)
+                    && self.dead_letter(name, rejected_event, message).await
+                {
+                    self.metrics
+                        .update(name, |metrics| metrics.dead_lettered += 1);
+                    match self.commit_offset(sink_state, *event_id).await {
+                        Ok(()) => {
+                            self.on_success(sink_state);
+                            return Ok(true);
+                        }
+                        Err(offset_error) => {
+                            error!("Failed to commit offset of {name}: {offset_error}");
+                            *sink_state.offset.lock().unwrap() = None;
+                        }
+                    }
+                }
+            }
+            _ => *sink_state.offset.lock().unwrap() = None,
+        }
+        self.on_failure(sink_state, &sink_error);
+        Err(sink_error)
+    }
+
+    /// False while the sink waits out its backoff or its open circuit. Once the open period
+    /// ends the circuit turns half-open and one batch is let through.
+    fn is_ready(&self, sink_state: &SinkState) -> bool {
+        let mut health = sink_state.health.lock().unwrap();
+        if health
+            .retry_at
+            .is_some_and(|retry_at| Instant::now() < retry_at)
+        {
+            return false;
+        }
+        if health.circuit == CircuitState::Open {
+            info!("Circuit of {} half-open", sink_state.name());
+            health.circuit = CircuitState::HalfOpen;
+            self.metrics.update(sink_state.name(), |metrics| {
+                metrics.circuit = CircuitState::HalfOpen
+            });
+        }
+        true
+    }
+
+    fn on_success(&self, sink_state: &SinkState) {
+        let mut health = sink_state.health.lock().unwrap();
+        if health.circuit != CircuitState::Closed {
+            info!("Circuit of {} closed", sink_state.name());
+        }
+        *health = SinkHealth::default();
+        self.metrics.update(sink_state.name(), |metrics| {
+            metrics.circuit = CircuitState::Closed;
+            metrics.consecutive_failures = 0;
+        });
+    }
+
+    fn on_failure(&self, sink_state: &SinkState, sink_error: &EventSinkError) {
+        let sink_name = sink_state.name();
+        self.metrics.record_error(sink_name, sink_error.kind());
+
+        let mut health = sink_state.health.lock().unwrap();
+        health.consecutive_failures += 1;
+        let delay = if health.circuit == CircuitState::HalfOpen
+            || health.consecutive_failures >= self.config.circuit_failure_threshold
+        {
+            if health.circuit != CircuitState::Open {
+                warn!(
+                    "Circuit of {sink_name} opened for {:?} after {} failures",
+                    self.config.circuit_open, health.consecutive_failures
+                );
+            }
+            health.circuit = CircuitState::Open;
+            self.config.circuit_open
+        } else {
+            self.config.backoff(health.consecutive_failures)
+        };
+        health.retry_at = Some(Instant::now() + delay);
+
+        let (circuit, consecutive_failures) = (health.circuit, health.consecutive_failures);
+        self.metrics.update(sink_name, |metrics| {
+            metrics.circuit = circuit;
+            metrics.consecutive_failures = consecutive_failures;
+        });
+    }
+
+    /// Counts the rejection and tells whether `event_id` has now been rejected often enough
+    /// to be dead-lettered.
+    fn should_dead_letter(&self, sink_state: &SinkState, event_id: EventId) -> bool {
+        let mut health = sink_state.health.lock().unwrap();
+        let rejections = match health.rejected {
+            Some((rejected_event_id, rejections)) if rejected_event_id == event_id => {
+                rejections + 1
+            }
+            _ => 1,
+        };
+        health.rejected = Some((event_id, rejections));
+        self.dead_letter_queue.is_some() && rejections >= self.config.dead_letter_after
+    }
+
+    async fn dead_letter(&self, sink_name: &str, event: &BalanceEventData, reason: &str) -> bool {
+        let Some(dead_letter_queue) = &self.dead_letter_queue else {
+            return false;
+        };
+        let dead_letter = DeadLetter {
+            sink: sink_name,
+            reason,
+            dead_lettered_at: Utc::now().timestamp_nanos_opt().unwrap() as u64,
+            event,
+        };
+        match dead_letter_queue.store(&dead_letter).await {
+            Ok(()) => {
+                warn!(
+                    "Event {} dead-lettered to {} after {sink_name} rejected it: {reason}",
+                    event.id,
+                    dead_letter_queue.name()
+                );
+                true
+            }
+            Err(dead_letter_error) => {
+                error!(
+                    "Failed to dead-letter event {}: {dead_letter_error}",
+                    event.id
+                );
+                false
             }
-            self.offset_db.set_offset(event.id);
         }
     }
+
+    /// Moves the offset past events that are not published, such as filtered out ones. A sink
+    /// that owns its offset commits it itself, so it survives a restart.
+    async fn commit_offset(
+        &self,
+        sink_state: &SinkState,
+        event_id: EventId,
+    ) -> Result<(), EventSinkError> {
+        if sink_state.sink.owns_offset() {
+            sink_state.sink.publish_with_offset(&[], event_id).await?;
+        }
+        self.advance_offset(sink_state, event_id);
+        Ok(())
+    }
+
+    fn advance_offset(&self, sink_state: &SinkState, event_id: EventId) {
+        if !sink_state.sink.owns_offset() {
+            self.offset_db.set_offset(sink_state.name(), event_id);
+        }
+        *sink_state.offset.lock().unwrap() = Some(event_id);
+        self.metrics
+            .update(sink_state.name(), |metrics| metrics.offset = Some(event_id));
+    }
+
+    fn load_offset(&self, sink_state: &SinkState) -> Result<EventId, EventSinkError> {
+        if let Some(known_offset) = *sink_state.offset.lock().unwrap() {
+            return Ok(known_offset);
+        }
+
+        let sink = sink_state.sink.as_ref();
+        let owned_offset = match sink.owns_offset() {
+            true => sink.read_owned_offset()?,
+            false => None,
+        };
+        let offset = owned_offset.unwrap_or_else(|| self.offset_db.get_offset(sink_state.name()));
+        info!("{} resumes after event {offset}", sink_state.name());
+        *sink_state.offset.lock().unwrap() = Some(offset);
+        self.metrics
+            .update(sink_state.name(), |metrics| metrics.offset = Some(offset));
+        Ok(offset)
+    }
+
+    /// An unreadable event never gets better with retries, so it is skipped rather than
+    /// blocking the sink, and counted in `skipped_unreadable`.
+    fn readable_events(
+        &self,
+        sink_state: &SinkState,
+        batch: Vec<BalanceEventResult>,
+    ) -> Vec<BalanceEventData> {
+        batch
+            .into_iter()
+            .filter_map(|result| match result {
+                Ok(event) => Some(event),
+                Err(unreadable) => {
+                    error!("{} skips {unreadable}", sink_state.name());
+                    self.metrics
+                        .update(sink_state.name(), |metrics| metrics.skipped_unreadable += 1);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+fn event_id(result: &BalanceEventResult) -> EventId {
+    match result {
+        Ok(event) => event.id,
+        Err(unreadable) => unreadable.id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        application::{
+            balance::spi::{
+                balance_event_repository::{BalanceEventRepository, StoredBalanceEvent},
+                event_sink::EventSinkFuture,
+            },
+            transaction_spi::TransactionContext,
+        },
+        core::domain::{
+            balance_event::{BalanceCreatedEvent, BalanceEvent, BalanceEventType},
+            balance_event_upcaster::BalanceEventUpcasterChain,
+        },
+        infrastructure::event_sink::event_subscription::{EventFilter, SinkEnv},
+    };
+
+    /// Committed `BalanceCreated` events, event `n` creating balance `n`.
+    struct CreatedEvents(u64);
+
+    impl BalanceEventRepository for CreatedEvents {
+        fn persist_in_transaction(
+            &self,
+            _event_type: BalanceEventType,
+            _event: Vec<u8>,
+            _transaction_context: Rc<dyn TransactionContext>,
+        ) -> EventId {
+            unimplemented!()
+        }
+
+        fn read(&self, offset: u64, limit: u64) -> Vec<StoredBalanceEvent> {
+            (offset..=self.0)
+                .take(limit as usize)
+                .map(|id| {
+                    Ok(BalanceEvent {
+                        id,
+                        event_type: BalanceEventType::BalanceCreated,
+                        schema_version: BalanceCreatedEvent::SCHEMA_VERSION,
+                        event_time: 0,
+                        data: bincode::encode_to_vec(
+                            BalanceCreatedEvent { id },
+                            bincode::config::standard(),
+                        )
+                        .unwrap(),
+                    })
+                })
+                .collect()
+        }
+
+        fn last_event_id(&self) -> EventId {
+            self.0
+        }
+    }
+
+    /// Event ids handed to `publish_with_offset`, with the offset.
+    type PublishedBatch = (Vec<EventId>, EventId);
+
+    /// Owns its offset like the exactly-once Kafka sink, recording each `publish_with_offset`.
+    #[derive(Default)]
+    struct OffsetOwningSink {
+        offset: Arc<Mutex<Option<EventId>>>,
+        batches: Arc<Mutex<Vec<PublishedBatch>>>,
+        failing: bool,
+    }
+
+    impl EventSink for OffsetOwningSink {
+        fn name(&self) -> &str {
+            "offset_owning"
+        }
+
+        fn publish<'a>(&'a self, _events: &'a [BalanceEventData]) -> EventSinkFuture<'a> {
+            panic!("a sink owning its offset is only handed batches with their offset")
+        }
+
+        fn owns_offset(&self) -> bool {
+            true
+        }
+
+        fn publish_with_offset<'a>(
+            &'a self,
+            events: &'a [BalanceEventData],
+            offset: EventId,
+        ) -> EventSinkFuture<'a> {
+            Box::pin(async move {
+                if self.failing {
+                    return Err(EventSinkError::DeliveryFailed("broker down".to_string()));
+                }
+                let event_ids = events.iter().map(|event| event.id).collect();
+                self.batches.lock().unwrap().push((event_ids, offset));
+                *self.offset.lock().unwrap() = Some(offset);
+                Ok(())
+            })
+        }
+
+        fn read_owned_offset(&self) -> Result<Option<EventId>, EventSinkError> {
+            Ok(*self.offset.lock().unwrap())
+        }
+    }
+
+    /// Leaves the offset to the emitter and acknowledges events in order up to `fail_on`,
+    /// which fails with every event after it, or up to `reject`, which it refuses.
+    #[derive(Default)]
+    struct AtLeastOnceSink {
+        published: Arc<Mutex<Vec<EventId>>>,
+        fail_on: Arc<Mutex<Option<EventId>>>,
+        reject: Option<EventId>,
+    }
+
+    impl EventSink for AtLeastOnceSink {
+        fn name(&self) -> &str {
+            "at_least_once"
+        }
+
+        fn publish<'a>(&'a self, events: &'a [BalanceEventData]) -> EventSinkFuture<'a> {
+            Box::pin(async move {
+                let fail_on = *self.fail_on.lock().unwrap();
+                let mut last_delivered = None;
+                for event in events {
+                    if Some(event.id) == self.reject {
+                        return Err(EventSinkError::Rejected {
+                            event_id: event.id,
+                            message: "record too large".to_string(),
+                        });
+                    }
+                    if Some(event.id) == fail_on {
+                        let message = "broker down".to_string();
+                        return Err(match last_delivered {
+                            Some(last_delivered) => EventSinkError::PartiallyDelivered {
+                                last_delivered,
+                                message,
+                            },
+                            None => EventSinkError::DeliveryFailed(message),
+                        });
+                    }
+                    self.published.lock().unwrap().push(event.id);
+                    last_delivered = Some(event.id);
+                }
+                Ok(())
+            })
+        }
+    }
+
+    fn emitter(
+        dir: &tempfile::TempDir,
+        event_count: u64,
+        balance_ids: Option<&str>,
+        sink: impl EventSink + 'static,
+    ) -> BalanceEventEmitterJob {
+        let subscription = Arc::new(EventSubscription::new(
+            "audit".to_string(),
+            "kafka".to_string(),
+            EventFilter::parse(None, balance_ids).unwrap(),
+            SinkEnv::for_subscription("audit"),
+        ));
+        BalanceEventEmitterJob {
+            balance_event_api: Arc::new(BalanceEventApi {
+                balance_event_repository: Arc::new(CreatedEvents(event_count)),
+                balance_event_upcaster_chain: BalanceEventUpcasterChain::new(),
+            }),
+            offset_db: EventSinkOffsetDB::open(dir.path()),
+            sinks: vec![SinkState {
+                subscription,
+                sink: Box::new(sink),
+                offset: Mutex::new(None),
+                health: Mutex::new(SinkHealth::default()),
+            }],
+            dead_letter_queue: None,
+            metrics: Arc::new(BalanceEventEmitterMetrics::default()),
+            event_commit_notify: Arc::new(Notify::new()),
+            config: BalanceEventEmitterConfig::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn owned_offset_moves_to_the_batch_end_past_filtered_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = OffsetOwningSink::default();
+        let batches = sink.batches.clone();
+        let job = emitter(&dir, 3, Some("1"), sink);
+
+        job.publish_event().await.unwrap();
+
+        assert_eq!(*batches.lock().unwrap(), vec![(vec![1], 3)]);
+    }
+
+    #[tokio::test]
+    async fn filtered_out_batch_still_commits_the_owned_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = OffsetOwningSink::default();
+        let (offset, batches) = (sink.offset.clone(), sink.batches.clone());
+        let job = emitter(&dir, 3, Some("9"), sink);
+
+        job.publish_event().await.unwrap();
+
+        assert_eq!(*batches.lock().unwrap(), vec![(vec![], 3)]);
+        assert_eq!(*offset.lock().unwrap(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn sink_without_an_owned_offset_starts_from_the_stored_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = OffsetOwningSink::default();
+        let batches = sink.batches.clone();
+        let job = emitter(&dir, 3, None, sink);
+        job.offset_db.set_offset("audit", 2);
+
+        job.publish_event().await.unwrap();
+
+        assert_eq!(*batches.lock().unwrap(), vec![(vec![3], 3)]);
+    }
+
+    #[tokio::test]
+    async fn offset_reset_is_committed_to_a_sink_owning_its_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = OffsetOwningSink::default();
+        *sink.offset.lock().unwrap() = Some(3);
+        let batches = sink.batches.clone();
+        let job = emitter(&dir, 3, None, sink);
+        job.sinks[0].subscription.request_offset_reset(1);
+
+        job.publish_event().await.unwrap();
+
+        assert_eq!(*batches.lock().unwrap(), vec![(vec![], 1), (vec![2, 3], 3)]);
+    }
+
+    #[tokio::test]
+    async fn failed_offset_reset_is_requested_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = OffsetOwningSink {
+            failing: true,
+            ..OffsetOwningSink::default()
+        };
+        let job = emitter(&dir, 3, None, sink);
+        let subscription = job.sinks[0].subscription.clone();
+        subscription.request_offset_reset(1);
+
+        let failure = job.publish_event().await.unwrap_err();
+
+        assert_eq!(failure, "audit: Delivery failed: broker down");
+        assert_eq!(subscription.take_offset_reset(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn partial_delivery_moves_the_offset_to_the_last_acknowledged_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = AtLeastOnceSink::default();
+        let (published, fail_on) = (sink.published.clone(), sink.fail_on.clone());
+        *fail_on.lock().unwrap() = Some(4);
+        let mut job = emitter(&dir, 5, None, sink);
+        job.config.backoff_initial = Duration::ZERO;
+
+        let failure = job.publish_event().await.unwrap_err();
+
+        assert_eq!(failure, "audit: Delivery failed after event 3: broker down");
+        assert_eq!(job.offset_db.get_offset("audit"), 3);
+
+        *fail_on.lock().unwrap() = None;
+        job.publish_event().await.unwrap();
+
+        assert_eq!(*published.lock().unwrap(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(job.offset_db.get_offset("audit"), 5);
+    }
+
+    #[tokio::test]
+    async fn failure_on_the_first_event_keeps_the_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = AtLeastOnceSink::default();
+        *sink.fail_on.lock().unwrap() = Some(1);
+        let job = emitter(&dir, 3, None, sink);
+
+        job.publish_event().await.unwrap_err();
+
+        assert_eq!(job.offset_db.get_offset("audit"), 0);
+    }
+
+    /// Keeps the ids of the dead-lettered events.
+    #[derive(Default)]
+    struct RecordingDeadLetterQueue {
+        event_ids: Arc<Mutex<Vec<EventId>>>,
+    }
+
+    impl DeadLetterQueue for RecordingDeadLetterQueue {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        fn store<'a>(&'a self, dead_letter: &'a DeadLetter<'a>) -> EventSinkFuture<'a> {
+            Box::pin(async move {
+                self.event_ids.lock().unwrap().push(dead_letter.event.id);
+                Ok(())
+            })
+        }
+    }
+
+    fn circuit(job: &BalanceEventEmitterJob) -> CircuitState {
+        job.metrics.status(0).sinks["audit"].circuit
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let mut config = BalanceEventEmitterConfig::new();
+        config.backoff_initial = Duration::from_millis(100);
+        config.backoff_max = Duration::from_millis(1000);
+
+        let backoffs: Vec<_> = (1..=6)
+            .map(|consecutive_failures| config.backoff(consecutive_failures).as_millis())
+            .collect();
+
+        assert_eq!(backoffs, vec![100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(config.backoff(u32::MAX), Duration::from_millis(1000));
+    }
+
+    #[tokio::test]
+    async fn circuit_opens_after_the_failure_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = AtLeastOnceSink::default();
+        *sink.fail_on.lock().unwrap() = Some(1);
+        let mut job = emitter(&dir, 3, None, sink);
+        job.config.backoff_initial = Duration::ZERO;
+        job.config.circuit_failure_threshold = 3;
+        job.config.circuit_open = Duration::from_secs(3600);
+
+        for _ in 0..2 {
+            job.publish_event().await.unwrap_err();
+            assert_eq!(circuit(&job), CircuitState::Closed);
+        }
+        job.publish_event().await.unwrap_err();
+        assert_eq!(circuit(&job), CircuitState::Open);
+
+        // the open circuit holds the sink back, nothing is attempted
+        assert_eq!(job.publish_event().await, Ok(false));
+        assert_eq!(job.metrics.status(0).sinks["audit"].consecutive_failures, 3);
+    }
+
+    #[tokio::test]
+    async fn circuit_half_opens_after_the_open_period() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = AtLeastOnceSink::default();
+        let fail_on = sink.fail_on.clone();
+        *fail_on.lock().unwrap() = Some(1);
+        let mut job = emitter(&dir, 3, None, sink);
+        job.config.circuit_failure_threshold = 1;
+        job.config.circuit_open = Duration::ZERO;
+        job.publish_event().await.unwrap_err();
+        assert_eq!(circuit(&job), CircuitState::Open);
+
+        assert!(job.is_ready(&job.sinks[0]));
+        assert_eq!(circuit(&job), CircuitState::HalfOpen);
+        // a failed trial batch opens the circuit again
+        job.publish_event().await.unwrap_err();
+        assert_eq!(circuit(&job), CircuitState::Open);
+
+        *fail_on.lock().unwrap() = None;
+        job.publish_event().await.unwrap();
+        assert_eq!(circuit(&job), CircuitState::Closed);
+        assert_eq!(job.metrics.status(0).sinks["audit"].consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn rejected_event_is_dead_lettered_and_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = AtLeastOnceSink {
+            reject: Some(2),
+            ..AtLeastOnceSink::default()
+        };
+        let published = sink.published.clone();
+        let dead_letter_queue = RecordingDeadLetterQueue::default();
+        let dead_lettered = dead_letter_queue.event_ids.clone();
+        let mut job = emitter(&dir, 3, None, sink);
+        job.dead_letter_queue = Some(Box::new(dead_letter_queue));
+        job.config.dead_letter_after = 1;
+
+        assert_eq!(job.publish_event().await, Ok(true));
+
+        assert_eq!(*dead_lettered.lock().unwrap(), vec![2]);
+        assert_eq!(job.offset_db.get_offset("audit"), 2);
+        assert_eq!(job.metrics.status(0).sinks["audit"].dead_lettered, 1);
+
+        job.publish_event().await.unwrap();
+
+        assert_eq!(*published.lock().unwrap(), vec![1, 3]);
+        assert_eq!(job.offset_db.get_offset("audit"), 3);
+    }
+
+    #[tokio::test]
+    async fn rejected_event_is_retried_until_dead_letter_after() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = AtLeastOnceSink {
+            reject: Some(2),
+            ..AtLeastOnceSink::default()
+        };
+        let dead_letter_queue = RecordingDeadLetterQueue::default();
+        let dead_lettered = dead_letter_queue.event_ids.clone();
+        let mut job = emitter(&dir, 3, None, sink);
+        job.dead_letter_queue = Some(Box::new(dead_letter_queue));
+        job.config.dead_letter_after = 2;
+        job.config.backoff_initial = Duration::ZERO;
+
+        job.publish_event().await.unwrap_err();
+        assert!(dead_lettered.lock().unwrap().is_empty());
+        assert_eq!(job.offset_db.get_offset("audit"), 1);
+
+        job.publish_event().await.unwrap();
+        assert_eq!(*dead_lettered.lock().unwrap(), vec![2]);
+        assert_eq!(job.offset_db.get_offset("audit"), 2);
+    }
 }
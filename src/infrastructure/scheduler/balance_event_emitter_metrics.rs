@@ -0,0 +1,70 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::core::domain::balance_event::EventId;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Batches go out, with an exponential backoff after each failure.
+    #[default]
+    Closed,
+    /// Too many failures in a row; the sink is left alone until the open period ends.
+    Open,
+    /// The open period ended; the next batch decides whether the circuit closes again.
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct SinkMetrics {
+    /// Last event id delivered to the sink, `None` until it is loaded.
+    pub offset: Option<EventId>,
+    /// Committed events the sink has not received yet.
+    pub lag: u64,
+    pub circuit: CircuitState,
+    pub consecutive_failures: u32,
+    /// Failures by `EventSinkError::kind`.
+    pub errors: BTreeMap<&'static str, u64>,
+    pub dead_lettered: u64,
+    /// Stored events that could not be read and were skipped.
+    pub skipped_unreadable: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BalanceEventEmitterStatus {
+    pub last_event_id: EventId,
+    pub sinks: BTreeMap<String, SinkMetrics>,
+}
+
+/// Emitter state shared with the REST layer, written by the emitter job only.
+#[derive(Debug, Default)]
+pub struct BalanceEventEmitterMetrics {
+    sinks: Mutex<BTreeMap<String, SinkMetrics>>,
+}
+
+impl BalanceEventEmitterMetrics {
+    pub fn update(&self, sink_name: &str, update: impl FnOnce(&mut SinkMetrics)) {
+        let mut sinks = self.sinks.lock().unwrap();
+        update(sinks.entry(sink_name.to_string()).or_default());
+    }
+
+    pub fn record_error(&self, sink_name: &str, kind: &'static str) {
+        self.update(sink_name, |metrics| {
+            *metrics.errors.entry(kind).or_default() += 1;
+        });
+    }
+
+    /// Lag is measured against `last_event_id`, the last committed event.
+    pub fn status(&self, last_event_id: EventId) -> BalanceEventEmitterStatus {
+        let mut sinks = self.sinks.lock().unwrap().clone();
+        for metrics in sinks.values_mut() {
+            metrics.lag = last_event_id.saturating_sub(metrics.offset.unwrap_or(0));
+        }
+        BalanceEventEmitterStatus {
+            last_event_id,
+            sinks,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::infrastructure::{
+    event_log::segment_log::SegmentLog,
+    scheduler::scheduled_job::{JobFuture, JobSchedule, ScheduledJob},
+};
+
+pub const EVENT_LOG_RETENTION_JOB: &str = "event_log_retention";
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Deletes the event log segments beyond `EVENT_LOG_RETENTION_SEGMENTS`, every minute by
+/// default. Appends wait on the log's lock only while the files are removed.
+pub struct EventLogRetentionJob {
+    segment_log: Arc<Mutex<SegmentLog>>,
+}
+
+impl EventLogRetentionJob {
+    pub fn new(segment_log: Arc<Mutex<SegmentLog>>) -> Self {
+        Self { segment_log }
+    }
+}
+
+impl ScheduledJob for EventLogRetentionJob {
+    fn name(&self) -> &'static str {
+        EVENT_LOG_RETENTION_JOB
+    }
+
+    fn default_schedule(&self) -> JobSchedule {
+        JobSchedule::FixedDelay(DEFAULT_INTERVAL)
+    }
+
+    fn run(&self) -> JobFuture<'_> {
+        Box::pin(async move {
+            self.segment_log
+                .lock()
+                .unwrap()
+                .apply_retention()
+                .map(|_| false)
+                .map_err(|io_error| format!("Failed to delete event log segments: {io_error}"))
+        })
+    }
+}
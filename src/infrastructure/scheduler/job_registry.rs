@@ -0,0 +1,438 @@
+use std::{
+    error::Error,
+    fmt,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use chrono::Utc;
+use log::{error, info, warn};
+use serde::Serialize;
+use tokio::{sync::Notify, time};
+use tokio_cron_scheduler::{Job, JobScheduler};
+use utoipa::ToSchema;
+
+use crate::infrastructure::scheduler::scheduled_job::{JobSchedule, ScheduledJob};
+
+#[derive(Debug)]
+pub enum JobError {
+    AlreadyRunning(&'static str),
+    SchedulingFailed(String),
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobError::AlreadyRunning(name) => write!(f, "Job {name} is already running"),
+            JobError::SchedulingFailed(message) => write!(f, "Failed to schedule job: {message}"),
+        }
+    }
+}
+
+impl Error for JobError {}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct JobStatus {
+    pub name: &'static str,
+    pub schedule: String,
+    pub paused: bool,
+    pub running: bool,
+    pub runs: u64,
+    /// Cron ticks dropped because the previous run had not ended.
+    pub skipped_runs: u64,
+    pub consecutive_failures: u32,
+    /// Nanoseconds since the epoch, like event times.
+    pub last_started_at: Option<u64>,
+    pub last_finished_at: Option<u64>,
+    pub last_duration_ms: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<u64>,
+}
+
+/// A job with its schedule and status. Runs happen one at a time on the job's own task,
+/// started by the schedule, by a wake-up of the job or by a manual trigger.
+pub struct RegisteredJob {
+    job: Arc<dyn ScheduledJob>,
+    schedule: JobSchedule,
+    trigger: Arc<Notify>,
+    paused: AtomicBool,
+    running: AtomicBool,
+    manual_run: AtomicBool,
+    status: Mutex<JobStatus>,
+}
+
+impl RegisteredJob {
+    fn new(job: Arc<dyn ScheduledJob>, schedule: JobSchedule) -> Self {
+        let trigger = job.wake().unwrap_or_default();
+        let status = JobStatus {
+            name: job.name(),
+            schedule: schedule.to_string(),
+            ..JobStatus::default()
+        };
+        Self {
+            job,
+            schedule,
+            trigger,
+            paused: AtomicBool::new(false),
+            running: AtomicBool::new(false),
+            manual_run: AtomicBool::new(false),
+            status: Mutex::new(status),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.job.name()
+    }
+
+    pub fn status(&self) -> JobStatus {
+        let mut status = self.status.lock().unwrap().clone();
+        status.paused = self.paused.load(Ordering::Relaxed);
+        status.running = self.running.load(Ordering::Relaxed);
+        status
+    }
+
+    /// Scheduled runs are skipped until resumed; a run in progress completes.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+        self.trigger.notify_one();
+    }
+
+    /// Runs the job once as soon as possible, paused or not.
+    pub fn trigger(&self) -> Result<(), JobError> {
+        if self.running.load(Ordering::Relaxed) {
+            return Err(JobError::AlreadyRunning(self.name()));
+        }
+        self.manual_run.store(true, Ordering::Relaxed);
+        self.trigger.notify_one();
+        Ok(())
+    }
+
+    /// Called on each cron tick; a tick that finds the job running is dropped rather than
+    /// queued behind it.
+    fn on_tick(&self) {
+        if self.running.load(Ordering::Relaxed) {
+            warn!("Job {} still running, tick skipped", self.name());
+            self.status.lock().unwrap().skipped_runs += 1;
+            return;
+        }
+        self.trigger.notify_one();
+    }
+
+    /// Fixed-delay jobs run once at startup, cron jobs wait for their first tick.
+    async fn run_forever(self: Arc<Self>) {
+        if let JobSchedule::Cron(_) = self.schedule {
+            self.trigger.notified().await;
+        }
+        loop {
+            let manual_run = self.manual_run.swap(false, Ordering::Relaxed);
+            if (manual_run || !self.paused.load(Ordering::Relaxed)) && self.run_once().await {
+                continue;
+            }
+            // a wake-up during the run leaves a permit behind, so it is never missed
+            match self.schedule {
+                JobSchedule::FixedDelay(delay) => {
+                    tokio::select! {
+                        _ = self.trigger.notified() => {}
+                        _ = time::sleep(delay) => {}
+                    }
+                }
+                JobSchedule::Cron(_) => self.trigger.notified().await,
+            }
+        }
+    }
+
+    /// Whether the job asked to run again right away.
+    async fn run_once(&self) -> bool {
+        self.running.store(true, Ordering::Relaxed);
+        let started = Instant::now();
+        self.status.lock().unwrap().last_started_at = Some(now_nanos());
+
+        let result = self.job.run().await;
+
+        let mut status = self.status.lock().unwrap();
+        status.runs += 1;
+        status.last_finished_at = Some(now_nanos());
+        status.last_duration_ms = Some(started.elapsed().as_millis() as u64);
+        let run_again = match result {
+            Ok(run_again) => {
+                status.consecutive_failures = 0;
+                run_again
+            }
+            Err(message) => {
+                error!("Job {} failed: {message}", self.name());
+                status.consecutive_failures += 1;
+                status.last_error = Some(message);
+                status.last_error_at = status.last_finished_at;
+                false
+            }
+        };
+        self.running.store(false, Ordering::Relaxed);
+        run_again
+    }
+}
+
+/// Jobs of the process, shared with the admin endpoints.
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<Vec<Arc<RegisteredJob>>>,
+    cron_scheduler: tokio::sync::Mutex<Option<JobScheduler>>,
+}
+
+impl JobRegistry {
+    /// Registers `job` and starts running it on the current runtime.
+    pub async fn start(&self, job: Arc<dyn ScheduledJob>) -> Result<(), JobError> {
+        let schedule = JobSchedule::from_env(job.name(), job.default_schedule());
+        let registered_job = Arc::new(RegisteredJob::new(job, schedule));
+        if let JobSchedule::Cron(cron) = &registered_job.schedule {
+            self.add_cron(cron, registered_job.clone()).await?;
+        }
+        info!(
+            "Job {} scheduled: {}",
+            registered_job.name(),
+            registered_job.schedule
+        );
+        self.jobs.lock().unwrap().push(registered_job.clone());
+        tokio::spawn(registered_job.run_forever());
+        Ok(())
+    }
+
+    async fn add_cron(
+        &self,
+        cron: &str,
+        registered_job: Arc<RegisteredJob>,
+    ) -> Result<(), JobError> {
+        let scheduling_failed =
+            |scheduler_error| JobError::SchedulingFailed(format!("{cron}: {scheduler_error:?}"));
+        let mut cron_scheduler = self.cron_scheduler.lock().await;
+        if cron_scheduler.is_none() {
+            let scheduler = JobScheduler::new().await.map_err(scheduling_failed)?;
+            scheduler.start().await.map_err(scheduling_failed)?;
+            *cron_scheduler = Some(scheduler);
+        }
+        let tick = Job::new_async(cron, move |_, _| {
+            let registered_job = registered_job.clone();
+            Box::pin(async move { registered_job.on_tick() })
+        })
+        .map_err(scheduling_failed)?;
+        cron_scheduler
+            .as_ref()
+            .unwrap()
+            .add(tick)
+            .await
+            .map_err(scheduling_failed)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<RegisteredJob>> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|registered_job| registered_job.name() == name)
+            .cloned()
+    }
+
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|registered_job| registered_job.status())
+            .collect()
+    }
+}
+
+fn now_nanos() -> u64 {
+    Utc::now().timestamp_nanos_opt().unwrap() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::atomic::AtomicU64, time::Duration};
+
+    use super::*;
+    use crate::infrastructure::scheduler::scheduled_job::JobFuture;
+
+    struct FailingJob;
+
+    impl ScheduledJob for FailingJob {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn default_schedule(&self) -> JobSchedule {
+            JobSchedule::FixedDelay(Duration::from_secs(1))
+        }
+
+        fn run(&self) -> JobFuture<'_> {
+            Box::pin(async { Err("kafka: Delivery failed: broker down".to_string()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_run_is_recorded_as_last_error() {
+        let registered_job =
+            RegisteredJob::new(Arc::new(FailingJob), FailingJob.default_schedule());
+
+        assert!(!registered_job.run_once().await);
+        registered_job.run_once().await;
+
+        let status = registered_job.status();
+        assert_eq!(status.runs, 2);
+        assert_eq!(status.consecutive_failures, 2);
+        assert_eq!(
+            status.last_error.as_deref(),
+            Some("kafka: Delivery failed: broker down")
+        );
+        assert!(status.last_error_at.is_some());
+    }
+
+    /// Counts its runs; while `hold` is set, a run waits for `release` before it ends.
+    struct CountingJob {
+        schedule: JobSchedule,
+        runs: AtomicU64,
+        hold: AtomicBool,
+        release: Notify,
+    }
+
+    impl CountingJob {
+        fn new(schedule: JobSchedule) -> Arc<Self> {
+            Arc::new(Self {
+                schedule,
+                runs: AtomicU64::new(0),
+                hold: AtomicBool::new(false),
+                release: Notify::new(),
+            })
+        }
+    }
+
+    impl ScheduledJob for CountingJob {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn default_schedule(&self) -> JobSchedule {
+            self.schedule.clone()
+        }
+
+        fn run(&self) -> JobFuture<'_> {
+            Box::pin(async {
+                self.runs.fetch_add(1, Ordering::Relaxed);
+                if self.hold.load(Ordering::Relaxed) {
+                    self.release.notified().await;
+                }
+                Ok(false)
+            })
+        }
+    }
+
+    const AN_HOUR: Duration = Duration::from_secs(3600);
+
+    fn start(job: Arc<CountingJob>) -> Arc<RegisteredJob> {
+        let registered_job = Arc::new(RegisteredJob::new(job.clone(), job.default_schedule()));
+        tokio::spawn(registered_job.clone().run_forever());
+        registered_job
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        time::timeout(Duration::from_secs(5), async {
+            while !condition() {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("condition not reached within 5s");
+    }
+
+    /// Gives a run that should not happen the time to happen.
+    async fn settle() {
+        time::sleep(Duration::from_millis(100)).await;
+    }
+
+    #[tokio::test]
+    async fn paused_job_runs_again_when_resumed() {
+        let job = CountingJob::new(JobSchedule::FixedDelay(AN_HOUR));
+        let registered_job = Arc::new(RegisteredJob::new(job.clone(), job.default_schedule()));
+        registered_job.pause();
+        tokio::spawn(registered_job.clone().run_forever());
+
+        settle().await;
+        assert_eq!(job.runs.load(Ordering::Relaxed), 0);
+        assert!(registered_job.status().paused);
+
+        registered_job.resume();
+        wait_until(|| registered_job.status().runs == 1).await;
+        assert!(!registered_job.status().paused);
+    }
+
+    #[tokio::test]
+    async fn trigger_runs_a_paused_job_once() {
+        let job = CountingJob::new(JobSchedule::FixedDelay(AN_HOUR));
+        let registered_job = start(job.clone());
+        wait_until(|| registered_job.status().runs == 1).await;
+        registered_job.pause();
+
+        registered_job.trigger().unwrap();
+        wait_until(|| registered_job.status().runs == 2).await;
+        settle().await;
+
+        assert_eq!(job.runs.load(Ordering::Relaxed), 2);
+        assert!(registered_job.status().paused);
+    }
+
+    #[tokio::test]
+    async fn tick_during_a_run_is_skipped() {
+        let job = CountingJob::new(JobSchedule::FixedDelay(AN_HOUR));
+        job.hold.store(true, Ordering::Relaxed);
+        let registered_job = start(job.clone());
+        wait_until(|| registered_job.status().running).await;
+
+        registered_job.on_tick();
+        assert!(matches!(
+            registered_job.trigger(),
+            Err(JobError::AlreadyRunning("counting"))
+        ));
+        job.hold.store(false, Ordering::Relaxed);
+        job.release.notify_one();
+        wait_until(|| !registered_job.status().running).await;
+        settle().await;
+
+        let status = registered_job.status();
+        assert_eq!(status.runs, 1);
+        assert_eq!(status.skipped_runs, 1);
+
+        // the next tick finds the job idle
+        registered_job.on_tick();
+        wait_until(|| registered_job.status().runs == 2).await;
+    }
+
+    #[tokio::test]
+    async fn cron_job_waits_for_its_first_tick() {
+        let job = CountingJob::new(JobSchedule::Cron("* * * * * *".to_string()));
+        let job_registry = JobRegistry::default();
+        job_registry.start(job.clone()).await.unwrap();
+        let registered_job = job_registry.get("counting").unwrap();
+        assert_eq!(registered_job.status().schedule, "cron * * * * * *");
+
+        wait_until(|| registered_job.status().runs >= 1).await;
+    }
+
+    #[tokio::test]
+    async fn invalid_cron_is_rejected() {
+        let job = CountingJob::new(JobSchedule::Cron("every minute".to_string()));
+        let job_registry = JobRegistry::default();
+
+        let result = job_registry.start(job).await;
+
+        assert!(matches!(result, Err(JobError::SchedulingFailed(_))));
+        assert!(job_registry.get("counting").is_none());
+        assert!(job_registry.statuses().is_empty());
+    }
+}
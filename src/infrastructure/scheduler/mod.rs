@@ -1,2 +1,6 @@
 pub mod balance_event_emitter_job;
+pub mod balance_event_emitter_metrics;
+pub mod event_log_retention_job;
+pub mod job_registry;
+pub mod scheduled_job;
 pub mod scheduler;
@@ -0,0 +1,66 @@
+use std::{env, fmt, future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use tokio::sync::Notify;
+
+/// `Ok(true)` when the run left work behind and the job should run again right away.
+pub type JobFuture<'a> = Pin<Box<dyn Future<Output = Result<bool, String>> + Send + 'a>>;
+
+#[derive(Debug, Clone)]
+pub enum JobSchedule {
+    /// Six fields, seconds first: `0 */5 * * * *` runs every five minutes.
+    Cron(String),
+    /// The next run starts this long after the previous one ended.
+    FixedDelay(Duration),
+}
+
+impl JobSchedule {
+    /// `<NAME>_JOB_CRON` wins over `<NAME>_JOB_FIXED_DELAY_MS`; `default` applies when
+    /// neither is set.
+    pub fn from_env(job_name: &str, default: JobSchedule) -> Self {
+        let env_prefix = job_name.to_uppercase();
+        if let Ok(cron) = env::var(format!("{env_prefix}_JOB_CRON")) {
+            return JobSchedule::Cron(cron);
+        }
+        match env::var(format!("{env_prefix}_JOB_FIXED_DELAY_MS"))
+            .ok()
+            .and_then(|delay_ms| delay_ms.parse::<u64>().ok())
+        {
+            Some(delay_ms) => JobSchedule::FixedDelay(Duration::from_millis(delay_ms)),
+            None => default,
+        }
+    }
+}
+
+impl fmt::Display for JobSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobSchedule::Cron(cron) => write!(f, "cron {cron}"),
+            JobSchedule::FixedDelay(delay) => write!(f, "fixed delay {}ms", delay.as_millis()),
+        }
+    }
+}
+
+/// `<NAME>_JOB_ENABLED`, true by default. Checked before creating the job, which may open
+/// connections.
+pub fn is_job_enabled(job_name: &str) -> bool {
+    env::var(format!("{}_JOB_ENABLED", job_name.to_uppercase()))
+        .unwrap_or("true".to_string())
+        .parse::<bool>()
+        .unwrap_or(true)
+}
+
+/// A periodic job run by the `JobRegistry`.
+pub trait ScheduledJob: Send + Sync {
+    /// Upper-cased, also the prefix of its settings: `balance_event_emitter` reads
+    /// `BALANCE_EVENT_EMITTER_JOB_ENABLED`, `_JOB_CRON` and `_JOB_FIXED_DELAY_MS`.
+    fn name(&self) -> &'static str;
+
+    fn default_schedule(&self) -> JobSchedule;
+
+    fn run(&self) -> JobFuture<'_>;
+
+    /// Notified when work arrives, running the job before its schedule is due.
+    fn wake(&self) -> Option<Arc<Notify>> {
+        None
+    }
+}
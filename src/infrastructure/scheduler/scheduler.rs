@@ -1,40 +1,59 @@
-use std::{env, sync::Arc, time::Duration};
+use std::sync::Arc;
 
-use tokio::time;
+use log::{error, info};
 
 use crate::infrastructure::{
-    app_ioc::AppState, scheduler::balance_event_emitter_job::BalanceEventEmitterJob,
+    app_ioc::AppState,
+    scheduler::{
+        balance_event_emitter_job::{BALANCE_EVENT_EMITTER_JOB, BalanceEventEmitterJob},
+        event_log_retention_job::{EVENT_LOG_RETENTION_JOB, EventLogRetentionJob},
+        scheduled_job::is_job_enabled,
+    },
 };
 
 pub async fn schedule(ioc: Arc<AppState>) {
-    run_balance_event_emitter_job(ioc).await;
+    schedule_balance_event_emitter_job(ioc.clone()).await;
+    schedule_event_log_retention_job(ioc).await;
 }
 
-async fn run_balance_event_emitter_job(ioc: Arc<AppState>) {
+async fn schedule_balance_event_emitter_job(ioc: Arc<AppState>) {
+    if !is_job_enabled(BALANCE_EVENT_EMITTER_JOB) {
+        info!("Balance event emitter job disabled");
+        return;
+    }
+
     let balance_event_emitter_job = BalanceEventEmitterJob::new(ioc.clone());
-    tokio::spawn(async move {
-        run_with_fixed_delay(
-            || async {
-                balance_event_emitter_job.publish_event().await;
-            },
-            Duration::from_millis(
-                env::var("BALANCE_EVENT_PUBLISH_EVENT_INTERVAL_MS")
-                    .unwrap_or("100".to_string())
-                    .parse::<u64>()
-                    .unwrap_or(100),
-            ),
-        )
-        .await;
-    });
+    if !balance_event_emitter_job.has_sinks() {
+        info!("No balance event sink enabled, balance event emitter job not scheduled");
+        return;
+    }
+    if let Err(job_error) = ioc
+        .job_registry
+        .start(Arc::new(balance_event_emitter_job))
+        .await
+    {
+        error!("Balance event emitter job not scheduled: {job_error}");
+    }
 }
 
-async fn run_with_fixed_delay<F, Fut>(mut task: F, delay: Duration)
-where
-    F: FnMut() -> Fut,
-    Fut: std::future::Future<Output = ()>,
-{
-    loop {
-        task().await;
-        time::sleep(delay).await;
+async fn schedule_event_log_retention_job(ioc: Arc<AppState>) {
+    let Some(event_log) = &ioc.event_log else {
+        return;
+    };
+    if event_log.lock().unwrap().retention_segments() == 0 {
+        info!("Event log keeps every segment, retention job not scheduled");
+        return;
+    }
+    if !is_job_enabled(EVENT_LOG_RETENTION_JOB) {
+        info!("Event log retention job disabled");
+        return;
+    }
+
+    if let Err(job_error) = ioc
+        .job_registry
+        .start(Arc::new(EventLogRetentionJob::new(event_log.clone())))
+        .await
+    {
+        error!("Event log retention job not scheduled: {job_error}");
     }
 }
@@ -0,0 +1,4 @@
+pub mod record_format;
+pub mod record_version_header_migration;
+pub mod storage_migration;
+pub mod storage_migrator;
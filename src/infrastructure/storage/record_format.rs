@@ -0,0 +1,62 @@
+use std::error::Error;
+use std::fmt;
+
+use bincode::{Decode, Encode, config};
+
+/// Every value stored in `BALANCES_CF`, `EVENTS_CF` and `COMMAND_RESULTS_CF` is prefixed with a
+/// big-endian record version, followed by the bincode payload of that version.
+pub type RecordVersion = u16;
+
+pub const RECORD_HEADER_SIZE: usize = size_of::<RecordVersion>();
+
+#[derive(Debug)]
+pub enum RecordFormatError {
+    MissingHeader,
+    UnsupportedVersion(RecordVersion),
+    DecodeError(String),
+}
+
+impl fmt::Display for RecordFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordFormatError::MissingHeader => write!(f, "Record is missing its version header"),
+            RecordFormatError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported record version {version}")
+            }
+            RecordFormatError::DecodeError(message) => {
+                write!(f, "Failed to decode record: {message}")
+            }
+        }
+    }
+}
+
+impl Error for RecordFormatError {}
+
+pub fn encode_record<T: Encode>(version: RecordVersion, value: &T) -> Vec<u8> {
+    let payload = bincode::encode_to_vec(value, config::standard()).unwrap();
+    with_header(version, &payload)
+}
+
+pub fn with_header(version: RecordVersion, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(RECORD_HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(&version.to_be_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+pub fn split_header(bytes: &[u8]) -> Result<(RecordVersion, &[u8]), RecordFormatError> {
+    if bytes.len() < RECORD_HEADER_SIZE {
+        return Err(RecordFormatError::MissingHeader);
+    }
+    let (header, payload) = bytes.split_at(RECORD_HEADER_SIZE);
+    Ok((
+        RecordVersion::from_be_bytes(header.try_into().unwrap()),
+        payload,
+    ))
+}
+
+pub fn decode_payload<T: Decode<()>>(payload: &[u8]) -> Result<T, RecordFormatError> {
+    bincode::decode_from_slice(payload, config::standard())
+        .map(|(value, _)| value)
+        .map_err(|error| RecordFormatError::DecodeError(error.to_string()))
+}
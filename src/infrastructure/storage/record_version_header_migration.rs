@@ -0,0 +1,42 @@
+use log::info;
+use rust_rocksdb::{DBWithThreadMode, IteratorMode, SingleThreaded, WriteBatch};
+
+use crate::infrastructure::{
+    balance::balance_config::{BALANCES_CF, EVENTS_CF, LAST_EVENT_ID},
+    storage::{
+        record_format::{RecordVersion, with_header},
+        storage_migration::{SchemaVersion, StorageMigration},
+    },
+};
+
+/// Records written before schema version 1 are raw bincode without a header.
+/// Their layout is exactly the version 1 payload, so the upgrade only prepends the header.
+pub struct RecordVersionHeaderMigration;
+
+const FIRST_RECORD_VERSION: RecordVersion = 1;
+
+impl StorageMigration for RecordVersionHeaderMigration {
+    fn version(&self) -> SchemaVersion {
+        1
+    }
+
+    fn description(&self) -> &str {
+        "add record version header to balances and events"
+    }
+
+    fn migrate(&self, db: &DBWithThreadMode<SingleThreaded>, batch: &mut WriteBatch) {
+        for cf_name in [BALANCES_CF, EVENTS_CF] {
+            let cf = db.cf_handle(cf_name).unwrap();
+            let mut migrated = 0;
+            for result in db.iterator_cf(cf, IteratorMode::Start) {
+                let (key, value) = result.unwrap();
+                if key.as_ref() == LAST_EVENT_ID.as_bytes() {
+                    continue;
+                }
+                batch.put_cf(cf, key, with_header(FIRST_RECORD_VERSION, &value));
+                migrated += 1;
+            }
+            info!("{cf_name}: {migrated} records staged for version header");
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use rust_rocksdb::{DBWithThreadMode, SingleThreaded, WriteBatch};
+
+pub type SchemaVersion = u32;
+
+/// A one-way upgrade of the persisted records.
+///
+/// Migrations stage their writes into `batch`; the migrator commits the batch together with
+/// the new schema version, so a migration is either fully applied or not at all.
+pub trait StorageMigration {
+    fn version(&self) -> SchemaVersion;
+    fn description(&self) -> &str;
+    fn migrate(&self, db: &DBWithThreadMode<SingleThreaded>, batch: &mut WriteBatch);
+}
@@ -0,0 +1,197 @@
+use std::{env, sync::Arc};
+
+use log::info;
+use rust_rocksdb::{DBWithThreadMode, SingleThreaded, WriteBatch};
+
+use crate::infrastructure::{
+    balance::balance_config::{META_CF, SCHEMA_VERSION},
+    storage::{
+        record_version_header_migration::RecordVersionHeaderMigration,
+        storage_migration::{SchemaVersion, StorageMigration},
+    },
+};
+
+pub struct StorageMigrator {
+    db: Arc<DBWithThreadMode<SingleThreaded>>,
+    migrations: Vec<Box<dyn StorageMigration>>,
+}
+
+impl StorageMigrator {
+    /// Migrations must be listed in ascending version order.
+    pub fn new(db: Arc<DBWithThreadMode<SingleThreaded>>) -> Self {
+        Self {
+            db,
+            migrations: vec![Box::new(RecordVersionHeaderMigration)],
+        }
+    }
+
+    pub fn latest_version(&self) -> SchemaVersion {
+        self.migrations
+            .last()
+            .map(|migration| migration.version())
+            .unwrap_or(0)
+    }
+
+    pub fn schema_version(&self) -> SchemaVersion {
+        let cf = self.db.cf_handle(META_CF).unwrap();
+        self.db
+            .get_cf(cf, SCHEMA_VERSION)
+            .unwrap()
+            .map(|bytes| SchemaVersion::from_be_bytes(bytes.try_into().unwrap()))
+            .unwrap_or(0)
+    }
+
+    pub fn migrate(&self) -> SchemaVersion {
+        let current_version = self.schema_version();
+        for migration in self
+            .migrations
+            .iter()
+            .filter(|migration| migration.version() > current_version)
+        {
+            info!(
+                "Running storage migration v{}: {}",
+                migration.version(),
+                migration.description()
+            );
+            let mut batch = WriteBatch::default();
+            migration.migrate(&self.db, &mut batch);
+            batch.put_cf(
+                self.db.cf_handle(META_CF).unwrap(),
+                SCHEMA_VERSION,
+                migration.version().to_be_bytes(),
+            );
+            self.db.write(batch).unwrap();
+        }
+
+        let schema_version = self.schema_version();
+        info!("Storage schema version: {schema_version}");
+        schema_version
+    }
+
+    /// Runs pending migrations when `STORAGE_MIGRATE_ON_STARTUP` is enabled (default),
+    /// otherwise refuses to start on an outdated store.
+    pub fn migrate_on_startup(&self) {
+        let migrate_on_startup = env::var("STORAGE_MIGRATE_ON_STARTUP")
+            .unwrap_or("true".to_string())
+            .parse::<bool>()
+            .unwrap_or(true);
+        if migrate_on_startup {
+            self.migrate();
+            return;
+        }
+
+        let schema_version = self.schema_version();
+        if schema_version < self.latest_version() {
+            panic!(
+                "Storage schema version {schema_version} is behind {}, run `actor-bank migrate` first",
+                self.latest_version()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode::{Encode, config};
+
+    use super::*;
+    use crate::{
+        application::balance::spi::{
+            balance_event_repository::BalanceEventRepository, balance_repository::BalanceRepository,
+        },
+        core::domain::{
+            balance::Balance,
+            balance_event::{BalanceEventType, EventId},
+        },
+        infrastructure::balance::{
+            balance_config::{BALANCES_CF, EVENTS_CF, open_db},
+            balance_event_repository_rocksdb::BalanceEventRepositoryRocksdb,
+            balance_event_store::stage_last_event_id,
+            balance_repository_rocksdb::BalanceRepositoryRocksdb,
+        },
+    };
+
+    /// `BalanceEvent` as it was stored before schema version 1: no record header and no
+    /// per-event schema version.
+    #[derive(Encode)]
+    struct UnversionedBalanceEvent {
+        id: EventId,
+        event_type: BalanceEventType,
+        event_time: u64,
+        data: Vec<u8>,
+    }
+
+    fn seed_unversioned_store(db: &DBWithThreadMode<SingleThreaded>) {
+        let mut batch = WriteBatch::default();
+        let balances_cf = db.cf_handle(BALANCES_CF).unwrap();
+        for balance in [Balance::new(1, 100), Balance::new(2, 250)] {
+            let bytes = bincode::encode_to_vec(&balance, config::standard()).unwrap();
+            batch.put_cf(balances_cf, balance.id.to_be_bytes(), bytes);
+        }
+        let events_cf = db.cf_handle(EVENTS_CF).unwrap();
+        for id in 1..=2 {
+            let event = UnversionedBalanceEvent {
+                id,
+                event_type: BalanceEventType::BalanceCreated,
+                event_time: 42,
+                data: vec![id as u8],
+            };
+            let bytes = bincode::encode_to_vec(&event, config::standard()).unwrap();
+            batch.put_cf(events_cf, id.to_be_bytes(), bytes);
+        }
+        stage_last_event_id(db, &mut batch, 2);
+        db.write(batch).unwrap();
+    }
+
+    #[test]
+    fn migrate_adds_record_headers_and_bumps_schema_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(dir.path());
+        seed_unversioned_store(&db);
+        let migrator = StorageMigrator::new(db.clone());
+        assert_eq!(migrator.schema_version(), 0);
+
+        assert_eq!(migrator.migrate(), migrator.latest_version());
+
+        let meta_cf = db.cf_handle(META_CF).unwrap();
+        let stored_version = db.get_cf(meta_cf, SCHEMA_VERSION).unwrap().unwrap();
+        assert_eq!(stored_version, 1_u32.to_be_bytes());
+
+        let balances = BalanceRepositoryRocksdb::new(db.clone()).load_all();
+        assert_eq!(balances, vec![Balance::new(1, 100), Balance::new(2, 250)]);
+
+        let event_repository = BalanceEventRepositoryRocksdb::new(db.clone());
+        assert_eq!(event_repository.last_event_id(), 2);
+        let events: Vec<_> = event_repository
+            .read(1, 10)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(events.len(), 2);
+        for (event, id) in events.iter().zip(1_u64..) {
+            assert_eq!(event.id, id);
+            assert_eq!(event.event_type, BalanceEventType::BalanceCreated);
+            assert_eq!(event.schema_version, 1);
+            assert_eq!(event.event_time, 42);
+            assert_eq!(event.data, vec![id as u8]);
+        }
+    }
+
+    #[test]
+    fn migrate_is_a_no_op_on_an_up_to_date_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(dir.path());
+        seed_unversioned_store(&db);
+        let migrator = StorageMigrator::new(db.clone());
+        migrator.migrate();
+        let balances_cf = db.cf_handle(BALANCES_CF).unwrap();
+        let migrated = db.get_cf(balances_cf, 1_u64.to_be_bytes()).unwrap();
+
+        assert_eq!(migrator.migrate(), migrator.latest_version());
+
+        assert_eq!(
+            db.get_cf(balances_cf, 1_u64.to_be_bytes()).unwrap(),
+            migrated
+        );
+    }
+}
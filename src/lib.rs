@@ -0,0 +1,4 @@
+pub mod application;
+pub mod core;
+pub mod infrastructure;
+pub mod transport;
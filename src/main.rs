@@ -1,20 +1,32 @@
+use actix::Arbiter;
 use actix_web::middleware;
 use actix_web::{App, HttpServer, web};
-use core::common::types::Result;
-use core::common::types::Void;
+use actor_bank::core::common::types::Result;
+use actor_bank::core::common::types::Void;
+use actor_bank::infrastructure::app_ioc::AppState;
+use actor_bank::transport::rest::balance_resource;
 use dotenv::dotenv;
-use infrastructure::app_ioc::AppState;
+use std::env;
 use std::sync::Arc;
-use transport::rest::balance_resource;
 
-use crate::infrastructure::scheduler::scheduler::schedule;
-use crate::infrastructure::server_config::{ServerConfig, initialize_logging};
-use crate::transport::rest::balance_event_resource;
-
-pub mod application;
-pub mod core;
-pub mod infrastructure;
-pub mod transport;
+use actor_bank::infrastructure::balance::balance_config::new_db_single_threaded_mode;
+use actor_bank::infrastructure::scheduler::scheduler::schedule;
+use actor_bank::infrastructure::server_config::{ServerConfig, initialize_logging};
+use actor_bank::infrastructure::storage::storage_migrator::StorageMigrator;
+#[cfg(feature = "grpc")]
+use actor_bank::transport::grpc::grpc_server::serve_grpc;
+#[cfg(feature = "kafka")]
+use actor_bank::transport::kafka::balance_command_consumer::consume_balance_commands;
+use actor_bank::transport::rest::balance_batch_resource;
+use actor_bank::transport::rest::balance_event_resource;
+use actor_bank::transport::rest::balance_event_stream_resource;
+use actor_bank::transport::rest::balance_event_subscription_resource;
+use actor_bank::transport::rest::balance_list_resource;
+use actor_bank::transport::rest::job_resource;
+use actor_bank::transport::rest::openapi_resource;
+use actor_bank::transport::rest::problem_response;
+use actor_bank::transport::tcp::balance_tcp_server::serve_tcp;
+use actor_bank::transport::ws::balance_ws;
 
 #[actix_web::main]
 async fn main() -> Result<Void> {
@@ -22,15 +34,38 @@ async fn main() -> Result<Void> {
     let config = ServerConfig::from_env();
     initialize_logging(&config.log_config_path)?;
 
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("migrate") {
+        StorageMigrator::new(new_db_single_threaded_mode()).migrate();
+        return Ok(());
+    }
+
     let app_state = AppState::new();
 
-    tokio::spawn(schedule(Arc::new(app_state.clone())));
+    // jobs read the event store synchronously, keep them off the HTTP and ledger threads
+    Arbiter::new().spawn(schedule(Arc::new(app_state.clone())));
+    let balance_event_stream = app_state.balance_event_stream.clone();
+    Arbiter::new().spawn(async move { balance_event_stream.run().await });
+    Arbiter::new().spawn(serve_tcp(app_state.balance_api_addr.clone()));
+    #[cfg(feature = "grpc")]
+    Arbiter::new().spawn(serve_grpc(Arc::new(app_state.clone())));
+    #[cfg(feature = "kafka")]
+    Arbiter::new().spawn(consume_balance_commands(app_state.balance_api_addr.clone()));
 
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(app_state.clone()))
+            .configure(problem_response::config)
             .configure(balance_resource::config)
+            .configure(balance_batch_resource::config)
+            .configure(balance_list_resource::config)
             .configure(balance_event_resource::config)
+            .configure(balance_event_stream_resource::config)
+            .configure(balance_event_subscription_resource::config)
+            .configure(job_resource::config)
+            .configure(balance_ws::config)
+            .configure(openapi_resource::config)
+            .default_service(web::to(problem_response::route_not_found))
             .wrap(middleware::Compress::default())
     })
     .workers(config.worker_size)
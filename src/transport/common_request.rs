@@ -0,0 +1,45 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::{
+    application::balance::api::{
+        create_balance_api::CreateBalanceCommand, deposit_balance_api::DepositBalanceCommand,
+        ledger_command_api::LedgerCommand, transfer_balance_api::TransferBalanceCommand,
+        withdraw_balance_api::WithdrawBalanceCommand,
+    },
+    transport::rest::balance_payload::{
+        CreateBalanceRequest, DepositBalanceRequest, TransferBalanceRequest, WithdrawBalanceRequest,
+    },
+};
+
+/// A ledger command outside of its REST route, carrying the body of that route.
+///
+/// Externally tagged, `{"deposit": {"id": 1, "amount": 100}}`: serde buffers internally
+/// tagged enums, and its buffer cannot hold the `u128` amounts.
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceCommandRequest {
+    Create(CreateBalanceRequest),
+    Deposit(DepositBalanceRequest),
+    Withdraw(WithdrawBalanceRequest),
+    Transfer(TransferBalanceRequest),
+}
+
+impl BalanceCommandRequest {
+    pub fn ledger_command(&self) -> LedgerCommand {
+        match self {
+            BalanceCommandRequest::Create(request) => {
+                LedgerCommand::Create(CreateBalanceCommand::new(request.id))
+            }
+            BalanceCommandRequest::Deposit(request) => {
+                LedgerCommand::Deposit(DepositBalanceCommand::new(request.id, request.amount))
+            }
+            BalanceCommandRequest::Withdraw(request) => {
+                LedgerCommand::Withdraw(WithdrawBalanceCommand::new(request.id, request.amount))
+            }
+            BalanceCommandRequest::Transfer(request) => LedgerCommand::Transfer(
+                TransferBalanceCommand::new(request.from_id, request.to_id, request.amount),
+            ),
+        }
+    }
+}
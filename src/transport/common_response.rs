@@ -1,13 +1,21 @@
 use serde::Serialize;
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct SuccessResponse<T: Serialize> {
     pub code: u16,
     pub data: T,
 }
 
-#[derive(Serialize)]
-pub struct ErrorResponse {
-    pub code: u16,
-    pub message: String,
+/// Problem details (RFC 9457), served as `application/problem+json`.
+#[derive(Serialize, ToSchema)]
+pub struct ProblemResponse {
+    /// Always `about:blank`: `code` tells the problems apart.
+    #[serde(rename = "type")]
+    pub problem_type: &'static str,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    /// Stable and machine readable, unlike `detail`.
+    pub code: &'static str,
 }
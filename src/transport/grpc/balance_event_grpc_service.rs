@@ -0,0 +1,223 @@
+use std::{collections::BTreeSet, pin::Pin, sync::Arc};
+
+use log::error;
+use tokio_stream::{Stream, StreamExt, wrappers::ReceiverStream};
+use tonic::{Request, Response, Status};
+
+use crate::{
+    application::balance::api::balance_event_api::BalanceEventApi,
+    core::domain::balance_event::BalanceEventType,
+    infrastructure::{
+        event_sink::{event_subscription::EventFilter, protobuf_event_encoding::BalanceEventProto},
+        event_stream::{
+            balance_event_follower::{FollowOptions, FollowedEvent},
+            balance_event_stream::BalanceEventStream,
+        },
+    },
+    transport::grpc::{
+        balance_grpc_message::{ReadEventsReply, ReadEventsRequest, SubscribeRequest},
+        generated::balance_event_service_server::BalanceEventService,
+    },
+};
+
+pub struct BalanceEventGrpcService {
+    pub balance_event_api: Arc<BalanceEventApi>,
+    pub balance_event_stream: Arc<BalanceEventStream>,
+    /// Events waiting for a subscriber; a subscriber further behind has its stream ended.
+    pub subscriber_buffer: usize,
+    /// Larger `limit`s are lowered to it.
+    pub read_events_max_limit: u64,
+}
+
+fn event_filter(request: &SubscribeRequest) -> Result<EventFilter, Status> {
+    let event_types = if request.event_types.is_empty() {
+        None
+    } else {
+        let event_types = request
+            .event_types
+            .iter()
+            .map(|name| {
+                BalanceEventType::from_name(name).ok_or_else(|| {
+                    Status::invalid_argument(format!("Unknown balance event type: {name}"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Some(event_types)
+    };
+    let balance_ids = (!request.balance_ids.is_empty())
+        .then(|| request.balance_ids.iter().copied().collect::<BTreeSet<_>>());
+    Ok(EventFilter {
+        event_types,
+        balance_ids,
+    })
+}
+
+#[tonic::async_trait]
+impl BalanceEventService for BalanceEventGrpcService {
+    async fn read_events(
+        &self,
+        request: Request<ReadEventsRequest>,
+    ) -> Result<Response<ReadEventsReply>, Status> {
+        let request = request.into_inner();
+        // event ids start at 1, and an unset field reads as 0
+        if request.offset == 0 {
+            return Err(Status::invalid_argument("offset must be at least 1"));
+        }
+        if request.limit == 0 {
+            return Err(Status::invalid_argument("limit must be at least 1"));
+        }
+        let offset = request.offset;
+        let limit = request.limit.min(self.read_events_max_limit);
+        let mut events = vec![];
+        let mut unreadable_event_ids = vec![];
+        for result in self.balance_event_api.get_balance_events(offset, limit) {
+            match result {
+                Ok(event) => events.push(BalanceEventProto::from(&event)),
+                Err(unreadable) => {
+                    error!("ReadEvents leaves out {unreadable}");
+                    unreadable_event_ids.push(unreadable.id);
+                }
+            }
+        }
+        Ok(Response::new(ReadEventsReply {
+            events,
+            unreadable_event_ids,
+        }))
+    }
+
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<BalanceEventProto, Status>> + Send>>;
+
+    /// Ends when the subscriber falls behind; it resubscribes with the id of the last event it
+    /// received as `last_event_id`.
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let request = request.into_inner();
+        let followed_events = self.balance_event_stream.follow(FollowOptions {
+            filter: event_filter(&request)?,
+            resume_after: request.last_event_id,
+            buffer: self.subscriber_buffer,
+            heartbeat: None,
+        });
+        let events =
+            ReceiverStream::new(followed_events).filter_map(
+                |followed_event| match followed_event {
+                    FollowedEvent::Event(event) => {
+                        Some(Ok(BalanceEventProto::from(event.as_ref())))
+                    }
+                    FollowedEvent::Heartbeat => None,
+                },
+            );
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tokio::sync::Notify;
+    use tonic::Code;
+
+    use super::*;
+    use crate::infrastructure::balance::balance_event_repository_rocksdb::tests::stored_events;
+
+    /// Over `count` stored events, the `unreadable` ones corrupted, reading at most two at once.
+    fn service(dir: &Path, count: u64, unreadable: &[u64]) -> BalanceEventGrpcService {
+        let (_db, _repository, balance_event_api) = stored_events(dir, count, unreadable);
+        BalanceEventGrpcService {
+            balance_event_api: balance_event_api.clone(),
+            balance_event_stream: Arc::new(BalanceEventStream::new(
+                balance_event_api,
+                Arc::new(Notify::new()),
+            )),
+            subscriber_buffer: 16,
+            read_events_max_limit: 2,
+        }
+    }
+
+    async fn read_events(
+        service: &BalanceEventGrpcService,
+        offset: u64,
+        limit: u64,
+    ) -> Result<ReadEventsReply, Status> {
+        service
+            .read_events(Request::new(ReadEventsRequest { offset, limit }))
+            .await
+            .map(Response::into_inner)
+    }
+
+    fn event_ids(reply: &ReadEventsReply) -> Vec<u64> {
+        reply.events.iter().map(|event| event.id).collect()
+    }
+
+    #[tokio::test]
+    async fn read_events_rejects_a_zero_offset_or_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path(), 3, &[]);
+
+        let zero_offset = read_events(&service, 0, 2).await.unwrap_err();
+        let zero_limit = read_events(&service, 1, 0).await.unwrap_err();
+
+        assert_eq!(zero_offset.code(), Code::InvalidArgument);
+        assert_eq!(zero_limit.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn read_events_limit_is_lowered_to_the_maximum() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path(), 3, &[]);
+
+        let first_page = read_events(&service, 1, u64::MAX).await.unwrap();
+        let last_page = read_events(&service, 3, 10).await.unwrap();
+
+        assert_eq!(event_ids(&first_page), vec![1, 2]);
+        assert_eq!(event_ids(&last_page), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn read_events_lists_the_unreadable_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path(), 3, &[1]);
+
+        let reply = read_events(&service, 1, 2).await.unwrap();
+
+        assert_eq!(event_ids(&reply), vec![2]);
+        assert_eq!(reply.unreadable_event_ids, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn subscribe_replays_the_matching_stored_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path(), 3, &[]);
+
+        let mut events = service
+            .subscribe(Request::new(SubscribeRequest {
+                event_types: vec!["balance_created".to_string()],
+                balance_ids: vec![2],
+                last_event_id: Some(0),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(events.next().await.unwrap().unwrap().id, 2);
+    }
+
+    #[tokio::test]
+    async fn subscribe_rejects_an_unknown_event_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path(), 3, &[]);
+
+        let subscription = service
+            .subscribe(Request::new(SubscribeRequest {
+                event_types: vec!["balance_closed".to_string()],
+                balance_ids: vec![],
+                last_event_id: None,
+            }))
+            .await;
+
+        assert_eq!(subscription.err().unwrap().code(), Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,83 @@
+use prost::Message;
+
+use crate::infrastructure::event_sink::protobuf_event_encoding::BalanceEventProto;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CreateBalanceRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct DepositRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct WithdrawRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TransferRequest {
+    #[prost(uint64, tag = "1")]
+    pub from_id: u64,
+    #[prost(uint64, tag = "2")]
+    pub to_id: u64,
+    #[prost(string, tag = "3")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CommandReply {
+    #[prost(uint64, tag = "1")]
+    pub event_id: u64,
+    #[prost(message, repeated, tag = "2")]
+    pub balances: Vec<BalanceReply>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct GetBalanceRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BalanceReply {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ReadEventsRequest {
+    #[prost(uint64, tag = "1")]
+    pub offset: u64,
+    #[prost(uint64, tag = "2")]
+    pub limit: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ReadEventsReply {
+    #[prost(message, repeated, tag = "1")]
+    pub events: Vec<BalanceEventProto>,
+    #[prost(uint64, repeated, tag = "2")]
+    pub unreadable_event_ids: Vec<u64>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct SubscribeRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub event_types: Vec<String>,
+    #[prost(uint64, repeated, tag = "2")]
+    pub balance_ids: Vec<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub last_event_id: Option<u64>,
+}
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use actix::{Addr, MailboxError};
+use tonic::{Request, Response, Status};
+
+use crate::{
+    application::balance::api::{
+        balance_api::BalanceApi, balance_query_api::BalanceQuery,
+        create_balance_api::CreateBalanceCommand, deposit_balance_api::DepositBalanceCommand,
+        ledger_command_api::CommandReceipt, transfer_balance_api::TransferBalanceCommand,
+        withdraw_balance_api::WithdrawBalanceCommand,
+    },
+    core::domain::{
+        balance::{Balance, BalanceAmount},
+        balance_error::BalanceError,
+    },
+    transport::grpc::{
+        balance_grpc_message::{
+            BalanceReply, CommandReply, CreateBalanceRequest, DepositRequest, GetBalanceRequest,
+            TransferRequest, WithdrawRequest,
+        },
+        generated::balance_service_server::BalanceService,
+    },
+};
+
+impl From<BalanceError> for Status {
+    fn from(balance_error: BalanceError) -> Self {
+        let message = balance_error.to_string();
+        match balance_error {
+            BalanceError::BalanceAlreadyExists(_) => Status::already_exists(message),
+            BalanceError::BalanceNotFound(_) => Status::not_found(message),
+            BalanceError::InsufficientFunds { .. } => Status::failed_precondition(message),
+            BalanceError::UnknownError(_) => Status::internal(message),
+        }
+    }
+}
+
+impl From<&Balance> for BalanceReply {
+    fn from(balance: &Balance) -> Self {
+        Self {
+            id: balance.id(),
+            amount: balance.amount().to_string(),
+        }
+    }
+}
+
+impl From<CommandReceipt> for CommandReply {
+    fn from(receipt: CommandReceipt) -> Self {
+        Self {
+            event_id: receipt.event_id,
+            balances: receipt.balances.iter().map(BalanceReply::from).collect(),
+        }
+    }
+}
+
+fn ledger_unavailable(mailbox_error: MailboxError) -> Status {
+    Status::unavailable(format!("Ledger unavailable: {mailbox_error}"))
+}
+
+/// Amounts travel as decimal strings, protobuf has no 128-bit integer.
+fn parse_amount(amount: &str) -> Result<BalanceAmount, Status> {
+    amount
+        .parse::<BalanceAmount>()
+        .map_err(|_| Status::invalid_argument(format!("Invalid amount: {amount}")))
+}
+
+/// Sends to the same ledger actor as the REST resources.
+pub struct BalanceGrpcService {
+    pub balance_api_addr: Arc<Addr<BalanceApi>>,
+}
+
+#[tonic::async_trait]
+impl BalanceService for BalanceGrpcService {
+    async fn create_balance(
+        &self,
+        request: Request<CreateBalanceRequest>,
+    ) -> Result<Response<CommandReply>, Status> {
+        let request = request.into_inner();
+        let receipt = self
+            .balance_api_addr
+            .send(CreateBalanceCommand::new(request.id))
+            .await
+            .map_err(ledger_unavailable)??;
+        Ok(Response::new(receipt.into()))
+    }
+
+    async fn deposit(
+        &self,
+        request: Request<DepositRequest>,
+    ) -> Result<Response<CommandReply>, Status> {
+        let request = request.into_inner();
+        let amount = parse_amount(&request.amount)?;
+        let receipt = self
+            .balance_api_addr
+            .send(DepositBalanceCommand::new(request.id, amount))
+            .await
+            .map_err(ledger_unavailable)??;
+        Ok(Response::new(receipt.into()))
+    }
+
+    async fn withdraw(
+        &self,
+        request: Request<WithdrawRequest>,
+    ) -> Result<Response<CommandReply>, Status> {
+        let request = request.into_inner();
+        let amount = parse_amount(&request.amount)?;
+        let receipt = self
+            .balance_api_addr
+            .send(WithdrawBalanceCommand::new(request.id, amount))
+            .await
+            .map_err(ledger_unavailable)??;
+        Ok(Response::new(receipt.into()))
+    }
+
+    async fn transfer(
+        &self,
+        request: Request<TransferRequest>,
+    ) -> Result<Response<CommandReply>, Status> {
+        let request = request.into_inner();
+        let amount = parse_amount(&request.amount)?;
+        let receipt = self
+            .balance_api_addr
+            .send(TransferBalanceCommand::new(
+                request.from_id,
+                request.to_id,
+                amount,
+            ))
+            .await
+            .map_err(ledger_unavailable)??;
+        Ok(Response::new(receipt.into()))
+    }
+
+    async fn get_balance(
+        &self,
+        request: Request<GetBalanceRequest>,
+    ) -> Result<Response<BalanceReply>, Status> {
+        let request = request.into_inner();
+        let balance = self
+            .balance_api_addr
+            .send(BalanceQuery { id: request.id })
+            .await
+            .map_err(ledger_unavailable)??;
+        Ok(Response::new(BalanceReply::from(&balance)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tonic::Code;
+
+    use super::*;
+    use crate::infrastructure::{
+        app_ioc::AppState,
+        balance::{balance_actor::tests::stopped_balance_api, balance_config::open_db},
+    };
+
+    fn service(dir: &Path) -> BalanceGrpcService {
+        BalanceGrpcService {
+            balance_api_addr: AppState::with_db(open_db(dir)).balance_api_addr,
+        }
+    }
+
+    fn balances(reply: &CommandReply) -> Vec<(u64, &str)> {
+        reply
+            .balances
+            .iter()
+            .map(|balance| (balance.id, balance.amount.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn balance_errors_map_to_status_codes() {
+        let balance_errors = [
+            (BalanceError::BalanceAlreadyExists(1), Code::AlreadyExists),
+            (BalanceError::BalanceNotFound(1), Code::NotFound),
+            (
+                BalanceError::InsufficientFunds {
+                    balance: 10,
+                    amount: 20,
+                },
+                Code::FailedPrecondition,
+            ),
+            (
+                BalanceError::UnknownError("disk full".to_string()),
+                Code::Internal,
+            ),
+        ];
+        for (balance_error, code) in balance_errors {
+            let message = balance_error.to_string();
+            let status = Status::from(balance_error);
+            assert_eq!(status.code(), code);
+            assert_eq!(status.message(), message);
+        }
+    }
+
+    #[actix_web::test]
+    async fn commands_reply_with_their_receipt() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+        for id in [1, 2] {
+            service
+                .create_balance(Request::new(CreateBalanceRequest { id }))
+                .await
+                .unwrap();
+        }
+        let deposit = service
+            .deposit(Request::new(DepositRequest {
+                id: 1,
+                amount: "100".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let transfer = service
+            .transfer(Request::new(TransferRequest {
+                from_id: 1,
+                to_id: 2,
+                amount: "40".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let balance = service
+            .get_balance(Request::new(GetBalanceRequest { id: 2 }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(deposit.event_id, 3);
+        assert_eq!(balances(&deposit), vec![(1, "100")]);
+        assert_eq!(transfer.event_id, 4);
+        assert_eq!(balances(&transfer), vec![(1, "60"), (2, "40")]);
+        assert_eq!(balance.amount, "40");
+    }
+
+    #[actix_web::test]
+    async fn failures_carry_their_status_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+        service
+            .create_balance(Request::new(CreateBalanceRequest { id: 1 }))
+            .await
+            .unwrap();
+
+        let create_again = service
+            .create_balance(Request::new(CreateBalanceRequest { id: 1 }))
+            .await;
+        let unknown_balance = service
+            .get_balance(Request::new(GetBalanceRequest { id: 9 }))
+            .await;
+        let overdraft = service
+            .withdraw(Request::new(WithdrawRequest {
+                id: 1,
+                amount: "1".to_string(),
+            }))
+            .await;
+        let invalid_amount = service
+            .deposit(Request::new(DepositRequest {
+                id: 1,
+                amount: "ten".to_string(),
+            }))
+            .await;
+
+        assert_eq!(create_again.unwrap_err().code(), Code::AlreadyExists);
+        assert_eq!(unknown_balance.unwrap_err().code(), Code::NotFound);
+        assert_eq!(overdraft.unwrap_err().code(), Code::FailedPrecondition);
+        let invalid_amount = invalid_amount.unwrap_err();
+        assert_eq!(invalid_amount.code(), Code::InvalidArgument);
+        assert_eq!(invalid_amount.message(), "Invalid amount: ten");
+    }
+
+    #[actix_web::test]
+    async fn stopped_ledger_is_unavailable() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = BalanceGrpcService {
+            balance_api_addr: Arc::new(stopped_balance_api(dir.path())),
+        };
+
+        let status = service
+            .create_balance(Request::new(CreateBalanceRequest { id: 1 }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::Unavailable);
+    }
+}
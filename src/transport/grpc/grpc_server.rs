@@ -0,0 +1,88 @@
+use std::{env, net::SocketAddr, sync::Arc};
+
+use log::{error, info};
+use tonic::transport::Server;
+
+use crate::{
+    infrastructure::app_ioc::AppState,
+    transport::grpc::{
+        balance_event_grpc_service::BalanceEventGrpcService,
+        balance_grpc_service::BalanceGrpcService,
+        generated::{
+            balance_event_service_server::BalanceEventServiceServer,
+            balance_service_server::BalanceServiceServer,
+        },
+    },
+};
+
+pub struct GrpcServerConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub subscriber_buffer: usize,
+    pub read_events_max_limit: u64,
+}
+
+impl GrpcServerConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: env::var("GRPC_ENABLED")
+                .unwrap_or("true".to_string())
+                .parse::<bool>()
+                .unwrap_or(true),
+            host: env::var("GRPC_HOST")
+                .or_else(|_| env::var("HOST"))
+                .unwrap_or("0.0.0.0".to_string()),
+            port: env::var("GRPC_PORT")
+                .unwrap_or("50051".to_string())
+                .parse::<u16>()
+                .unwrap_or(50051),
+            subscriber_buffer: env::var("GRPC_SUBSCRIBER_BUFFER")
+                .unwrap_or("1024".to_string())
+                .parse::<usize>()
+                .unwrap_or(1024),
+            read_events_max_limit: env::var("GRPC_READ_EVENTS_MAX_LIMIT")
+                .unwrap_or("1000".to_string())
+                .parse::<u64>()
+                .unwrap_or(1000),
+        }
+    }
+}
+
+/// Serves the gRPC API when `GRPC_ENABLED`, on its own port next to the HTTP server.
+pub async fn serve_grpc(ioc: Arc<AppState>) {
+    let config = GrpcServerConfig::from_env();
+    if !config.enabled {
+        info!("gRPC server disabled");
+        return;
+    }
+    let address = match format!("{}:{}", config.host, config.port).parse::<SocketAddr>() {
+        Ok(address) => address,
+        Err(address_error) => {
+            error!(
+                "Invalid gRPC address {}:{}: {address_error}",
+                config.host, config.port
+            );
+            return;
+        }
+    };
+
+    let balance_service = BalanceGrpcService {
+        balance_api_addr: ioc.balance_api_addr.clone(),
+    };
+    let balance_event_service = BalanceEventGrpcService {
+        balance_event_api: ioc.balance_event_api.clone(),
+        balance_event_stream: ioc.balance_event_stream.clone(),
+        subscriber_buffer: config.subscriber_buffer,
+        read_events_max_limit: config.read_events_max_limit,
+    };
+    info!("gRPC server listening on {address}");
+    if let Err(transport_error) = Server::builder()
+        .add_service(BalanceServiceServer::new(balance_service))
+        .add_service(BalanceEventServiceServer::new(balance_event_service))
+        .serve(address)
+        .await
+    {
+        error!("gRPC server error: {transport_error}");
+    }
+}
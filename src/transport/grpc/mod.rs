@@ -0,0 +1,16 @@
+pub mod balance_event_grpc_service;
+pub mod balance_grpc_message;
+pub mod balance_grpc_service;
+pub mod grpc_server;
+
+/// Server side of the services generated by `build.rs`.
+pub mod generated {
+    include!(concat!(
+        env!("OUT_DIR"),
+        "/actor_bank.balance.v1.BalanceService.rs"
+    ));
+    include!(concat!(
+        env!("OUT_DIR"),
+        "/actor_bank.balance.v1.BalanceEventService.rs"
+    ));
+}
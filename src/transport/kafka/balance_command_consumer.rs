@@ -0,0 +1,257 @@
+use std::{env, sync::Arc, time::Duration};
+
+use actix::{Addr, MailboxError};
+use log::{error, info, warn};
+use rdkafka::{
+    ClientConfig, Message,
+    consumer::{CommitMode, Consumer, StreamConsumer},
+    error::KafkaResult,
+    message::BorrowedMessage,
+    producer::{FutureProducer, FutureRecord},
+};
+use tokio::time;
+
+use crate::{
+    application::balance::api::{
+        balance_api::BalanceApi,
+        ledger_command_api::{CommandResult, LedgerCommandOutcome, RejectLedgerCommand},
+    },
+    transport::kafka::balance_command_payload::{
+        BalanceCommandMessage, BalanceCommandReply, InvalidBalanceCommandReply,
+    },
+};
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+pub struct BalanceCommandConsumerConfig {
+    pub enabled: bool,
+    pub brokers: String,
+    pub topic: String,
+    pub reply_topic: String,
+    pub group_id: String,
+}
+
+impl BalanceCommandConsumerConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: env::var("BALANCE_COMMAND_CONSUMER_ENABLED")
+                .unwrap_or("false".to_string())
+                .parse::<bool>()
+                .unwrap_or(false),
+            brokers: env::var("KAFKA_BROKERS").unwrap_or("localhost:9092".to_string()),
+            topic: env::var("BALANCE_COMMAND_TOPIC").unwrap_or("balance.command".to_string()),
+            reply_topic: env::var("BALANCE_COMMAND_REPLY_TOPIC")
+                .unwrap_or("balance.command.reply".to_string()),
+            group_id: env::var("BALANCE_COMMAND_CONSUMER_GROUP")
+                .unwrap_or("actor-bank.balance-command".to_string()),
+        }
+    }
+}
+
+/// Forwards the commands of `BALANCE_COMMAND_TOPIC` to the ledger actor, one at a time and in
+/// order, and answers each on `BALANCE_COMMAND_REPLY_TOPIC`.
+///
+/// The offset of a record is committed once its result is stored with its effects and its reply
+/// is delivered. A record consumed again after a crash carries a command id the ledger already
+/// knows, so it is answered from the stored result rather than applied twice.
+pub struct BalanceCommandConsumer {
+    config: BalanceCommandConsumerConfig,
+    consumer: StreamConsumer,
+    producer: FutureProducer,
+    balance_api_addr: Arc<Addr<BalanceApi>>,
+}
+
+impl BalanceCommandConsumer {
+    pub fn new(
+        config: BalanceCommandConsumerConfig,
+        balance_api_addr: Arc<Addr<BalanceApi>>,
+    ) -> KafkaResult<Self> {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("group.id", &config.group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .set("isolation.level", "read_committed")
+            .create()?;
+        consumer.subscribe(&[&config.topic])?;
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .create()?;
+        Ok(Self {
+            config,
+            consumer,
+            producer,
+            balance_api_addr,
+        })
+    }
+
+    pub async fn run(&self) {
+        info!(
+            "Consuming balance commands from {}, replies to {}",
+            self.config.topic, self.config.reply_topic
+        );
+        loop {
+            let message = match self.consumer.recv().await {
+                Ok(message) => message,
+                Err(kafka_error) => {
+                    error!("Failed to consume balance command: {kafka_error:?}");
+                    time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            };
+            let Some((reply_key, reply)) = self.handle(&message).await else {
+                // the record is not committed, the next consumer of the topic runs it again
+                error!("Ledger stopped, stopping the balance command consumer");
+                return;
+            };
+            self.send_reply(reply_key.as_deref(), &reply).await;
+            if let Err(kafka_error) = self.consumer.commit_message(&message, CommitMode::Async) {
+                // the record is consumed again after a restart and answered from its result
+                warn!("Failed to commit balance command offset: {kafka_error:?}");
+            }
+        }
+    }
+
+    /// Key and value of the reply to `message`, `None` once the ledger actor has stopped.
+    async fn handle(&self, message: &BorrowedMessage<'_>) -> Option<(Option<String>, String)> {
+        let record_key = message
+            .key()
+            .map(|key| String::from_utf8_lossy(key).into_owned());
+        let command = match serde_json::from_slice::<BalanceCommandMessage>(
+            message.payload().unwrap_or_default(),
+        ) {
+            Ok(command) => command,
+            Err(json_error) => {
+                warn!(
+                    "Invalid balance command at {}/{}: {json_error}",
+                    message.partition(),
+                    message.offset()
+                );
+                let reply = InvalidBalanceCommandReply {
+                    status: "invalid",
+                    message: json_error.to_string(),
+                };
+                return Some((record_key, serde_json::to_string(&reply).unwrap()));
+            }
+        };
+
+        let outcome = self.execute(&command).await?;
+        let reply = BalanceCommandReply {
+            command_id: &command.command_id,
+            correlation_id: command.correlation_id(),
+            result: &outcome.result,
+            replayed: outcome.replayed,
+        };
+        Some((
+            Some(command.correlation_id().to_string()),
+            serde_json::to_string(&reply).unwrap(),
+        ))
+    }
+
+    /// Retries while the command does not reach the ledger: holding back the record keeps the
+    /// commands of the topic in order. A command the ledger fails to apply, such as one that
+    /// panics and is answered with an unknown error, would fail again, so its failure is stored
+    /// as its result instead.
+    ///
+    /// Once the ledger actor has stopped its mailbox is closed and no later command can be
+    /// applied either, so `None` is returned rather than retrying.
+    async fn execute(&self, command: &BalanceCommandMessage) -> Option<LedgerCommandOutcome> {
+        loop {
+            match self.balance_api_addr.send(command.ledger_command()).await {
+                Ok(Ok(outcome)) => return Some(outcome),
+                Ok(Err(balance_error)) => {
+                    error!(
+                        "Balance command {} failed, rejecting it: {balance_error}",
+                        command.command_id
+                    );
+                    return self.reject(command, balance_error.to_string()).await;
+                }
+                Err(MailboxError::Closed) => {
+                    error!(
+                        "Balance command {} not delivered, the ledger stopped",
+                        command.command_id
+                    );
+                    return None;
+                }
+                Err(mailbox_error) => error!(
+                    "Balance command {} not delivered to the ledger, retrying: {mailbox_error}",
+                    command.command_id
+                ),
+            }
+            time::sleep(RETRY_DELAY).await;
+        }
+    }
+
+    /// When the rejection cannot be stored either, the reply still carries it and a redelivery
+    /// of the record runs the command again.
+    async fn reject(
+        &self,
+        command: &BalanceCommandMessage,
+        message: String,
+    ) -> Option<LedgerCommandOutcome> {
+        loop {
+            let reject_command = RejectLedgerCommand {
+                command_id: command.command_id.clone(),
+                message: message.clone(),
+            };
+            match self.balance_api_addr.send(reject_command).await {
+                Ok(Ok(outcome)) => return Some(outcome),
+                Ok(Err(balance_error)) => {
+                    error!(
+                        "Failed to store the rejection of balance command {}: {balance_error}",
+                        command.command_id
+                    );
+                    return Some(LedgerCommandOutcome {
+                        result: CommandResult::Rejected { message },
+                        replayed: false,
+                    });
+                }
+                Err(MailboxError::Closed) => {
+                    error!(
+                        "Rejection of balance command {} not delivered, the ledger stopped",
+                        command.command_id
+                    );
+                    return None;
+                }
+                Err(mailbox_error) => error!(
+                    "Rejection of balance command {} not delivered to the ledger, retrying: \
+                     {mailbox_error}",
+                    command.command_id
+                ),
+            }
+            time::sleep(RETRY_DELAY).await;
+        }
+    }
+
+    async fn send_reply(&self, key: Option<&str>, reply: &str) {
+        loop {
+            let mut record = FutureRecord::to(&self.config.reply_topic).payload(reply);
+            if let Some(key) = key {
+                record = record.key(key);
+            }
+            match self.producer.send(record, SEND_TIMEOUT).await {
+                Ok(_) => return,
+                Err((kafka_error, _)) => {
+                    error!("Failed to send balance command reply, retrying: {kafka_error:?}")
+                }
+            }
+            time::sleep(RETRY_DELAY).await;
+        }
+    }
+}
+
+/// Runs the consumer when `BALANCE_COMMAND_CONSUMER_ENABLED` is set.
+pub async fn consume_balance_commands(balance_api_addr: Arc<Addr<BalanceApi>>) {
+    let config = BalanceCommandConsumerConfig::from_env();
+    if !config.enabled {
+        info!("Balance command consumer disabled");
+        return;
+    }
+    match BalanceCommandConsumer::new(config, balance_api_addr) {
+        Ok(consumer) => consumer.run().await,
+        Err(kafka_error) => error!("Balance command consumer creation error: {kafka_error:?}"),
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::balance::api::ledger_command_api::{
+        CommandId, CommandResult, IdempotentLedgerCommand,
+    },
+    transport::common_request::BalanceCommandRequest,
+};
+
+/// Value of a record of `BALANCE_COMMAND_TOPIC`.
+#[derive(Deserialize)]
+pub struct BalanceCommandMessage {
+    /// Idempotency key: a command id seen before is answered with its stored result.
+    pub command_id: CommandId,
+    /// Key of the reply, the command id when absent.
+    pub correlation_id: Option<String>,
+    pub command: BalanceCommandRequest,
+}
+
+impl BalanceCommandMessage {
+    pub fn correlation_id(&self) -> &str {
+        self.correlation_id.as_deref().unwrap_or(&self.command_id)
+    }
+
+    pub fn ledger_command(&self) -> IdempotentLedgerCommand {
+        IdempotentLedgerCommand {
+            command_id: self.command_id.clone(),
+            command: self.command.ledger_command(),
+        }
+    }
+}
+
+/// Value of a record of `BALANCE_COMMAND_REPLY_TOPIC`, keyed by correlation id.
+#[derive(Serialize)]
+pub struct BalanceCommandReply<'a> {
+    pub command_id: &'a str,
+    pub correlation_id: &'a str,
+    #[serde(flatten)]
+    pub result: &'a CommandResult,
+    /// The command had already been executed, the ledger was left untouched.
+    pub replayed: bool,
+}
+
+/// Reply to a record that is not a command, keyed like that record.
+#[derive(Serialize)]
+pub struct InvalidBalanceCommandReply {
+    pub status: &'static str,
+    pub message: String,
+}
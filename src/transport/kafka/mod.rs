@@ -0,0 +1,2 @@
+pub mod balance_command_consumer;
+pub mod balance_command_payload;
@@ -1,3 +1,9 @@
+pub mod common_request;
 pub mod common_response;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "kafka")]
+pub mod kafka;
 pub mod rest;
+pub mod tcp;
 pub mod ws;
@@ -0,0 +1,259 @@
+use std::env;
+
+use actix_web::{
+    HttpResponse, Responder,
+    http::StatusCode,
+    post,
+    web::{self, Json},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    application::balance::api::{
+        batch_balance_api::{BatchCommand, BatchItemResult, BatchMode},
+        ledger_command_api::CommandReceipt,
+    },
+    infrastructure::app_ioc::AppState,
+    transport::{
+        common_request::BalanceCommandRequest,
+        common_response::{ProblemResponse, SuccessResponse},
+        rest::problem_response::{balance_problem, ledger_unavailable, problem},
+    },
+};
+
+pub struct BalanceBatchConfig {
+    pub max_size: usize,
+}
+
+impl BalanceBatchConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_size: env::var("BALANCE_BATCH_MAX_SIZE")
+                .unwrap_or("1000".to_string())
+                .parse::<usize>()
+                .unwrap_or(1000),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchModeRequest {
+    Atomic,
+    Independent,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BatchBalanceRequest {
+    pub mode: BatchModeRequest,
+    pub commands: Vec<BalanceCommandRequest>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Applied,
+    Rejected,
+    NotApplied,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchItemResponse {
+    pub status: BatchItemStatus,
+    /// Event id and balances of an applied command.
+    #[serde(flatten)]
+    pub receipt: Option<CommandReceipt>,
+    /// Error code of a rejected command, as in problem responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchBalanceResponse {
+    /// At least one command was applied.
+    pub committed: bool,
+    pub results: Vec<BatchItemResponse>,
+}
+
+impl From<BatchItemResult> for BatchItemResponse {
+    fn from(result: BatchItemResult) -> Self {
+        match result {
+            BatchItemResult::Applied(receipt) => Self {
+                status: BatchItemStatus::Applied,
+                receipt: Some(receipt),
+                code: None,
+                message: None,
+            },
+            BatchItemResult::Rejected(balance_error) => Self {
+                status: BatchItemStatus::Rejected,
+                receipt: None,
+                code: Some(balance_error.code()),
+                message: Some(balance_error.to_string()),
+            },
+            BatchItemResult::NotApplied => Self {
+                status: BatchItemStatus::NotApplied,
+                receipt: None,
+                code: None,
+                message: Some("Not applied, another command of the batch was rejected".to_string()),
+            },
+        }
+    }
+}
+
+/// Commands are applied in order, each seeing the balances left by the ones before it.
+/// `atomic` applies all of them or none; `independent` applies every command that is not
+/// rejected. Either way the batch is one ledger message and one write.
+#[utoipa::path(
+    tag = "balances",
+    request_body = BatchBalanceRequest,
+    responses(
+        (status = 200, description = "One result per command", body = SuccessResponse<BatchBalanceResponse>),
+        (status = 400, description = "Invalid body or batch size", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 503, description = "Ledger unavailable", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/balance/batch")]
+async fn execute_batch(
+    ioc: web::Data<AppState>,
+    batch_config: web::Data<BalanceBatchConfig>,
+    request: Json<BatchBalanceRequest>,
+) -> impl Responder {
+    if request.commands.is_empty() || request.commands.len() > batch_config.max_size {
+        return problem(
+            StatusCode::BAD_REQUEST,
+            "invalid_batch_size",
+            format!(
+                "A batch holds between 1 and {} commands, got {}",
+                batch_config.max_size,
+                request.commands.len()
+            ),
+        );
+    }
+    let command = BatchCommand {
+        mode: match request.mode {
+            BatchModeRequest::Atomic => BatchMode::Atomic,
+            BatchModeRequest::Independent => BatchMode::Independent,
+        },
+        commands: request
+            .commands
+            .iter()
+            .map(BalanceCommandRequest::ledger_command)
+            .collect(),
+    };
+    let result = ioc.balance_api_addr.send(command).await;
+    match result {
+        Ok(Ok(item_results)) => {
+            let committed = item_results
+                .iter()
+                .any(|item_result| matches!(item_result, BatchItemResult::Applied(_)));
+            HttpResponse::Ok().json(SuccessResponse {
+                code: 200,
+                data: BatchBalanceResponse {
+                    committed,
+                    results: item_results.into_iter().map(Into::into).collect(),
+                },
+            })
+        }
+        Ok(Err(balance_error)) => balance_problem(&balance_error),
+        Err(mailbox_error) => ledger_unavailable(mailbox_error),
+    }
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::Data::new(BalanceBatchConfig::from_env()))
+        .service(execute_batch);
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App,
+        dev::ServiceResponse,
+        http::header,
+        test::{self, TestRequest},
+    };
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{
+        infrastructure::balance::balance_config::open_db, transport::rest::problem_response,
+    };
+
+    async fn post_batch(ioc: &AppState, body: Value) -> ServiceResponse {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(ioc.clone()))
+                .app_data(web::Data::new(BalanceBatchConfig { max_size: 2 }))
+                .configure(problem_response::config)
+                .service(execute_batch),
+        )
+        .await;
+        let request = TestRequest::post().uri("/balance/batch").set_json(body);
+        test::call_service(&app, request.to_request()).await
+    }
+
+    async fn assert_invalid_batch_size(response: ServiceResponse, detail: &str) {
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/problem+json"
+        );
+        let problem: Value = test::read_body_json(response).await;
+        assert_eq!(problem["code"], "invalid_batch_size");
+        assert_eq!(problem["detail"], detail);
+    }
+
+    #[actix_web::test]
+    async fn batch_over_the_size_limit_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = AppState::with_db(open_db(dir.path()));
+
+        let commands = json!([{"create": {"id": 1}}, {"create": {"id": 2}}, {"create": {"id": 3}}]);
+        let response = post_batch(&ioc, json!({"mode": "independent", "commands": commands})).await;
+
+        assert_invalid_batch_size(response, "A batch holds between 1 and 2 commands, got 3").await;
+        assert_eq!(ioc.balance_event_api.last_event_id(), 0);
+    }
+
+    #[actix_web::test]
+    async fn empty_batch_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = AppState::with_db(open_db(dir.path()));
+
+        let response = post_batch(&ioc, json!({"mode": "atomic", "commands": []})).await;
+
+        assert_invalid_batch_size(response, "A batch holds between 1 and 2 commands, got 0").await;
+    }
+
+    #[actix_web::test]
+    async fn results_are_listed_in_command_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = AppState::with_db(open_db(dir.path()));
+
+        let commands = json!([{"create": {"id": 1}}, {"withdraw": {"id": 1, "amount": 5}}]);
+        let response = post_batch(&ioc, json!({"mode": "atomic", "commands": commands})).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(
+            body,
+            json!({"code": 200, "data": {
+                "committed": false,
+                "results": [
+                    {
+                        "status": "not_applied",
+                        "message": "Not applied, another command of the batch was rejected",
+                    },
+                    {
+                        "status": "rejected",
+                        "code": "insufficient_funds",
+                        "message": "Insufficient funds for withdrawal. Balance: 0, Requested: 5",
+                    },
+                ],
+            }})
+        );
+    }
+}
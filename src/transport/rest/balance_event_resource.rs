@@ -2,11 +2,22 @@ use actix_web::{
     HttpResponse, Responder, get,
     web::{self},
 };
+use log::error;
 use serde::Deserialize;
+use utoipa::IntoParams;
 
-use crate::infrastructure::app_ioc::AppState;
+use crate::{
+    application::balance::api::balance_event_api::BalanceEventData,
+    infrastructure::{
+        app_ioc::AppState, scheduler::balance_event_emitter_metrics::BalanceEventEmitterStatus,
+    },
+    transport::common_response::ProblemResponse,
+};
+
+/// Ids of the events of the page that could not be read and were left out.
+pub const UNREADABLE_EVENTS_HEADER: &str = "X-Unreadable-Events";
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
 pub struct BalanceEventQuery {
     #[serde(default = "default_offset")]
     pub offset: u64,
@@ -22,17 +33,60 @@ fn default_limit() -> u64 {
     10
 }
 
+#[utoipa::path(
+    tag = "balance-events",
+    params(BalanceEventQuery),
+    responses(
+        (
+            status = 200,
+            description = "Events from `offset`, at most `limit`",
+            body = Vec<BalanceEventData>,
+            headers(("X-Unreadable-Events" = String, description = "Comma-separated ids of the events of the page that could not be read and were left out")),
+        ),
+        (status = 400, description = "Invalid query", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
 #[get("/balance-events")]
 async fn get_balance_events(
     ioc: web::Data<AppState>,
     query: web::Query<BalanceEventQuery>,
 ) -> impl Responder {
-    let balance_events = ioc
+    let mut balance_events = vec![];
+    let mut unreadable_ids = vec![];
+    for result in ioc
         .balance_event_api
-        .get_balance_events(query.offset, query.limit);
-    HttpResponse::Ok().json(balance_events)
+        .get_balance_events(query.offset, query.limit)
+    {
+        match result {
+            Ok(balance_event) => balance_events.push(balance_event),
+            Err(unreadable) => {
+                error!("GET /balance-events leaves out {unreadable}");
+                unreadable_ids.push(unreadable.id.to_string());
+            }
+        }
+    }
+
+    let mut response = HttpResponse::Ok();
+    if !unreadable_ids.is_empty() {
+        response.insert_header((UNREADABLE_EVENTS_HEADER, unreadable_ids.join(",")));
+    }
+    response.json(balance_events)
+}
+
+/// Emitted offset, lag and failure counters of every subscription.
+#[utoipa::path(
+    tag = "balance-events",
+    responses(
+        (status = 200, description = "Emitter state by subscription", body = BalanceEventEmitterStatus),
+    )
+)]
+#[get("/balance-events/emitter")]
+async fn get_balance_event_emitter_status(ioc: web::Data<AppState>) -> impl Responder {
+    let last_event_id = ioc.balance_event_api.last_event_id();
+    HttpResponse::Ok().json(ioc.balance_event_emitter_metrics.status(last_event_id))
 }
 
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_balance_events);
+    cfg.service(get_balance_events)
+        .service(get_balance_event_emitter_status);
 }
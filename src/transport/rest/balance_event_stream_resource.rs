@@ -0,0 +1,338 @@
+use std::{convert::Infallible, env, time::Duration};
+
+use actix_web::{
+    HttpRequest, HttpResponse, Responder, get,
+    http::{
+        StatusCode,
+        header::{self, ContentEncoding},
+    },
+    web::{self, Bytes},
+};
+use serde::Deserialize;
+use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+use utoipa::IntoParams;
+
+use crate::{
+    application::balance::api::balance_event_api::BalanceEventData,
+    core::domain::balance_event::EventId,
+    infrastructure::{
+        app_ioc::AppState,
+        event_sink::event_subscription::EventFilter,
+        event_stream::balance_event_follower::{FollowOptions, FollowedEvent},
+    },
+    transport::{common_response::ProblemResponse, rest::problem_response::problem},
+};
+
+const HEARTBEAT_FRAME: &str = ": heartbeat\n\n";
+
+pub struct BalanceEventSseConfig {
+    pub heartbeat: Duration,
+    /// Frames waiting for a client; a live client further behind is disconnected.
+    pub client_buffer: usize,
+}
+
+impl BalanceEventSseConfig {
+    pub fn from_env() -> Self {
+        Self {
+            heartbeat: Duration::from_millis(
+                env::var("BALANCE_EVENT_SSE_HEARTBEAT_MS")
+                    .unwrap_or("15000".to_string())
+                    .parse::<u64>()
+                    .unwrap_or(15000),
+            ),
+            client_buffer: env::var("BALANCE_EVENT_SSE_CLIENT_BUFFER")
+                .unwrap_or("1024".to_string())
+                .parse::<usize>()
+                .unwrap_or(1024),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct BalanceEventStreamQuery {
+    /// Comma separated, `balance_deposited,balance_withdrawn`.
+    pub event_types: Option<String>,
+    /// Comma separated; a transfer matches either side.
+    pub balance_ids: Option<String>,
+    /// Same as the `Last-Event-ID` header, which wins when both are sent.
+    pub last_event_id: Option<EventId>,
+}
+
+/// `id` is the event id, so a reconnecting `EventSource` resumes right after the last event
+/// it received.
+fn frame(followed_event: FollowedEvent) -> Result<Bytes, Infallible> {
+    let bytes = match followed_event {
+        FollowedEvent::Event(event) => Bytes::from(event_frame(&event)),
+        FollowedEvent::Heartbeat => Bytes::from_static(HEARTBEAT_FRAME.as_bytes()),
+    };
+    Ok(bytes)
+}
+
+fn event_frame(event: &BalanceEventData) -> String {
+    format!(
+        "id: {}\ndata: {}\n\n",
+        event.id,
+        serde_json::to_string(event).unwrap()
+    )
+}
+
+fn bad_request(message: String) -> HttpResponse {
+    problem(StatusCode::BAD_REQUEST, "invalid_query", message)
+}
+
+/// Committed events as server-sent events, then the new ones as they commit.
+#[utoipa::path(
+    tag = "balance-events",
+    params(
+        BalanceEventStreamQuery,
+        ("Last-Event-ID" = Option<EventId>, Header, description = "Id of the last event received; the stream resumes after it"),
+    ),
+    responses(
+        (
+            status = 200,
+            description = "One `id:`/`data:` frame per event, with comment heartbeats in between",
+            body = BalanceEventData,
+            content_type = "text/event-stream",
+        ),
+        (status = 400, description = "Invalid query or `Last-Event-ID`", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[get("/balance-events/stream")]
+async fn stream_balance_events(
+    ioc: web::Data<AppState>,
+    config: web::Data<BalanceEventSseConfig>,
+    request: HttpRequest,
+    query: web::Query<BalanceEventStreamQuery>,
+) -> impl Responder {
+    let filter =
+        match EventFilter::parse(query.event_types.as_deref(), query.balance_ids.as_deref()) {
+            Ok(filter) => filter,
+            Err(message) => return bad_request(message),
+        };
+    let resume_after = match request.headers().get("Last-Event-ID") {
+        Some(header_value) => match header_value
+            .to_str()
+            .ok()
+            .and_then(|last_event_id| last_event_id.trim().parse::<EventId>().ok())
+        {
+            Some(last_event_id) => Some(last_event_id),
+            None => return bad_request("Invalid Last-Event-ID".to_string()),
+        },
+        None => query.last_event_id,
+    };
+
+    let followed_events = ioc.balance_event_stream.follow(FollowOptions {
+        filter,
+        resume_after,
+        buffer: config.client_buffer,
+        heartbeat: Some(config.heartbeat),
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        // compressing would hold frames back until a block fills
+        .insert_header(ContentEncoding::Identity)
+        .streaming(ReceiverStream::new(followed_events).map(frame))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::Data::new(BalanceEventSseConfig::from_env()))
+        .service(stream_balance_events);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future, path::Path, pin::Pin};
+
+    use actix_web::{
+        App,
+        body::{BoxBody, MessageBody},
+        dev::ServiceResponse,
+        rt,
+        test::{self, TestRequest},
+    };
+    use tokio::{task, time};
+
+    use super::*;
+    use crate::{
+        application::balance::api::create_balance_api::CreateBalanceCommand,
+        infrastructure::balance::balance_config::open_db, transport::rest::problem_response,
+    };
+
+    const AN_HOUR: Duration = Duration::from_secs(3600);
+
+    async fn app_state(dir: &Path) -> AppState {
+        let ioc = AppState::with_db(open_db(dir));
+        let balance_event_stream = ioc.balance_event_stream.clone();
+        rt::spawn(async move { balance_event_stream.run().await });
+        // the stream starts after the last stored event, so it must start before any commit
+        task::yield_now().await;
+        ioc
+    }
+
+    async fn create_balances(ioc: &AppState, count: u64) {
+        for id in 1..=count {
+            ioc.balance_api_addr
+                .send(CreateBalanceCommand::new(id))
+                .await
+                .unwrap()
+                .unwrap();
+        }
+    }
+
+    async fn call(
+        ioc: &AppState,
+        config: BalanceEventSseConfig,
+        request: TestRequest,
+    ) -> ServiceResponse {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(ioc.clone()))
+                .app_data(web::Data::new(config))
+                .configure(problem_response::config)
+                .service(stream_balance_events),
+        )
+        .await;
+        test::call_service(&app, request.to_request()).await
+    }
+
+    fn quiet_config() -> BalanceEventSseConfig {
+        BalanceEventSseConfig {
+            heartbeat: AN_HOUR,
+            client_buffer: 16,
+        }
+    }
+
+    /// `None` once the stream has ended.
+    async fn next_frame(body: &mut BoxBody) -> Option<String> {
+        let chunk = time::timeout(
+            Duration::from_secs(5),
+            future::poll_fn(|cx| Pin::new(&mut *body).poll_next(cx)),
+        )
+        .await
+        .expect("no frame within 5s");
+        chunk.map(|bytes| String::from_utf8(bytes.unwrap().to_vec()).unwrap())
+    }
+
+    fn frame_id(frame: &str) -> EventId {
+        frame
+            .strip_prefix("id: ")
+            .and_then(|rest| rest.split('\n').next())
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    async fn frame_ids(response: ServiceResponse, count: usize) -> Vec<EventId> {
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = response.into_body();
+        let mut ids = vec![];
+        for _ in 0..count {
+            ids.push(frame_id(&next_frame(&mut body).await.unwrap()));
+        }
+        ids
+    }
+
+    async fn assert_invalid_query(response: ServiceResponse) {
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/problem+json"
+        );
+        let problem: serde_json::Value = test::read_body_json(response).await;
+        assert_eq!(problem["code"], "invalid_query");
+    }
+
+    #[actix_web::test]
+    async fn query_last_event_id_resumes_after_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = app_state(dir.path()).await;
+        create_balances(&ioc, 3).await;
+
+        let request = TestRequest::get().uri("/balance-events/stream?last_event_id=1");
+        let response = call(&ioc, quiet_config(), request).await;
+
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        assert_eq!(frame_ids(response, 2).await, vec![2, 3]);
+    }
+
+    #[actix_web::test]
+    async fn last_event_id_header_wins_over_the_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = app_state(dir.path()).await;
+        create_balances(&ioc, 3).await;
+
+        let request = TestRequest::get()
+            .uri("/balance-events/stream?last_event_id=2")
+            .insert_header(("Last-Event-ID", " 0 "));
+        let response = call(&ioc, quiet_config(), request).await;
+
+        assert_eq!(frame_ids(response, 3).await, vec![1, 2, 3]);
+    }
+
+    #[actix_web::test]
+    async fn invalid_last_event_id_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = app_state(dir.path()).await;
+
+        let request = TestRequest::get()
+            .uri("/balance-events/stream")
+            .insert_header(("Last-Event-ID", "abc"));
+        assert_invalid_query(call(&ioc, quiet_config(), request).await).await;
+
+        let request = TestRequest::get().uri("/balance-events/stream?last_event_id=-1");
+        assert_invalid_query(call(&ioc, quiet_config(), request).await).await;
+
+        let request = TestRequest::get().uri("/balance-events/stream?balance_ids=one");
+        assert_invalid_query(call(&ioc, quiet_config(), request).await).await;
+    }
+
+    #[actix_web::test]
+    async fn heartbeat_is_sent_while_nothing_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = app_state(dir.path()).await;
+        let config = BalanceEventSseConfig {
+            heartbeat: Duration::from_millis(50),
+            client_buffer: 16,
+        };
+
+        let response = call(
+            &ioc,
+            config,
+            TestRequest::get().uri("/balance-events/stream"),
+        )
+        .await;
+        let mut body = response.into_body();
+
+        assert_eq!(next_frame(&mut body).await.unwrap(), HEARTBEAT_FRAME);
+        assert_eq!(next_frame(&mut body).await.unwrap(), HEARTBEAT_FRAME);
+    }
+
+    #[actix_web::test]
+    async fn live_client_that_does_not_read_is_disconnected() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = app_state(dir.path()).await;
+        let config = BalanceEventSseConfig {
+            heartbeat: AN_HOUR,
+            client_buffer: 1,
+        };
+        let response = call(
+            &ioc,
+            config,
+            TestRequest::get().uri("/balance-events/stream"),
+        )
+        .await;
+
+        // the client reads nothing while five events are pushed to its one-frame buffer
+        create_balances(&ioc, 5).await;
+        time::sleep(Duration::from_millis(200)).await;
+
+        let mut body = response.into_body();
+        assert_eq!(frame_id(&next_frame(&mut body).await.unwrap()), 1);
+        assert_eq!(next_frame(&mut body).await, None);
+    }
+}
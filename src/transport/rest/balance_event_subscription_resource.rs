@@ -0,0 +1,195 @@
+use actix_web::{
+    HttpResponse, Responder, get,
+    http::StatusCode,
+    post,
+    web::{self, Json},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    core::domain::{balance::BalanceId, balance_event::EventId},
+    infrastructure::{
+        app_ioc::AppState, event_sink::event_subscription::EventSubscription,
+        scheduler::balance_event_emitter_metrics::SinkMetrics,
+    },
+    transport::{
+        common_response::{ProblemResponse, SuccessResponse},
+        rest::problem_response::problem,
+    },
+};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SubscriptionResponse {
+    pub name: String,
+    pub sink: String,
+    /// `None` when every event type is subscribed.
+    pub event_types: Option<Vec<&'static str>>,
+    /// `None` when every balance is subscribed.
+    pub balance_ids: Option<Vec<BalanceId>>,
+    pub paused: bool,
+    #[serde(flatten)]
+    pub metrics: SinkMetrics,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ResetOffsetRequest {
+    /// Last event id considered delivered; `0` replays the whole history.
+    pub offset: EventId,
+}
+
+fn subscription_response(ioc: &AppState, subscription: &EventSubscription) -> SubscriptionResponse {
+    let last_event_id = ioc.balance_event_api.last_event_id();
+    let metrics = ioc
+        .balance_event_emitter_metrics
+        .status(last_event_id)
+        .sinks
+        .remove(&subscription.name)
+        .unwrap_or_default();
+    SubscriptionResponse {
+        name: subscription.name.clone(),
+        sink: subscription.sink.clone(),
+        event_types: subscription.filter.event_types.as_ref().map(|event_types| {
+            event_types
+                .iter()
+                .map(|event_type| event_type.name())
+                .collect()
+        }),
+        balance_ids: subscription
+            .filter
+            .balance_ids
+            .as_ref()
+            .map(|balance_ids| balance_ids.iter().copied().collect()),
+        paused: subscription.is_paused(),
+        metrics,
+    }
+}
+
+fn subscription_not_found(name: &str) -> HttpResponse {
+    problem(
+        StatusCode::NOT_FOUND,
+        "subscription_not_found",
+        format!("Unknown subscription: {name}"),
+    )
+}
+
+#[utoipa::path(
+    tag = "balance-events",
+    responses(
+        (status = 200, description = "Every subscription with its emitter state", body = Vec<SubscriptionResponse>),
+    )
+)]
+#[get("/balance-events/subscriptions")]
+async fn get_subscriptions(ioc: web::Data<AppState>) -> impl Responder {
+    let subscriptions: Vec<SubscriptionResponse> = ioc
+        .balance_event_subscriptions
+        .iter()
+        .map(|subscription| subscription_response(&ioc, subscription))
+        .collect();
+    HttpResponse::Ok().json(subscriptions)
+}
+
+#[utoipa::path(
+    tag = "balance-events",
+    params(("name" = String, Path, description = "Subscription name")),
+    responses(
+        (status = 200, description = "The subscription with its emitter state", body = SubscriptionResponse),
+        (status = 404, description = "Subscription not found", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[get("/balance-events/subscriptions/{name}")]
+async fn get_subscription(ioc: web::Data<AppState>, name: web::Path<String>) -> impl Responder {
+    match ioc.balance_event_subscriptions.get(&name) {
+        Some(subscription) => HttpResponse::Ok().json(subscription_response(&ioc, subscription)),
+        None => subscription_not_found(&name),
+    }
+}
+
+#[utoipa::path(
+    tag = "balance-events",
+    params(("name" = String, Path, description = "Subscription name")),
+    responses(
+        (status = 200, description = "Subscription paused", body = SubscriptionResponse),
+        (status = 404, description = "Subscription not found", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/balance-events/subscriptions/{name}/pause")]
+async fn pause_subscription(ioc: web::Data<AppState>, name: web::Path<String>) -> impl Responder {
+    set_paused(&ioc, &name, true)
+}
+
+#[utoipa::path(
+    tag = "balance-events",
+    params(("name" = String, Path, description = "Subscription name")),
+    responses(
+        (status = 200, description = "Subscription resumed", body = SubscriptionResponse),
+        (status = 404, description = "Subscription not found", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/balance-events/subscriptions/{name}/resume")]
+async fn resume_subscription(ioc: web::Data<AppState>, name: web::Path<String>) -> impl Responder {
+    set_paused(&ioc, &name, false)
+}
+
+/// Pausing holds back the next batch; one already in flight still completes.
+fn set_paused(ioc: &AppState, name: &str, paused: bool) -> HttpResponse {
+    let Some(subscription) = ioc.balance_event_subscriptions.get(name) else {
+        return subscription_not_found(name);
+    };
+    subscription.set_paused(paused);
+    if !paused {
+        ioc.event_commit_notify.notify_one();
+    }
+    HttpResponse::Ok().json(subscription_response(ioc, subscription))
+}
+
+/// The emitter applies the reset before the next batch of the subscription, paused or not.
+#[utoipa::path(
+    tag = "balance-events",
+    params(("name" = String, Path, description = "Subscription name")),
+    request_body = ResetOffsetRequest,
+    responses(
+        (status = 202, description = "Reset requested", body = SuccessResponse<String>),
+        (status = 400, description = "Invalid body", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 404, description = "Subscription not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Offset past the last event", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/balance-events/subscriptions/{name}/offset")]
+async fn reset_subscription_offset(
+    ioc: web::Data<AppState>,
+    name: web::Path<String>,
+    request: Json<ResetOffsetRequest>,
+) -> impl Responder {
+    let Some(subscription) = ioc.balance_event_subscriptions.get(&name) else {
+        return subscription_not_found(&name);
+    };
+    let last_event_id = ioc.balance_event_api.last_event_id();
+    if request.offset > last_event_id {
+        return problem(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "offset_past_last_event",
+            format!(
+                "Offset {} is past the last event {last_event_id}",
+                request.offset
+            ),
+        );
+    }
+    subscription.request_offset_reset(request.offset);
+    ioc.event_commit_notify.notify_one();
+    HttpResponse::Accepted().json(SuccessResponse {
+        code: 202,
+        data: format!(
+            "Subscription {name} will resume after event {}",
+            request.offset
+        ),
+    })
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_subscriptions)
+        .service(get_subscription)
+        .service(pause_subscription)
+        .service(resume_subscription)
+        .service(reset_subscription_offset);
+}
@@ -0,0 +1,195 @@
+use std::env;
+
+use actix_web::{HttpResponse, Responder, get, http::StatusCode, web};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    application::balance::api::balance_list_api::{
+        BalanceCursor, BalanceListQuery, BalancePage, BalanceSort,
+    },
+    core::domain::balance::{Balance, BalanceAmount},
+    infrastructure::app_ioc::AppState,
+    transport::{common_response::ProblemResponse, rest::problem_response::problem},
+};
+
+pub struct BalanceListConfig {
+    pub max_limit: usize,
+}
+
+impl BalanceListConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_limit: env::var("BALANCE_LIST_MAX_LIMIT")
+                .unwrap_or("1000".to_string())
+                .parse::<usize>()
+                .unwrap_or(1000),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceSortRequest {
+    #[default]
+    Id,
+    AmountAsc,
+    AmountDesc,
+}
+
+/// Amounts are read as strings: the query string decoder has no 128-bit integers.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct BalanceListRequest {
+    /// `next_cursor` of the previous page, with the same sort and filters.
+    pub cursor: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub sort: BalanceSortRequest,
+    #[param(value_type = Option<u128>)]
+    pub min_amount: Option<String>,
+    #[param(value_type = Option<u128>)]
+    pub max_amount: Option<String>,
+}
+
+fn default_limit() -> usize {
+    100
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BalancePageResponse {
+    pub balances: Vec<Balance>,
+    /// Absent on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl From<BalancePage> for BalancePageResponse {
+    fn from(page: BalancePage) -> Self {
+        Self {
+            balances: page.balances,
+            next_cursor: page.next_cursor.map(|cursor| cursor.to_string()),
+        }
+    }
+}
+
+fn parse_amount(name: &str, amount: Option<&str>) -> Result<Option<BalanceAmount>, String> {
+    amount
+        .map(|amount| {
+            amount
+                .parse::<BalanceAmount>()
+                .map_err(|_| format!("Invalid {name}: {amount}"))
+        })
+        .transpose()
+}
+
+fn list_query(request: &BalanceListRequest, max_limit: usize) -> Result<BalanceListQuery, String> {
+    if request.limit == 0 || request.limit > max_limit {
+        return Err(format!(
+            "limit must be between 1 and {max_limit}, got {}",
+            request.limit
+        ));
+    }
+    Ok(BalanceListQuery {
+        sort: match request.sort {
+            BalanceSortRequest::Id => BalanceSort::IdAsc,
+            BalanceSortRequest::AmountAsc => BalanceSort::AmountAsc,
+            BalanceSortRequest::AmountDesc => BalanceSort::AmountDesc,
+        },
+        after: request
+            .cursor
+            .as_deref()
+            .map(str::parse::<BalanceCursor>)
+            .transpose()?,
+        limit: request.limit,
+        min_amount: parse_amount("min_amount", request.min_amount.as_deref())?,
+        max_amount: parse_amount("max_amount", request.max_amount.as_deref())?,
+    })
+}
+
+/// Pages through committed balances by id, or by amount with ties broken by id. Sorting by
+/// amount reads every balance, on a blocking thread rather than on the HTTP worker.
+#[utoipa::path(
+    tag = "balances",
+    params(BalanceListRequest),
+    responses(
+        (status = 200, description = "A page of balances", body = BalancePageResponse),
+        (status = 400, description = "Invalid query", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[get("/balances")]
+async fn list_balances(
+    ioc: web::Data<AppState>,
+    list_config: web::Data<BalanceListConfig>,
+    request: web::Query<BalanceListRequest>,
+) -> impl Responder {
+    let query = match list_query(&request, list_config.max_limit) {
+        Ok(query) => query,
+        Err(message) => return problem(StatusCode::BAD_REQUEST, "invalid_query", message),
+    };
+    let balance_list_api = ioc.balance_list_api.clone();
+    match web::block(move || balance_list_api.list_balances(&query)).await {
+        Ok(page) => HttpResponse::Ok().json(BalancePageResponse::from(page)),
+        Err(blocking_error) => problem(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "unknown_error",
+            blocking_error.to_string(),
+        ),
+    }
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::Data::new(BalanceListConfig::from_env()))
+        .service(list_balances);
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App,
+        dev::ServiceResponse,
+        test::{self, TestRequest},
+    };
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::infrastructure::balance::balance_config::open_db;
+
+    async fn get(uri: &str) -> ServiceResponse {
+        let dir = tempfile::tempdir().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::with_db(open_db(dir.path()))))
+                .app_data(web::Data::new(BalanceListConfig { max_limit: 5 }))
+                .service(list_balances),
+        )
+        .await;
+        test::call_service(&app, TestRequest::get().uri(uri).to_request()).await
+    }
+
+    async fn assert_invalid_query(uri: &str, detail: &str) {
+        let response = get(uri).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+        let problem: Value = test::read_body_json(response).await;
+        assert_eq!(problem["code"], "invalid_query", "{uri}");
+        assert_eq!(problem["detail"], detail, "{uri}");
+    }
+
+    #[actix_web::test]
+    async fn limit_must_be_within_the_maximum() {
+        assert_invalid_query("/balances?limit=0", "limit must be between 1 and 5, got 0").await;
+        assert_invalid_query("/balances?limit=6", "limit must be between 1 and 5, got 6").await;
+
+        let response = get("/balances?limit=5").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let page: Value = test::read_body_json(response).await;
+        assert_eq!(page, json!({"balances": []}));
+    }
+
+    #[actix_web::test]
+    async fn malformed_cursor_or_amount_is_rejected() {
+        assert_invalid_query("/balances?limit=5&cursor=10", "Invalid cursor: 10").await;
+        assert_invalid_query("/balances?limit=5&cursor=a_1", "Invalid cursor: a_1").await;
+        assert_invalid_query("/balances?limit=5&min_amount=-1", "Invalid min_amount: -1").await;
+    }
+}
@@ -1,27 +1,36 @@
 use serde::Deserialize;
+use utoipa::ToSchema;
 
 use crate::core::domain::balance::{BalanceAmount, BalanceId};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateBalanceRequest {
+    #[schema(value_type = u64)]
     pub id: BalanceId,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct DepositBalanceRequest {
+    #[schema(value_type = u64)]
     pub id: BalanceId,
+    #[schema(value_type = u128)]
     pub amount: BalanceAmount,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct WithdrawBalanceRequest {
+    #[schema(value_type = u64)]
     pub id: BalanceId,
+    #[schema(value_type = u128)]
     pub amount: BalanceAmount,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct TransferBalanceRequest {
+    #[schema(value_type = u64)]
     pub from_id: BalanceId,
+    #[schema(value_type = u64)]
     pub to_id: BalanceId,
+    #[schema(value_type = u128)]
     pub amount: BalanceAmount,
 }
@@ -6,31 +6,52 @@ use actix_web::{
 use crate::{
     application::balance::api::{
         balance_query_api::BalanceQuery, create_balance_api::CreateBalanceCommand,
-        deposit_balance_api::DepositBalanceCommand, transfer_balance_api::TransferBalanceCommand,
-        withdraw_balance_api::WithdrawBalanceCommand,
+        deposit_balance_api::DepositBalanceCommand, ledger_command_api::CommandReceipt,
+        transfer_balance_api::TransferBalanceCommand, withdraw_balance_api::WithdrawBalanceCommand,
     },
+    core::domain::balance::Balance,
     infrastructure::app_ioc::AppState,
     transport::{
-        common_response::ErrorResponse,
-        common_response::SuccessResponse,
-        rest::balance_payload::{
-            CreateBalanceRequest, DepositBalanceRequest, TransferBalanceRequest,
-            WithdrawBalanceRequest,
+        common_response::{ProblemResponse, SuccessResponse},
+        rest::{
+            balance_payload::{
+                CreateBalanceRequest, DepositBalanceRequest, TransferBalanceRequest,
+                WithdrawBalanceRequest,
+            },
+            problem_response::{balance_problem, ledger_unavailable},
         },
     },
 };
 
+#[utoipa::path(
+    tag = "balances",
+    params(BalanceQuery),
+    responses(
+        (status = 200, description = "The balance", body = Balance),
+        (status = 404, description = "Balance not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 503, description = "Ledger unavailable", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
 #[get("/balance")]
 async fn get_balance(ioc: web::Data<AppState>, query: web::Query<BalanceQuery>) -> impl Responder {
-    let result = ioc.balance_api_addr.send(query.into_inner()).await.unwrap();
+    let result = ioc.balance_api_addr.send(query.into_inner()).await;
     match result {
-        Ok(balance) => HttpResponse::Ok().json(balance),
-        Err(balance_error) => {
-            HttpResponse::BadRequest().body(format!("Error getting balance: {balance_error}"))
-        }
+        Ok(Ok(balance)) => HttpResponse::Ok().json(balance),
+        Ok(Err(balance_error)) => balance_problem(&balance_error),
+        Err(mailbox_error) => ledger_unavailable(mailbox_error),
     }
 }
 
+#[utoipa::path(
+    tag = "balances",
+    request_body = CreateBalanceRequest,
+    responses(
+        (status = 200, description = "Balance created", body = SuccessResponse<CommandReceipt>),
+        (status = 400, description = "Invalid body", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Balance already exists", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 503, description = "Ledger unavailable", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
 #[post("/balance")]
 async fn create_balance(
     ioc: web::Data<AppState>,
@@ -39,20 +60,27 @@ async fn create_balance(
     let result = ioc
         .balance_api_addr
         .send(CreateBalanceCommand::new(request.id))
-        .await
-        .unwrap();
+        .await;
     match result {
-        Ok(balance_id) => HttpResponse::Ok().json(SuccessResponse {
+        Ok(Ok(receipt)) => HttpResponse::Ok().json(SuccessResponse {
             code: 200,
-            data: format!("Balance created with id: {balance_id:?}"),
-        }),
-        Err(balance_error) => HttpResponse::BadRequest().json(ErrorResponse {
-            code: 400,
-            message: balance_error.to_string(),
+            data: receipt,
         }),
+        Ok(Err(balance_error)) => balance_problem(&balance_error),
+        Err(mailbox_error) => ledger_unavailable(mailbox_error),
     }
 }
 
+#[utoipa::path(
+    tag = "balances",
+    request_body = DepositBalanceRequest,
+    responses(
+        (status = 200, description = "Amount deposited", body = SuccessResponse<CommandReceipt>),
+        (status = 400, description = "Invalid body", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 404, description = "Balance not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 503, description = "Ledger unavailable", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
 #[post("/balance/deposit")]
 async fn deposit_balance(
     ioc: web::Data<AppState>,
@@ -61,23 +89,28 @@ async fn deposit_balance(
     let result = ioc
         .balance_api_addr
         .send(DepositBalanceCommand::new(request.id, request.amount))
-        .await
-        .unwrap();
+        .await;
     match result {
-        Ok(_) => HttpResponse::Ok().json(SuccessResponse {
+        Ok(Ok(receipt)) => HttpResponse::Ok().json(SuccessResponse {
             code: 200,
-            data: format!(
-                "Balance deposited with id: {:?}, amount: {:?}",
-                request.id, request.amount
-            ),
-        }),
-        Err(balance_error) => HttpResponse::BadRequest().json(ErrorResponse {
-            code: 400,
-            message: balance_error.to_string(),
+            data: receipt,
         }),
+        Ok(Err(balance_error)) => balance_problem(&balance_error),
+        Err(mailbox_error) => ledger_unavailable(mailbox_error),
     }
 }
 
+#[utoipa::path(
+    tag = "balances",
+    request_body = WithdrawBalanceRequest,
+    responses(
+        (status = 200, description = "Amount withdrawn", body = SuccessResponse<CommandReceipt>),
+        (status = 400, description = "Invalid body", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 404, description = "Balance not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Insufficient funds", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 503, description = "Ledger unavailable", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
 #[post("/balance/withdraw")]
 async fn withdraw_balance(
     ioc: web::Data<AppState>,
@@ -86,23 +119,28 @@ async fn withdraw_balance(
     let result = ioc
         .balance_api_addr
         .send(WithdrawBalanceCommand::new(request.id, request.amount))
-        .await
-        .unwrap();
+        .await;
     match result {
-        Ok(_) => HttpResponse::Ok().json(SuccessResponse {
+        Ok(Ok(receipt)) => HttpResponse::Ok().json(SuccessResponse {
             code: 200,
-            data: format!(
-                "Balance withdrawn with id: {:?}, amount: {:?}",
-                request.id, request.amount
-            ),
-        }),
-        Err(balance_error) => HttpResponse::BadRequest().json(ErrorResponse {
-            code: 400,
-            message: balance_error.to_string(),
+            data: receipt,
         }),
+        Ok(Err(balance_error)) => balance_problem(&balance_error),
+        Err(mailbox_error) => ledger_unavailable(mailbox_error),
     }
 }
 
+#[utoipa::path(
+    tag = "balances",
+    request_body = TransferBalanceRequest,
+    responses(
+        (status = 200, description = "Amount transferred", body = SuccessResponse<CommandReceipt>),
+        (status = 400, description = "Invalid body", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 404, description = "Balance not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Insufficient funds", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 503, description = "Ledger unavailable", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
 #[post("/balance/transfer")]
 async fn transfer_balance(
     ioc: web::Data<AppState>,
@@ -115,20 +153,14 @@ async fn transfer_balance(
             request.to_id,
             request.amount,
         ))
-        .await
-        .unwrap();
+        .await;
     match result {
-        Ok(_) => HttpResponse::Ok().json(SuccessResponse {
+        Ok(Ok(receipt)) => HttpResponse::Ok().json(SuccessResponse {
             code: 200,
-            data: format!(
-                "Balance transferred from {:?} to {:?} with amount: {:?}",
-                request.from_id, request.to_id, request.amount
-            ),
-        }),
-        Err(balance_error) => HttpResponse::BadRequest().json(ErrorResponse {
-            code: 400,
-            message: balance_error.to_string(),
+            data: receipt,
         }),
+        Ok(Err(balance_error)) => balance_problem(&balance_error),
+        Err(mailbox_error) => ledger_unavailable(mailbox_error),
     }
 }
 
@@ -139,3 +171,60 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         .service(withdraw_balance)
         .service(transfer_balance);
 }
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App,
+        test::{self, TestRequest},
+    };
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::infrastructure::balance::balance_config::open_db;
+
+    #[actix_web::test]
+    async fn commands_answer_with_their_receipt() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState::with_db(open_db(dir.path()))))
+                .configure(config),
+        )
+        .await;
+        let post = async |uri: &str, body: Value| -> Value {
+            let request = TestRequest::post().uri(uri).set_json(body).to_request();
+            test::call_and_read_body_json(&app, request).await
+        };
+
+        let created = post("/balance", json!({"id": 1})).await;
+        post("/balance", json!({"id": 2})).await;
+        let deposited = post("/balance/deposit", json!({"id": 1, "amount": 100})).await;
+        let withdrawn = post("/balance/withdraw", json!({"id": 1, "amount": 30})).await;
+        let transferred = post(
+            "/balance/transfer",
+            json!({"from_id": 1, "to_id": 2, "amount": 20}),
+        )
+        .await;
+
+        assert_eq!(
+            created,
+            json!({"code": 200, "data": {"event_id": 1, "balances": [{"id": 1, "amount": 0}]}})
+        );
+        assert_eq!(
+            deposited,
+            json!({"code": 200, "data": {"event_id": 3, "balances": [{"id": 1, "amount": 100}]}})
+        );
+        assert_eq!(
+            withdrawn,
+            json!({"code": 200, "data": {"event_id": 4, "balances": [{"id": 1, "amount": 70}]}})
+        );
+        assert_eq!(
+            transferred,
+            json!({"code": 200, "data": {
+                "event_id": 5,
+                "balances": [{"id": 1, "amount": 50}, {"id": 2, "amount": 20}],
+            }})
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use actix_web::{HttpResponse, Responder, get, http::StatusCode, post, web};
+
+use crate::{
+    infrastructure::{
+        app_ioc::AppState,
+        scheduler::job_registry::{JobStatus, RegisteredJob},
+    },
+    transport::{
+        common_response::{ProblemResponse, SuccessResponse},
+        rest::problem_response::problem,
+    },
+};
+
+fn with_job(
+    ioc: &AppState,
+    name: &str,
+    action: impl FnOnce(&RegisteredJob) -> HttpResponse,
+) -> HttpResponse {
+    match ioc.job_registry.get(name) {
+        Some(registered_job) => action(&registered_job),
+        None => problem(
+            StatusCode::NOT_FOUND,
+            "job_not_found",
+            format!("Unknown job: {name}"),
+        ),
+    }
+}
+
+/// Schedule, last run and last error of every job.
+#[utoipa::path(
+    tag = "jobs",
+    responses(
+        (status = 200, description = "Every job", body = Vec<JobStatus>),
+    )
+)]
+#[get("/jobs")]
+async fn get_jobs(ioc: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(ioc.job_registry.statuses())
+}
+
+#[utoipa::path(
+    tag = "jobs",
+    params(("name" = String, Path, description = "Job name")),
+    responses(
+        (status = 200, description = "The job", body = JobStatus),
+        (status = 404, description = "Job not found", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[get("/jobs/{name}")]
+async fn get_job(ioc: web::Data<AppState>, name: web::Path<String>) -> impl Responder {
+    with_job(&ioc, &name, |registered_job| {
+        HttpResponse::Ok().json(registered_job.status())
+    })
+}
+
+#[utoipa::path(
+    tag = "jobs",
+    params(("name" = String, Path, description = "Job name")),
+    responses(
+        (status = 200, description = "Job paused", body = JobStatus),
+        (status = 404, description = "Job not found", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/jobs/{name}/pause")]
+async fn pause_job(ioc: web::Data<AppState>, name: web::Path<String>) -> impl Responder {
+    with_job(&ioc, &name, |registered_job| {
+        registered_job.pause();
+        HttpResponse::Ok().json(registered_job.status())
+    })
+}
+
+#[utoipa::path(
+    tag = "jobs",
+    params(("name" = String, Path, description = "Job name")),
+    responses(
+        (status = 200, description = "Job resumed", body = JobStatus),
+        (status = 404, description = "Job not found", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/jobs/{name}/resume")]
+async fn resume_job(ioc: web::Data<AppState>, name: web::Path<String>) -> impl Responder {
+    with_job(&ioc, &name, |registered_job| {
+        registered_job.resume();
+        HttpResponse::Ok().json(registered_job.status())
+    })
+}
+
+/// Runs the job once, even when paused. The run happens on the job's own task.
+#[utoipa::path(
+    tag = "jobs",
+    params(("name" = String, Path, description = "Job name")),
+    responses(
+        (status = 202, description = "Run started", body = SuccessResponse<String>),
+        (status = 404, description = "Job not found", body = ProblemResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Job already running", body = ProblemResponse, content_type = "application/problem+json"),
+    )
+)]
+#[post("/jobs/{name}/trigger")]
+async fn trigger_job(ioc: web::Data<AppState>, name: web::Path<String>) -> impl Responder {
+    with_job(&ioc, &name, |registered_job| {
+        match registered_job.trigger() {
+            Ok(()) => HttpResponse::Accepted().json(SuccessResponse {
+                code: 202,
+                data: format!("Job {} triggered", registered_job.name()),
+            }),
+            Err(job_error) => problem(
+                StatusCode::CONFLICT,
+                "job_already_running",
+                job_error.to_string(),
+            ),
+        }
+    })
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_jobs)
+        .service(get_job)
+        .service(pause_job)
+        .service(resume_job)
+        .service(trigger_job);
+}
@@ -1,3 +1,10 @@
+pub mod balance_batch_resource;
 pub mod balance_event_resource;
+pub mod balance_event_stream_resource;
+pub mod balance_event_subscription_resource;
+pub mod balance_list_resource;
 pub mod balance_payload;
 pub mod balance_resource;
+pub mod job_resource;
+pub mod openapi_resource;
+pub mod problem_response;
@@ -0,0 +1,118 @@
+use actix_web::web;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::transport::{
+    rest::{
+        balance_batch_resource, balance_event_resource, balance_event_stream_resource,
+        balance_event_subscription_resource, balance_list_resource, balance_resource, job_resource,
+    },
+    ws::balance_ws,
+};
+
+/// Built from the `#[utoipa::path]` of each handler and the schemas of their payloads, so a
+/// route or a payload that changes changes the document with it.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Actor bank", description = "Balances and their event log"),
+    paths(
+        balance_resource::get_balance,
+        balance_resource::create_balance,
+        balance_resource::deposit_balance,
+        balance_resource::withdraw_balance,
+        balance_resource::transfer_balance,
+        balance_batch_resource::execute_batch,
+        balance_list_resource::list_balances,
+        balance_event_resource::get_balance_events,
+        balance_event_resource::get_balance_event_emitter_status,
+        balance_event_stream_resource::stream_balance_events,
+        balance_event_subscription_resource::get_subscriptions,
+        balance_event_subscription_resource::get_subscription,
+        balance_event_subscription_resource::pause_subscription,
+        balance_event_subscription_resource::resume_subscription,
+        balance_event_subscription_resource::reset_subscription_offset,
+        job_resource::get_jobs,
+        job_resource::get_job,
+        job_resource::pause_job,
+        job_resource::resume_job,
+        job_resource::trigger_job,
+        balance_ws::balance_ws,
+    ),
+    tags(
+        (name = "balances", description = "Balance commands and queries"),
+        (name = "balance-events", description = "The committed event log, its live stream and its subscriptions"),
+        (name = "jobs", description = "Scheduled jobs"),
+    )
+)]
+pub struct BalanceApiDoc;
+
+/// `/openapi.json`, and the Swagger UI at `/docs/`, served from assets bundled in the binary.
+pub fn config(cfg: &mut web::ServiceConfig) {
+    let mut openapi = BalanceApiDoc::openapi();
+    // taken from the manifest, which declares no license
+    openapi.info.license = None;
+    cfg.service(SwaggerUi::new("/docs/{_:.*}").url("/openapi.json", openapi));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, fs, path::Path};
+
+    use super::*;
+
+    const ROUTE_METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+    /// `(method, path)` of every `#[get("…")]`-style route attribute under `dir`.
+    fn declared_routes(dir: &Path, routes: &mut BTreeSet<(String, String)>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                declared_routes(&path, routes);
+                continue;
+            }
+            for line in fs::read_to_string(&path).unwrap().lines() {
+                for method in ROUTE_METHODS {
+                    if let Some(route) = line
+                        .trim()
+                        .strip_prefix(&format!("#[{method}(\""))
+                        .and_then(|rest| rest.strip_suffix("\")]"))
+                    {
+                        routes.insert((method.to_string(), route.to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    fn documented_routes() -> BTreeSet<(String, String)> {
+        let openapi = BalanceApiDoc::openapi();
+        let mut routes = BTreeSet::new();
+        for (route, item) in openapi.paths.paths {
+            let operations = [
+                ("get", item.get),
+                ("post", item.post),
+                ("put", item.put),
+                ("patch", item.patch),
+                ("delete", item.delete),
+            ];
+            for (method, operation) in operations {
+                if operation.is_some() {
+                    routes.insert((method.to_string(), route.clone()));
+                }
+            }
+        }
+        routes
+    }
+
+    #[test]
+    fn document_lists_every_route_and_nothing_else() {
+        let mut declared = BTreeSet::new();
+        declared_routes(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("src/transport"),
+            &mut declared,
+        );
+
+        assert!(!declared.is_empty());
+        assert_eq!(documented_routes(), declared);
+    }
+}
@@ -0,0 +1,253 @@
+use actix::MailboxError;
+use actix_web::{
+    HttpRequest, HttpResponse,
+    error::{self, InternalError, JsonPayloadError},
+    http::StatusCode,
+    web,
+};
+
+use crate::{
+    core::domain::balance_error::BalanceError, transport::common_response::ProblemResponse,
+};
+
+pub fn problem(status: StatusCode, code: &'static str, detail: impl Into<String>) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type("application/problem+json")
+        .json(ProblemResponse {
+            problem_type: "about:blank",
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail: detail.into(),
+            code,
+        })
+}
+
+pub fn balance_error_status(balance_error: &BalanceError) -> StatusCode {
+    match balance_error {
+        BalanceError::BalanceAlreadyExists(_) => StatusCode::CONFLICT,
+        BalanceError::BalanceNotFound(_) => StatusCode::NOT_FOUND,
+        BalanceError::InsufficientFunds { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        BalanceError::UnknownError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+pub fn balance_problem(balance_error: &BalanceError) -> HttpResponse {
+    problem(
+        balance_error_status(balance_error),
+        balance_error.code(),
+        balance_error.to_string(),
+    )
+}
+
+/// The ledger actor is gone or its mailbox is full; the request may be retried.
+pub fn ledger_unavailable(mailbox_error: MailboxError) -> HttpResponse {
+    problem(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "ledger_unavailable",
+        format!("Ledger unavailable: {mailbox_error}"),
+    )
+}
+
+fn json_error(json_error: JsonPayloadError, _: &HttpRequest) -> error::Error {
+    let response = match &json_error {
+        JsonPayloadError::OverflowKnownLength { .. } | JsonPayloadError::Overflow { .. } => {
+            problem(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "payload_too_large",
+                json_error.to_string(),
+            )
+        }
+        JsonPayloadError::ContentType => problem(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "unsupported_media_type",
+            "Expected an application/json body",
+        ),
+        _ => problem(
+            StatusCode::BAD_REQUEST,
+            "invalid_body",
+            json_error.to_string(),
+        ),
+    };
+    InternalError::from_response(json_error, response).into()
+}
+
+fn query_error(query_error: error::QueryPayloadError, _: &HttpRequest) -> error::Error {
+    let response = problem(
+        StatusCode::BAD_REQUEST,
+        "invalid_query",
+        query_error.to_string(),
+    );
+    InternalError::from_response(query_error, response).into()
+}
+
+fn path_error(path_error: error::PathError, _: &HttpRequest) -> error::Error {
+    let response = problem(
+        StatusCode::BAD_REQUEST,
+        "invalid_path",
+        path_error.to_string(),
+    );
+    InternalError::from_response(path_error, response).into()
+}
+
+pub async fn route_not_found(request: HttpRequest) -> HttpResponse {
+    problem(
+        StatusCode::NOT_FOUND,
+        "route_not_found",
+        format!("No route for {} {}", request.method(), request.path()),
+    )
+}
+
+/// Extractor failures answer with a problem too, instead of actix's plain text.
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::JsonConfig::default().error_handler(json_error))
+        .app_data(web::QueryConfig::default().error_handler(query_error))
+        .app_data(web::PathConfig::default().error_handler(path_error));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::{
+        App,
+        dev::ServiceResponse,
+        http::header,
+        test::{self, TestRequest},
+    };
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{
+        infrastructure::{
+            app_ioc::AppState,
+            balance::{balance_actor::tests::stopped_balance_api, balance_config::open_db},
+        },
+        transport::rest::balance_resource,
+    };
+
+    async fn call(ioc: AppState, request: TestRequest) -> ServiceResponse {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(ioc))
+                .configure(config)
+                .configure(balance_resource::config)
+                .default_service(web::to(route_not_found)),
+        )
+        .await;
+        test::call_service(&app, request.to_request()).await
+    }
+
+    /// Checks the status and media type, and returns the problem.
+    async fn problem_of(response: ServiceResponse, status: StatusCode) -> Value {
+        assert_eq!(response.status(), status);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/problem+json"
+        );
+        let problem: Value = test::read_body_json(response).await;
+        assert_eq!(problem["type"], "about:blank");
+        assert_eq!(problem["status"], status.as_u16());
+        assert_eq!(problem["title"], status.canonical_reason().unwrap());
+        problem
+    }
+
+    fn post(uri: &str, body: Value) -> TestRequest {
+        TestRequest::post().uri(uri).set_json(body)
+    }
+
+    /// A ledger holding balance 1, empty.
+    async fn app_state(dir: &std::path::Path) -> AppState {
+        let ioc = AppState::with_db(open_db(dir));
+        call(ioc.clone(), post("/balance", json!({"id": 1}))).await;
+        ioc
+    }
+
+    #[actix_web::test]
+    async fn ledger_errors_are_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = app_state(dir.path()).await;
+
+        let not_found = call(ioc.clone(), TestRequest::get().uri("/balance?id=9")).await;
+        let problem = problem_of(not_found, StatusCode::NOT_FOUND).await;
+        assert_eq!(problem["code"], "balance_not_found");
+        assert_eq!(problem["detail"], "Balance with id 9 not found");
+
+        let conflict = call(ioc.clone(), post("/balance", json!({"id": 1}))).await;
+        let problem = problem_of(conflict, StatusCode::CONFLICT).await;
+        assert_eq!(problem["code"], "balance_already_exists");
+
+        let overdraft = call(
+            ioc.clone(),
+            post("/balance/withdraw", json!({"id": 1, "amount": 5})),
+        )
+        .await;
+        let problem = problem_of(overdraft, StatusCode::UNPROCESSABLE_ENTITY).await;
+        assert_eq!(problem["code"], "insufficient_funds");
+    }
+
+    #[actix_web::test]
+    async fn unreadable_bodies_are_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = app_state(dir.path()).await;
+
+        let bad_json = TestRequest::post()
+            .uri("/balance/deposit")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload(r#"{"id": 1, "amount": "#);
+        let problem = problem_of(call(ioc.clone(), bad_json).await, StatusCode::BAD_REQUEST).await;
+        assert_eq!(problem["code"], "invalid_body");
+
+        let too_large = TestRequest::post()
+            .uri("/balance/deposit")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload(format!(
+                r#"{{"id": 1, "amount": 1, "memo": "{}"}}"#,
+                "a".repeat(3 << 20)
+            ));
+        let problem = problem_of(
+            call(ioc.clone(), too_large).await,
+            StatusCode::PAYLOAD_TOO_LARGE,
+        )
+        .await;
+        assert_eq!(problem["code"], "payload_too_large");
+
+        let not_json = TestRequest::post()
+            .uri("/balance/deposit")
+            .insert_header((header::CONTENT_TYPE, "text/plain"))
+            .set_payload(r#"{"id": 1, "amount": 1}"#);
+        let problem = problem_of(
+            call(ioc.clone(), not_json).await,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        )
+        .await;
+        assert_eq!(problem["code"], "unsupported_media_type");
+
+        let bad_query = TestRequest::get().uri("/balance?id=one");
+        let problem = problem_of(call(ioc, bad_query).await, StatusCode::BAD_REQUEST).await;
+        assert_eq!(problem["code"], "invalid_query");
+    }
+
+    #[actix_web::test]
+    async fn stopped_ledger_is_unavailable() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ioc = AppState::with_db(open_db(dir.path().join("running")));
+        ioc.balance_api_addr = Arc::new(stopped_balance_api(&dir.path().join("stopped")));
+
+        let response = call(ioc, post("/balance", json!({"id": 1}))).await;
+
+        let problem = problem_of(response, StatusCode::SERVICE_UNAVAILABLE).await;
+        assert_eq!(problem["code"], "ledger_unavailable");
+    }
+
+    #[actix_web::test]
+    async fn unknown_route_is_a_problem() {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = AppState::with_db(open_db(dir.path()));
+
+        let response = call(ioc, TestRequest::delete().uri("/balances/1")).await;
+
+        let problem = problem_of(response, StatusCode::NOT_FOUND).await;
+        assert_eq!(problem["code"], "route_not_found");
+        assert_eq!(problem["detail"], "No route for DELETE /balances/1");
+    }
+}
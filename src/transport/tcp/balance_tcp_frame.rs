@@ -0,0 +1,191 @@
+use std::io;
+
+use bincode::{Decode, Encode, config};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{
+    application::balance::api::ledger_command_api::{CommandId, CommandResult},
+    core::domain::balance::{BalanceAmount, BalanceId},
+};
+
+/// Chosen by the client, unique among its requests in flight on the connection.
+pub type RequestId = u64;
+
+/// Every frame is a big-endian `u32` length followed by that many bytes of bincode.
+pub const FRAME_HEADER_SIZE: usize = size_of::<u32>();
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum TcpCommand {
+    Create {
+        id: BalanceId,
+    },
+    Deposit {
+        id: BalanceId,
+        amount: BalanceAmount,
+    },
+    Withdraw {
+        id: BalanceId,
+        amount: BalanceAmount,
+    },
+    Transfer {
+        from_id: BalanceId,
+        to_id: BalanceId,
+        amount: BalanceAmount,
+    },
+    GetBalance {
+        id: BalanceId,
+    },
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct TcpRequest {
+    pub request_id: RequestId,
+    /// Makes a command idempotent, as on the command topic; ignored by `GetBalance`.
+    pub command_id: Option<CommandId>,
+    pub command: TcpCommand,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum TcpReply {
+    Command {
+        result: CommandResult,
+        replayed: bool,
+    },
+    Balance {
+        id: BalanceId,
+        amount: BalanceAmount,
+    },
+    /// Nothing was stored: the query failed or the request did not reach the ledger.
+    Failed { message: String },
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct TcpResponse {
+    pub request_id: RequestId,
+    pub reply: TcpReply,
+}
+
+pub fn encode_frame<T: Encode>(message: &T) -> Vec<u8> {
+    let mut frame = vec![0; FRAME_HEADER_SIZE];
+    bincode::encode_into_std_write(message, &mut frame, config::standard()).unwrap();
+    let length = (frame.len() - FRAME_HEADER_SIZE) as u32;
+    frame[..FRAME_HEADER_SIZE].copy_from_slice(&length.to_be_bytes());
+    frame
+}
+
+/// `None` when the peer closed the connection between two frames.
+pub async fn read_frame<T: Decode<()>, R: AsyncRead + Unpin>(
+    reader: &mut R,
+    max_frame_bytes: usize,
+) -> io::Result<Option<T>> {
+    let mut header = [0; FRAME_HEADER_SIZE];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(io_error) if io_error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(io_error) => return Err(io_error),
+    }
+    let length = u32::from_be_bytes(header) as usize;
+    if length > max_frame_bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Frame of {length} bytes exceeds {max_frame_bytes}"),
+        ));
+    }
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload).await?;
+    bincode::decode_from_slice(&payload, config::standard())
+        .map(|(message, _)| Some(message))
+        .map_err(|decode_error| io::Error::new(io::ErrorKind::InvalidData, decode_error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_FRAME_BYTES: usize = 1024;
+
+    fn deposit(request_id: RequestId) -> TcpRequest {
+        TcpRequest {
+            request_id,
+            command_id: Some(format!("deposit-{request_id}")),
+            command: TcpCommand::Deposit {
+                id: 7,
+                amount: u128::MAX,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn frames_read_back_as_written() {
+        let mut bytes = encode_frame(&deposit(1));
+        bytes.extend(encode_frame(&deposit(2)));
+        let mut reader = bytes.as_slice();
+
+        for request_id in [1, 2] {
+            let request: TcpRequest = read_frame(&mut reader, MAX_FRAME_BYTES)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(request.request_id, request_id);
+            assert_eq!(request.command_id, Some(format!("deposit-{request_id}")));
+            assert!(matches!(
+                request.command,
+                TcpCommand::Deposit {
+                    id: 7,
+                    amount: u128::MAX
+                }
+            ));
+        }
+        let end = read_frame::<TcpRequest, _>(&mut reader, MAX_FRAME_BYTES).await;
+        assert!(end.unwrap().is_none());
+    }
+
+    #[test]
+    fn length_prefix_is_big_endian() {
+        let frame = encode_frame(&deposit(1));
+
+        let length = u32::from_be_bytes(frame[..FRAME_HEADER_SIZE].try_into().unwrap());
+
+        assert_eq!(length as usize, frame.len() - FRAME_HEADER_SIZE);
+    }
+
+    #[tokio::test]
+    async fn oversized_frame_is_refused_before_its_payload_is_read() {
+        let mut bytes = ((MAX_FRAME_BYTES + 1) as u32).to_be_bytes().to_vec();
+        bytes.extend(vec![0; 16]);
+        let mut reader = bytes.as_slice();
+
+        let io_error = read_frame::<TcpRequest, _>(&mut reader, MAX_FRAME_BYTES)
+            .await
+            .unwrap_err();
+
+        assert_eq!(io_error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(io_error.to_string(), "Frame of 1025 bytes exceeds 1024");
+        assert_eq!(reader.len(), 16);
+    }
+
+    #[tokio::test]
+    async fn truncated_frame_is_an_error() {
+        let frame = encode_frame(&deposit(1));
+        let mut reader = &frame[..frame.len() - 1];
+
+        let io_error = read_frame::<TcpRequest, _>(&mut reader, MAX_FRAME_BYTES)
+            .await
+            .unwrap_err();
+
+        assert_eq!(io_error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[tokio::test]
+    async fn undecodable_payload_is_invalid_data() {
+        let mut bytes = 3u32.to_be_bytes().to_vec();
+        bytes.extend([0xff, 0xff, 0xff]);
+        let mut reader = bytes.as_slice();
+
+        let io_error = read_frame::<TcpRequest, _>(&mut reader, MAX_FRAME_BYTES)
+            .await
+            .unwrap_err();
+
+        assert_eq!(io_error.kind(), io::ErrorKind::InvalidData);
+    }
+}
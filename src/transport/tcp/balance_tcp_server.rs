@@ -0,0 +1,345 @@
+use std::{env, future::Future, io, pin::Pin, sync::Arc};
+
+use actix::{Addr, MailboxError};
+use log::{error, info, warn};
+use tokio::{
+    io::{AsyncWriteExt, BufWriter},
+    net::{
+        TcpListener, TcpStream,
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+    },
+    sync::{OwnedSemaphorePermit, Semaphore, mpsc},
+};
+
+use crate::{
+    application::balance::api::{
+        balance_api::BalanceApi,
+        balance_query_api::BalanceQuery,
+        create_balance_api::CreateBalanceCommand,
+        deposit_balance_api::DepositBalanceCommand,
+        ledger_command_api::{IdempotentLedgerCommand, LedgerCommand, LedgerCommandResponse},
+        transfer_balance_api::TransferBalanceCommand,
+        withdraw_balance_api::WithdrawBalanceCommand,
+    },
+    transport::tcp::balance_tcp_frame::{
+        TcpCommand, TcpReply, TcpRequest, TcpResponse, encode_frame, read_frame,
+    },
+};
+
+pub struct BalanceTcpServerConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub max_frame_bytes: usize,
+    /// Requests of one connection waiting for the ledger; reading pauses beyond it.
+    pub max_in_flight: usize,
+}
+
+impl BalanceTcpServerConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: env::var("TCP_ENABLED")
+                .unwrap_or("false".to_string())
+                .parse::<bool>()
+                .unwrap_or(false),
+            host: env::var("TCP_HOST")
+                .or_else(|_| env::var("HOST"))
+                .unwrap_or("0.0.0.0".to_string()),
+            port: env::var("TCP_PORT")
+                .unwrap_or("7000".to_string())
+                .parse::<u16>()
+                .unwrap_or(7000),
+            max_frame_bytes: env::var("TCP_MAX_FRAME_BYTES")
+                .unwrap_or("65536".to_string())
+                .parse::<usize>()
+                .unwrap_or(65536),
+            max_in_flight: env::var("TCP_MAX_IN_FLIGHT")
+                .unwrap_or("1024".to_string())
+                .parse::<usize>()
+                .unwrap_or(1024),
+        }
+    }
+}
+
+type PendingReply = Pin<Box<dyn Future<Output = TcpReply> + Send>>;
+
+/// `Err` for the query, which is not a ledger command.
+fn ledger_command(command: TcpCommand) -> Result<LedgerCommand, BalanceQuery> {
+    let ledger_command = match command {
+        TcpCommand::Create { id } => LedgerCommand::Create(CreateBalanceCommand::new(id)),
+        TcpCommand::Deposit { id, amount } => {
+            LedgerCommand::Deposit(DepositBalanceCommand::new(id, amount))
+        }
+        TcpCommand::Withdraw { id, amount } => {
+            LedgerCommand::Withdraw(WithdrawBalanceCommand::new(id, amount))
+        }
+        TcpCommand::Transfer {
+            from_id,
+            to_id,
+            amount,
+        } => LedgerCommand::Transfer(TransferBalanceCommand::new(from_id, to_id, amount)),
+        TcpCommand::GetBalance { id } => return Err(BalanceQuery { id }),
+    };
+    Ok(ledger_command)
+}
+
+/// One client connection: requests are read and sent to the ledger as they arrive, and each
+/// reply is written as soon as the ledger answers, so replies may overtake each other.
+struct BalanceTcpConnection {
+    balance_api_addr: Arc<Addr<BalanceApi>>,
+    replies: mpsc::Sender<Vec<u8>>,
+    in_flight: Arc<Semaphore>,
+}
+
+impl BalanceTcpConnection {
+    async fn read_requests(&self, mut reader: OwnedReadHalf, max_frame_bytes: usize) {
+        loop {
+            let request = match read_frame::<TcpRequest, _>(&mut reader, max_frame_bytes).await {
+                Ok(Some(request)) => request,
+                Ok(None) => return,
+                Err(io_error) => {
+                    // the stream cannot be resynchronized after a bad frame
+                    warn!("TCP client sent an invalid frame, disconnected: {io_error}");
+                    return;
+                }
+            };
+            let Ok(permit) = self.in_flight.clone().acquire_owned().await else {
+                return;
+            };
+            self.dispatch(request, permit);
+        }
+    }
+
+    /// Sent to the ledger before returning, so it sees the commands of a connection in the
+    /// order they were read.
+    fn dispatch(&self, request: TcpRequest, permit: OwnedSemaphorePermit) {
+        let request_id = request.request_id;
+        let replies = self.replies.clone();
+        let reply: PendingReply = match (ledger_command(request.command), request.command_id) {
+            (Ok(command), Some(command_id)) => {
+                let pending = self.balance_api_addr.send(IdempotentLedgerCommand {
+                    command_id,
+                    command,
+                });
+                Box::pin(async move { command_reply(pending.await) })
+            }
+            (Ok(command), None) => {
+                let pending = self.balance_api_addr.send(command);
+                Box::pin(async move { command_reply(pending.await) })
+            }
+            (Err(query), _) => {
+                let pending = self.balance_api_addr.send(query);
+                Box::pin(async move {
+                    match pending.await {
+                        Ok(Ok(balance)) => TcpReply::Balance {
+                            id: balance.id(),
+                            amount: balance.amount(),
+                        },
+                        Ok(Err(balance_error)) => TcpReply::Failed {
+                            message: balance_error.to_string(),
+                        },
+                        Err(mailbox_error) => ledger_unavailable(mailbox_error),
+                    }
+                })
+            }
+        };
+        tokio::spawn(async move {
+            let frame = encode_frame(&TcpResponse {
+                request_id,
+                reply: reply.await,
+            });
+            let _ = replies.send(frame).await;
+            drop(permit);
+        });
+    }
+}
+
+fn command_reply(response: Result<LedgerCommandResponse, MailboxError>) -> TcpReply {
+    match response {
+        Ok(Ok(outcome)) => TcpReply::Command {
+            result: outcome.result,
+            replayed: outcome.replayed,
+        },
+        Ok(Err(balance_error)) => TcpReply::Failed {
+            message: balance_error.to_string(),
+        },
+        Err(mailbox_error) => ledger_unavailable(mailbox_error),
+    }
+}
+
+fn ledger_unavailable(mailbox_error: MailboxError) -> TcpReply {
+    TcpReply::Failed {
+        message: format!("Ledger unavailable: {mailbox_error}"),
+    }
+}
+
+/// Writes replies in the order they are ready, flushing once no other reply is waiting.
+async fn write_replies(writer: OwnedWriteHalf, mut replies: mpsc::Receiver<Vec<u8>>) {
+    let mut writer = BufWriter::new(writer);
+    while let Some(frame) = replies.recv().await {
+        if writer.write_all(&frame).await.is_err() {
+            return;
+        }
+        if replies.is_empty() && writer.flush().await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    balance_api_addr: Arc<Addr<BalanceApi>>,
+    max_frame_bytes: usize,
+    max_in_flight: usize,
+) {
+    // replies are small and latency matters more than packet count
+    let _ = stream.set_nodelay(true);
+    let (reader, writer) = stream.into_split();
+    let (replies, reply_receiver) = mpsc::channel(max_in_flight);
+    let writer_task = tokio::spawn(write_replies(writer, reply_receiver));
+    let connection = BalanceTcpConnection {
+        balance_api_addr,
+        replies,
+        in_flight: Arc::new(Semaphore::new(max_in_flight)),
+    };
+    connection.read_requests(reader, max_frame_bytes).await;
+    // pending replies still go out before the writer stops
+    drop(connection);
+    let _ = writer_task.await;
+}
+
+async fn listen(
+    config: &BalanceTcpServerConfig,
+    balance_api_addr: Arc<Addr<BalanceApi>>,
+) -> io::Result<()> {
+    let listener = TcpListener::bind((config.host.as_str(), config.port)).await?;
+    info!("TCP server listening on {}", listener.local_addr()?);
+    loop {
+        let (stream, peer_address) = listener.accept().await?;
+        info!("TCP client connected from {peer_address}");
+        tokio::spawn(serve_connection(
+            stream,
+            balance_api_addr.clone(),
+            config.max_frame_bytes,
+            config.max_in_flight,
+        ));
+    }
+}
+
+/// Serves the binary protocol when `TCP_ENABLED`, on its own port next to the HTTP server.
+pub async fn serve_tcp(balance_api_addr: Arc<Addr<BalanceApi>>) {
+    let config = BalanceTcpServerConfig::from_env();
+    if !config.enabled {
+        info!("TCP server disabled");
+        return;
+    }
+    if let Err(io_error) = listen(&config, balance_api_addr).await {
+        error!("TCP server error: {io_error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+    use crate::{
+        application::balance::api::ledger_command_api::CommandResult,
+        infrastructure::{app_ioc::AppState, balance::balance_config::open_db},
+        transport::tcp::balance_tcp_frame::RequestId,
+    };
+
+    const MAX_FRAME_BYTES: usize = 1024;
+
+    /// A client connected to a server for one connection, on a ledger under `dir`.
+    async fn connect(dir: &Path) -> TcpStream {
+        let balance_api_addr = AppState::with_db(open_db(dir)).balance_api_addr;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve_connection(stream, balance_api_addr, MAX_FRAME_BYTES, 16).await;
+        });
+        TcpStream::connect(address).await.unwrap()
+    }
+
+    fn request(request_id: RequestId, command_id: Option<&str>, command: TcpCommand) -> TcpRequest {
+        TcpRequest {
+            request_id,
+            command_id: command_id.map(str::to_string),
+            command,
+        }
+    }
+
+    #[actix_web::test]
+    async fn pipelined_requests_are_answered_by_request_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut client = connect(dir.path()).await;
+        let requests = [
+            request(1, None, TcpCommand::Create { id: 1 }),
+            request(2, Some("d-1"), TcpCommand::Deposit { id: 1, amount: 100 }),
+            request(3, Some("d-1"), TcpCommand::Deposit { id: 1, amount: 100 }),
+            request(4, None, TcpCommand::GetBalance { id: 1 }),
+            request(5, None, TcpCommand::GetBalance { id: 9 }),
+        ];
+        // every request is written before the first reply is read
+        let bytes: Vec<u8> = requests.iter().flat_map(encode_frame).collect();
+        client.write_all(&bytes).await.unwrap();
+
+        let mut replies = HashMap::new();
+        for _ in 0..requests.len() {
+            let response: TcpResponse = read_frame(&mut client, MAX_FRAME_BYTES)
+                .await
+                .unwrap()
+                .unwrap();
+            replies.insert(response.request_id, response.reply);
+        }
+
+        assert!(matches!(
+            &replies[&1],
+            TcpReply::Command {
+                result: CommandResult::Applied { .. },
+                replayed: false
+            }
+        ));
+        let TcpReply::Command {
+            result: deposit_result,
+            replayed: false,
+        } = &replies[&2]
+        else {
+            panic!("deposit not applied: {:?}", replies[&2]);
+        };
+        let TcpReply::Command {
+            result: replayed_result,
+            replayed: true,
+        } = &replies[&3]
+        else {
+            panic!("deposit not replayed: {:?}", replies[&3]);
+        };
+        assert_eq!(replayed_result, deposit_result);
+        assert!(matches!(
+            replies[&4],
+            TcpReply::Balance { id: 1, amount: 100 }
+        ));
+        let TcpReply::Failed { message } = &replies[&5] else {
+            panic!("unknown balance found: {:?}", replies[&5]);
+        };
+        assert_eq!(message, "Balance with id 9 not found");
+    }
+
+    #[actix_web::test]
+    async fn oversized_frame_closes_the_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut client = connect(dir.path()).await;
+
+        client
+            .write_all(&((MAX_FRAME_BYTES + 1) as u32).to_be_bytes())
+            .await
+            .unwrap();
+
+        let mut buffer = [0; 1];
+        assert_eq!(client.read(&mut buffer).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod balance_tcp_frame;
+pub mod balance_tcp_server;
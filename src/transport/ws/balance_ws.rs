@@ -0,0 +1,470 @@
+use std::{collections::BTreeSet, future::Future, pin::Pin, sync::Arc};
+
+use actix::MailboxError;
+use actix_web::{HttpRequest, HttpResponse, get, rt, web};
+use actix_ws::{Message, MessageStream, Session};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{
+    application::balance::api::{
+        balance_event_api::BalanceEventData,
+        ledger_command_api::{IdempotentLedgerCommand, LedgerCommandResponse},
+    },
+    core::domain::balance::BalanceId,
+    infrastructure::{app_ioc::AppState, event_sink::event_subscription::EventFilter},
+    transport::ws::balance_ws_message::{
+        ClientMessage, CommandMessage, CommandResultMessage, ErrorMessage, ServerMessage,
+        SubscriptionRequest, SubscriptionState,
+    },
+};
+
+type PendingCommand = Pin<Box<dyn Future<Output = Result<LedgerCommandResponse, MailboxError>>>>;
+
+/// One WebSocket client: its commands go to the ledger actor and are answered on the socket,
+/// and the committed events it subscribed to are pushed to it as they are broadcast.
+struct BalanceWsConnection {
+    ioc: Arc<AppState>,
+    session: Session,
+    all: bool,
+    balance_ids: BTreeSet<BalanceId>,
+    filter: EventFilter,
+    /// Only held while something is subscribed, so an idle connection does not lag.
+    events: Option<broadcast::Receiver<Arc<BalanceEventData>>>,
+}
+
+impl BalanceWsConnection {
+    fn new(ioc: Arc<AppState>, session: Session) -> Self {
+        Self {
+            ioc,
+            session,
+            all: false,
+            balance_ids: BTreeSet::new(),
+            filter: EventFilter::default(),
+            events: None,
+        }
+    }
+
+    async fn run(mut self, mut messages: MessageStream) {
+        loop {
+            let open = tokio::select! {
+                message = messages.recv() => match message {
+                    Some(Ok(message)) => self.on_message(message).await,
+                    // closed by the client or unreadable
+                    _ => false,
+                },
+                event = next_event(&mut self.events) => self.on_event(event).await,
+            };
+            if !open {
+                break;
+            }
+        }
+        let _ = self.session.close(None).await;
+    }
+
+    /// Whether the connection stays open.
+    async fn on_message(&mut self, message: Message) -> bool {
+        match message {
+            Message::Text(text) => self.on_text(&text).await,
+            Message::Ping(bytes) => self.session.pong(&bytes).await.is_ok(),
+            Message::Close(_) => false,
+            Message::Binary(_) => {
+                self.send_error(None, "Binary frames are not supported")
+                    .await
+            }
+            _ => true,
+        }
+    }
+
+    async fn on_text(&mut self, text: &str) -> bool {
+        match serde_json::from_str::<ClientMessage>(text) {
+            Ok(ClientMessage::Command(command)) => {
+                self.submit(command);
+                true
+            }
+            Ok(ClientMessage::Subscribe(request)) => self.subscribe(request).await,
+            Ok(ClientMessage::Unsubscribe(request)) => self.unsubscribe(request).await,
+            Err(json_error) => {
+                self.send_error(None, &format!("Invalid message: {json_error}"))
+                    .await
+            }
+        }
+    }
+
+    /// Sent to the ledger right away, so commands reach it in the order the client sent them;
+    /// each result is written back once the ledger answers, without holding up the socket.
+    fn submit(&self, command: CommandMessage) {
+        let ledger_command = command.request.ledger_command();
+        let pending: PendingCommand = match &command.command_id {
+            Some(command_id) => Box::pin(self.ioc.balance_api_addr.send(IdempotentLedgerCommand {
+                command_id: command_id.clone(),
+                command: ledger_command,
+            })),
+            None => Box::pin(self.ioc.balance_api_addr.send(ledger_command)),
+        };
+        let mut session = self.session.clone();
+        rt::spawn(async move {
+            let correlation_id = Some(command.correlation_id.as_str());
+            let reply = match pending.await {
+                Ok(Ok(outcome)) => ServerMessage::CommandResult(CommandResultMessage {
+                    correlation_id: &command.correlation_id,
+                    command_id: command.command_id.as_deref(),
+                    result: &outcome.result,
+                    replayed: outcome.replayed,
+                })
+                .to_json(),
+                // nothing was stored, the command can be sent again
+                Ok(Err(balance_error)) => ServerMessage::Error(ErrorMessage {
+                    correlation_id,
+                    message: balance_error.to_string(),
+                })
+                .to_json(),
+                Err(mailbox_error) => ServerMessage::Error(ErrorMessage {
+                    correlation_id,
+                    message: format!("Ledger unavailable: {mailbox_error}"),
+                })
+                .to_json(),
+            };
+            let _ = session.text(reply).await;
+        });
+    }
+
+    async fn subscribe(&mut self, request: SubscriptionRequest) -> bool {
+        match request.balance_ids {
+            Some(balance_ids) => self.balance_ids.extend(balance_ids),
+            None => self.all = true,
+        }
+        self.subscription_changed().await
+    }
+
+    async fn unsubscribe(&mut self, request: SubscriptionRequest) -> bool {
+        match request.balance_ids {
+            Some(balance_ids) => {
+                for balance_id in balance_ids {
+                    self.balance_ids.remove(&balance_id);
+                }
+            }
+            None => {
+                self.all = false;
+                self.balance_ids.clear();
+            }
+        }
+        self.subscription_changed().await
+    }
+
+    /// Events committed before a subscription are not replayed.
+    async fn subscription_changed(&mut self) -> bool {
+        self.filter = EventFilter {
+            event_types: None,
+            balance_ids: (!self.all).then(|| self.balance_ids.clone()),
+        };
+        if !self.all && self.balance_ids.is_empty() {
+            self.events = None;
+        } else if self.events.is_none() {
+            self.events = Some(self.ioc.balance_event_stream.subscribe());
+        }
+        let state = SubscriptionState {
+            all: self.all,
+            balance_ids: self.balance_ids.iter().copied().collect(),
+        };
+        self.send(ServerMessage::Subscription(state)).await
+    }
+
+    async fn on_event(&mut self, event: Result<Arc<BalanceEventData>, RecvError>) -> bool {
+        match event {
+            Ok(event) if self.filter.matches(&event) => {
+                self.send(ServerMessage::Event(&event)).await
+            }
+            Ok(_) => true,
+            Err(RecvError::Lagged(missed)) => self.send(ServerMessage::Lagged { missed }).await,
+            Err(RecvError::Closed) => {
+                self.events = None;
+                self.send_error(None, "Event stream stopped").await
+            }
+        }
+    }
+
+    async fn send(&mut self, message: ServerMessage<'_>) -> bool {
+        self.session.text(message.to_json()).await.is_ok()
+    }
+
+    async fn send_error(&mut self, correlation_id: Option<&str>, message: &str) -> bool {
+        self.send(ServerMessage::Error(ErrorMessage {
+            correlation_id,
+            message: message.to_string(),
+        }))
+        .await
+    }
+}
+
+async fn next_event(
+    events: &mut Option<broadcast::Receiver<Arc<BalanceEventData>>>,
+) -> Result<Arc<BalanceEventData>, RecvError> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Upgrades to the protocol described in the readme; the connection lives on the worker that
+/// accepted it.
+#[utoipa::path(
+    tag = "balances",
+    responses(
+        (status = 101, description = "Upgraded to a WebSocket carrying balance commands and events, see the readme"),
+        (status = 400, description = "Not a WebSocket upgrade request"),
+    )
+)]
+#[get("/ws")]
+async fn balance_ws(
+    ioc: web::Data<AppState>,
+    request: HttpRequest,
+    body: web::Payload,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (response, session, messages) = actix_ws::handle(&request, body)?;
+    rt::spawn(BalanceWsConnection::new(ioc.into_inner(), session).run(messages));
+    Ok(response)
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(balance_ws);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::{App, HttpServer};
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::{Value, json};
+    use tempfile::TempDir;
+    use tokio::{net::TcpStream, time};
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite};
+
+    use super::*;
+    use crate::infrastructure::balance::balance_config::open_db;
+
+    struct Client {
+        socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+        // the store lives as long as the connection
+        _dir: TempDir,
+    }
+
+    impl Client {
+        async fn send(&mut self, message: Value) {
+            self.send_text(&message.to_string()).await;
+        }
+
+        async fn send_text(&mut self, text: &str) {
+            self.socket
+                .send(tungstenite::Message::text(text))
+                .await
+                .unwrap();
+        }
+
+        async fn receive(&mut self) -> Value {
+            loop {
+                let message = time::timeout(Duration::from_secs(5), self.socket.next())
+                    .await
+                    .expect("no message within 5s")
+                    .unwrap()
+                    .unwrap();
+                if let tungstenite::Message::Text(text) = message {
+                    return serde_json::from_str(text.as_str()).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Serves `/ws` on a free port, with the event stream running, and connects to it.
+    async fn connect() -> Client {
+        let dir = tempfile::tempdir().unwrap();
+        let ioc = AppState::with_db(open_db(dir.path()));
+        let balance_event_stream = ioc.balance_event_stream.clone();
+        rt::spawn(async move { balance_event_stream.run().await });
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(ioc.clone()))
+                .configure(config)
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let address = server.addrs()[0];
+        rt::spawn(server.run());
+
+        let (socket, _) = connect_async(format!("ws://{address}/ws")).await.unwrap();
+        Client { socket, _dir: dir }
+    }
+
+    fn command(correlation_id: &str, command_id: Option<&str>, request: Value) -> Value {
+        let mut command = json!({"correlation_id": correlation_id, "request": request});
+        if let Some(command_id) = command_id {
+            command["command_id"] = json!(command_id);
+        }
+        json!({ "command": command })
+    }
+
+    #[actix_web::test]
+    async fn subscription_changes_are_answered_with_the_subscription() {
+        let mut client = connect().await;
+
+        client
+            .send(json!({"subscribe": {"balance_ids": [1, 2]}}))
+            .await;
+        assert_eq!(
+            client.receive().await,
+            json!({"subscription": {"all": false, "balance_ids": [1, 2]}})
+        );
+        client
+            .send(json!({"unsubscribe": {"balance_ids": [2]}}))
+            .await;
+        assert_eq!(
+            client.receive().await,
+            json!({"subscription": {"all": false, "balance_ids": [1]}})
+        );
+        client.send(json!({"subscribe": {}})).await;
+        assert_eq!(
+            client.receive().await,
+            json!({"subscription": {"all": true, "balance_ids": [1]}})
+        );
+        client.send(json!({"unsubscribe": {}})).await;
+        assert_eq!(
+            client.receive().await,
+            json!({"subscription": {"all": false, "balance_ids": []}})
+        );
+    }
+
+    #[actix_web::test]
+    async fn command_results_carry_the_receipt_or_the_rejection() {
+        let mut client = connect().await;
+
+        client
+            .send(command("c-1", None, json!({"create": {"id": 1}})))
+            .await;
+        assert_eq!(
+            client.receive().await,
+            json!({"command_result": {
+                "correlation_id": "c-1",
+                "status": "applied",
+                "receipt": {"event_id": 1, "balances": [{"id": 1, "amount": 0}]},
+                "replayed": false,
+            }})
+        );
+
+        let create_again = command("c-2", Some("create-1"), json!({"create": {"id": 1}}));
+        client.send(create_again.clone()).await;
+        assert_eq!(
+            client.receive().await,
+            json!({"command_result": {
+                "correlation_id": "c-2",
+                "command_id": "create-1",
+                "status": "rejected",
+                "message": "Balance with id 1 already exists",
+                "replayed": false,
+            }})
+        );
+        client.send(create_again).await;
+        assert_eq!(
+            client.receive().await["command_result"]["replayed"],
+            json!(true)
+        );
+
+        client
+            .send(command("c-3", None, json!({"create": {"id": 2}})))
+            .await;
+        client.receive().await;
+        client
+            .send(command(
+                "c-4",
+                None,
+                json!({"deposit": {"id": 1, "amount": 100}}),
+            ))
+            .await;
+        client.receive().await;
+        client
+            .send(command(
+                "c-5",
+                None,
+                json!({"transfer": {"from_id": 1, "to_id": 2, "amount": 40}}),
+            ))
+            .await;
+        assert_eq!(
+            client.receive().await,
+            json!({"command_result": {
+                "correlation_id": "c-5",
+                "status": "applied",
+                "receipt": {
+                    "event_id": 4,
+                    "balances": [{"id": 1, "amount": 60}, {"id": 2, "amount": 40}],
+                },
+                "replayed": false,
+            }})
+        );
+    }
+
+    #[actix_web::test]
+    async fn invalid_message_is_answered_with_an_error() {
+        let mut client = connect().await;
+
+        client.send_text("not json").await;
+        let error = client.receive().await;
+        let message = error["error"]["message"].as_str().unwrap();
+        assert!(message.starts_with("Invalid message: "), "{message}");
+        assert!(error["error"].get("correlation_id").is_none());
+
+        client.send(json!({"close_balance": {"id": 1}})).await;
+        assert!(client.receive().await.get("error").is_some());
+
+        // the connection stays usable
+        client.send(json!({"subscribe": {}})).await;
+        assert_eq!(
+            client.receive().await,
+            json!({"subscription": {"all": true, "balance_ids": []}})
+        );
+    }
+
+    #[actix_web::test]
+    async fn committed_events_of_the_subscribed_balances_are_pushed() {
+        let mut client = connect().await;
+        client
+            .send(json!({"subscribe": {"balance_ids": [1]}}))
+            .await;
+        client.receive().await;
+
+        client
+            .send(command("c-1", None, json!({"create": {"id": 1}})))
+            .await;
+        client
+            .send(command("c-2", None, json!({"create": {"id": 2}})))
+            .await;
+        client
+            .send(command(
+                "c-3",
+                None,
+                json!({"deposit": {"id": 1, "amount": 100}}),
+            ))
+            .await;
+
+        // results and events are written by different tasks, only their own orders hold
+        let mut correlation_ids = Vec::new();
+        let mut events = Vec::new();
+        while correlation_ids.len() < 3 || events.len() < 2 {
+            let message = client.receive().await;
+            if let Some(result) = message.get("command_result") {
+                correlation_ids.push(result["correlation_id"].clone());
+            } else {
+                events.push(message["event"].clone());
+            }
+        }
+
+        assert_eq!(
+            correlation_ids,
+            vec![json!("c-1"), json!("c-2"), json!("c-3")]
+        );
+        assert_eq!(events[0]["id"], json!(1));
+        assert_eq!(events[0]["event_type"], json!("BalanceCreated"));
+        assert_eq!(events[1]["id"], json!(3));
+        assert_eq!(events[1]["event_type"], json!("BalanceDeposited"));
+        assert_eq!(events[1]["data"], json!({"id": 1, "amount": 100}));
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::balance::api::{
+        balance_event_api::BalanceEventData,
+        ledger_command_api::{CommandId, CommandResult},
+    },
+    core::domain::balance::BalanceId,
+    transport::common_request::BalanceCommandRequest,
+};
+
+/// Text frame sent by a client, externally tagged like `BalanceCommandRequest`.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientMessage {
+    Command(CommandMessage),
+    Subscribe(SubscriptionRequest),
+    Unsubscribe(SubscriptionRequest),
+}
+
+#[derive(Deserialize)]
+pub struct CommandMessage {
+    /// Echoed in the result, so a client can pipeline commands and match their results.
+    pub correlation_id: String,
+    /// Makes the command idempotent, as on the command topic; sent again, it is answered with
+    /// its stored result.
+    pub command_id: Option<CommandId>,
+    pub request: BalanceCommandRequest,
+}
+
+/// Without `balance_ids`, the whole event stream.
+#[derive(Deserialize)]
+pub struct SubscriptionRequest {
+    pub balance_ids: Option<Vec<BalanceId>>,
+}
+
+/// Text frame sent by the server.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerMessage<'a> {
+    CommandResult(CommandResultMessage<'a>),
+    /// Answer to a subscribe or unsubscribe, with what the connection now receives.
+    Subscription(SubscriptionState),
+    Event(&'a BalanceEventData),
+    /// Events dropped because the connection did not keep up with the stream.
+    Lagged {
+        missed: u64,
+    },
+    Error(ErrorMessage<'a>),
+}
+
+#[derive(Serialize)]
+pub struct CommandResultMessage<'a> {
+    pub correlation_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_id: Option<&'a str>,
+    #[serde(flatten)]
+    pub result: &'a CommandResult,
+    pub replayed: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SubscriptionState {
+    /// Every event, whatever `balance_ids` holds.
+    pub all: bool,
+    pub balance_ids: Vec<BalanceId>,
+}
+
+#[derive(Serialize)]
+pub struct ErrorMessage<'a> {
+    /// Set when the error answers a command, which then has no result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<&'a str>,
+    pub message: String,
+}
+
+impl ServerMessage<'_> {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
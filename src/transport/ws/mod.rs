@@ -1 +1,2 @@
-
+pub mod balance_ws;
+pub mod balance_ws_message;